@@ -10,6 +10,24 @@ interface AuthenticationStackProps extends cdk.StackProps {
   config: EnvironmentConfig;
 }
 
+/**
+ * OTP hashing keys for create- and verify-auth-challenge. There is no usable default: without
+ * OTP_HASH_KEY both functions fail at cold start, so synth fails instead of deploying a stack
+ * nobody can sign in to.
+ */
+function otpHashKeyEnvironment(): Record<string, string> {
+  const current = process.env.OTP_HASH_KEY;
+  if (!current) {
+    throw new Error('OTP_HASH_KEY environment variable is required (<version>:<secret>)');
+  }
+  if (!/^\d+:.{32,}$/.test(current)) {
+    throw new Error('OTP_HASH_KEY must be <version>:<secret>, with a secret of at least 32 characters');
+  }
+
+  const previous = process.env.OTP_HASH_PREVIOUS_KEY;
+  return previous ? { OTP_HASH_KEY: current, OTP_HASH_PREVIOUS_KEY: previous } : { OTP_HASH_KEY: current };
+}
+
 /**
  * Authentication Stack for Appre Platform
  * 
@@ -38,6 +56,12 @@ export class AuthenticationStack extends cdk.Stack {
   public readonly rateLimitTable!: dynamodb.Table;
   public readonly usersTable!: dynamodb.Table;
   public readonly sessionTable!: dynamodb.Table;
+  public readonly signupPolicyTable!: dynamodb.Table;
+  private logoutFunction!: lambda.Function;
+  private completeProfileFunction!: lambda.Function;
+  private adminReviewFunction!: lambda.Function;
+  private deleteUserFunction!: lambda.Function;
+  private exportUserDataFunction!: lambda.Function;
 
   private readonly resourceNames: ResourceNames;
   private readonly tagBuilder: TagBuilder;
@@ -66,6 +90,12 @@ export class AuthenticationStack extends cdk.Stack {
     // Cognito User Pool with Custom Authentication
     this.createCognitoUserPool(lambdaFunctions);
 
+    // Session Lambda Functions (need the user pool ID, so created after the pool)
+    this.createSessionLambdaFunctions(lambdaFunctions.lambdaRole);
+
+    // Admin Lambda Functions
+    this.createAdminLambdaFunctions(lambdaFunctions.lambdaRole);
+
     // Configure passwordless authentication
     this.configurePasswordlessAuth();
 
@@ -165,6 +195,23 @@ export class AuthenticationStack extends cdk.Stack {
     Object.entries(sessionTags).forEach(([key, value]) => {
       cdk.Tags.of(this.sessionTable).add(key, value);
     });
+
+    // Sign-up Policy Table (runtime allow/deny entries for the pre-signup trigger)
+    (this as any).signupPolicyTable = new dynamodb.Table(this, 'SignupPolicyTable', {
+      tableName: this.resourceNames.dynamoTable('signup-policy'),
+      partitionKey: { name: 'list', type: dynamodb.AttributeType.STRING },
+      sortKey: { name: 'entry', type: dynamodb.AttributeType.STRING },
+      billingMode: dynamodb.BillingMode.PAY_PER_REQUEST,
+      encryption: dynamodb.TableEncryption.AWS_MANAGED,
+      pointInTimeRecovery: isProd,
+      removalPolicy: isProd ? cdk.RemovalPolicy.RETAIN : cdk.RemovalPolicy.DESTROY,
+    });
+
+    // Apply tags to Sign-up Policy table
+    const signupPolicyTags = this.tagBuilder.getDynamoTags('signup-policy');
+    Object.entries(signupPolicyTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.signupPolicyTable).add(key, value);
+    });
   }
 
   private createLambdaFunctions() {
@@ -201,7 +248,9 @@ export class AuthenticationStack extends cdk.Stack {
         this.rateLimitTable.tableArn,
         this.usersTable.tableArn,
         this.sessionTable.tableArn,
+        this.signupPolicyTable.tableArn,
         `${this.usersTable.tableArn}/index/*`,
+        `${this.sessionTable.tableArn}/index/*`,
       ],
       conditions: {
         StringEquals: {
@@ -235,6 +284,9 @@ export class AuthenticationStack extends cdk.Stack {
         'cognito-idp:AdminConfirmSignUp',
         'cognito-idp:AdminGetUser',
         'cognito-idp:AdminUpdateUserAttributes',
+        'cognito-idp:AdminUserGlobalSignOut',
+        'cognito-idp:AdminDeleteUser',
+        'cognito-idp:ListUsers',
       ],
       resources: [
         `arn:aws:cognito-idp:${this.region}:${this.account}:userpool/*`
@@ -246,6 +298,20 @@ export class AuthenticationStack extends cdk.Stack {
       },
     }));
 
+    // Trigger events captured to S3 (EVENT_CAPTURE=s3://<bucket>/<prefix>) need PutObject under
+    // that prefix; the bucket itself is managed outside this stack
+    const eventCapture = process.env.EVENT_CAPTURE || 'off';
+    if (eventCapture.startsWith('s3://')) {
+      const location = eventCapture.slice('s3://'.length).replace(/\/+$/, '');
+      lambdaRole.addToPolicy(new iam.PolicyStatement({
+        effect: iam.Effect.ALLOW,
+        actions: ['s3:PutObject'],
+        resources: [`arn:aws:s3:::${location}/*`],
+      }));
+    }
+
+    const otpHashKeys = otpHashKeyEnvironment();
+
     // Create Auth Challenge Lambda
     const deploymentTime = Date.now().toString();
     const createAuthChallenge = new lambda.Function(this, 'CreateAuthChallenge', {
@@ -265,12 +331,34 @@ export class AuthenticationStack extends cdk.Stack {
         USERS_TABLE_NAME: this.usersTable.tableName,
         SESSION_TABLE_NAME: this.sessionTable.tableName,
         FROM_EMAIL: process.env.FROM_EMAIL || 'noreply@appreciata.com',
+        // OTP attempt policy (lockout is honoured when issuing new OTPs)
+        OTP_MAX_ATTEMPTS: process.env.OTP_MAX_ATTEMPTS || '3',
+        OTP_LOCKOUT_MINUTES: process.env.OTP_LOCKOUT_MINUTES || '15',
+        // Whether bob+tag@example.com is bob@example.com (strip) or its own user (preserve)
+        EMAIL_SUBADDRESS_POLICY: process.env.EMAIL_SUBADDRESS_POLICY || 'preserve',
+        // Rate limit windows per action as <max>/<duration>[,...] (units s, m, h, d)
+        RATE_LIMIT_OTP_SEND: process.env.RATE_LIMIT_OTP_SEND || '3/15m,10/1d',
+        RATE_LIMIT_OTP_RESEND: process.env.RATE_LIMIT_OTP_RESEND || '1/1m',
+        RATE_LIMIT_OTP_SEND_IP: process.env.RATE_LIMIT_OTP_SEND_IP || '20/1h,100/1d',
+        RATE_LIMIT_OTP_SEND_DOMAIN: process.env.RATE_LIMIT_OTP_SEND_DOMAIN || '200/1h',
+        // OTP hashing secret as <version>:<secret>; set the previous key while rotating
+        ...otpHashKeys,
+        // Frontend page that receives magic sign-in links (token appended as ?token=...)
+        MAGIC_LINK_BASE_URL: process.env.MAGIC_LINK_BASE_URL || '',
+        // How challenge emails are sent: direct (SES only), queued (SQS only), or fallback
+        // (SES, queueing the email when SES is throttled or unavailable)
+        OTP_DELIVERY_STRATEGY: process.env.OTP_DELIVERY_STRATEGY || 'fallback',
+        EMAIL_QUEUE_URL: cdk.Fn.importValue(`${this.tagBuilder.config.appName}-EmailQueueUrl-${this.tagBuilder.config.environment}`),
         // SES Template names
         OTP_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-otp`,
+        MAGIC_LINK_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-magic-link`,
         WELCOME_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-welcome`,
         COMPLETE_REGISTRATION_USER_INFO_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-complete-registration-user-info`,
         COMPLETE_REGISTRATION_STRIPE_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-complete-registration-stripe`,
         NEWSLETTER_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-newsletter`,
+        // Record redacted trigger events as replay fixtures: off, log (see capture-events.sh),
+        // or s3://<bucket>/<prefix>
+        EVENT_CAPTURE: eventCapture,
         DEPLOYMENT_TIMESTAMP: deploymentTime,
         LAMBDA_VERSION: 'v2.0.0', // Increment this to force redeployment
       },
@@ -296,14 +384,29 @@ export class AuthenticationStack extends cdk.Stack {
         APP_NAME: this.tagBuilder.config.appName,
         ENVIRONMENT: this.tagBuilder.config.environment,
         OTP_TABLE_NAME: this.otpTable.tableName,
+        RATE_LIMIT_TABLE_NAME: this.rateLimitTable.tableName,
         USERS_TABLE_NAME: this.usersTable.tableName,
         SESSION_TABLE_NAME: this.sessionTable.tableName,
+        // OTP attempt policy
+        OTP_MAX_ATTEMPTS: process.env.OTP_MAX_ATTEMPTS || '3',
+        OTP_LOCKOUT_MINUTES: process.env.OTP_LOCKOUT_MINUTES || '15',
+        // Must match create-auth-challenge so the OTP is found under the same email
+        EMAIL_SUBADDRESS_POLICY: process.env.EMAIL_SUBADDRESS_POLICY || 'preserve',
+        // The welcome email is queued on a user's first verification
+        EMAIL_QUEUE_URL: cdk.Fn.importValue(`${this.tagBuilder.config.appName}-EmailQueueUrl-${this.tagBuilder.config.environment}`),
+        DASHBOARD_URL: process.env.DASHBOARD_URL || 'https://appreciata.com/dashboard',
+        // Rate limit windows for answer submissions as <max>/<duration>[,...]
+        RATE_LIMIT_OTP_VERIFY: process.env.RATE_LIMIT_OTP_VERIFY || '10/15m',
+        // OTP hashing secret as <version>:<secret>; set the previous key while rotating
+        ...otpHashKeys,
         // SES Template names
         OTP_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-otp`,
+        MAGIC_LINK_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-magic-link`,
         WELCOME_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-welcome`,
         COMPLETE_REGISTRATION_USER_INFO_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-complete-registration-user-info`,
         COMPLETE_REGISTRATION_STRIPE_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-complete-registration-stripe`,
         NEWSLETTER_TEMPLATE_NAME: `${this.tagBuilder.config.appName}-${this.tagBuilder.config.environment}-newsletter`,
+        EVENT_CAPTURE: eventCapture,
         DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
       },
       tracing: lambda.Tracing.ACTIVE,
@@ -327,6 +430,9 @@ export class AuthenticationStack extends cdk.Stack {
       environment: {
         APP_NAME: this.tagBuilder.config.appName,
         ENVIRONMENT: this.tagBuilder.config.environment,
+        // Answers allowed per sign-in session before it fails; 1 fails on the first wrong answer
+        CHALLENGE_MAX_ATTEMPTS: process.env.CHALLENGE_MAX_ATTEMPTS || '3',
+        EVENT_CAPTURE: eventCapture,
         DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
       },
       tracing: lambda.Tracing.ACTIVE,
@@ -350,6 +456,9 @@ export class AuthenticationStack extends cdk.Stack {
       environment: {
         APP_NAME: this.tagBuilder.config.appName,
         ENVIRONMENT: this.tagBuilder.config.environment,
+        SIGNUP_POLICY_TABLE_NAME: this.signupPolicyTable.tableName,
+        EMAIL_SUBADDRESS_POLICY: process.env.EMAIL_SUBADDRESS_POLICY || 'preserve',
+        EVENT_CAPTURE: eventCapture,
         DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
       },
       tracing: lambda.Tracing.ACTIVE,
@@ -361,14 +470,206 @@ export class AuthenticationStack extends cdk.Stack {
       cdk.Tags.of(preSignup).add(key, value);
     });
 
+    // Pre-Token-Generation Lambda (adds appre:status, appre:user_id and appre:role claims)
+    const preTokenGeneration = new lambda.Function(this, 'PreTokenGeneration', {
+      functionName: this.resourceNames.lambda('pre-token-generation'),
+      runtime: new lambda.Runtime('provided.al2023'),
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/pre-token-generation/'),
+      role: lambdaRole,
+      timeout: cdk.Duration.seconds(30),
+      memorySize: 128,
+      environment: {
+        APP_NAME: this.tagBuilder.config.appName,
+        ENVIRONMENT: this.tagBuilder.config.environment,
+        OTP_TABLE_NAME: this.otpTable.tableName,
+        USERS_TABLE_NAME: this.usersTable.tableName,
+        // Must match create-auth-challenge so the email fallback finds the same row
+        EMAIL_SUBADDRESS_POLICY: process.env.EMAIL_SUBADDRESS_POLICY || 'preserve',
+        EVENT_CAPTURE: eventCapture,
+        DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Pre-Token-Generation Lambda
+    const preTokenGenerationTags = this.tagBuilder.getLambdaTags('auth-pre-token-generation');
+    Object.entries(preTokenGenerationTags).forEach(([key, value]) => {
+      cdk.Tags.of(preTokenGeneration).add(key, value);
+    });
+
     return {
       createAuthChallenge,
       verifyAuthChallenge,
       defineAuthChallenge,
       preSignup,
+      preTokenGeneration,
+      lambdaRole,
     };
   }
 
+  private createSessionLambdaFunctions(lambdaRole: iam.Role) {
+    // Logout Lambda (invoked directly by the webapp)
+    this.logoutFunction = new lambda.Function(this, 'Logout', {
+      functionName: this.resourceNames.lambda('logout'),
+      runtime: new lambda.Runtime('provided.al2023'),
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/logout/'),
+      role: lambdaRole,
+      timeout: cdk.Duration.seconds(30),
+      memorySize: 128,
+      environment: {
+        APP_NAME: this.tagBuilder.config.appName,
+        ENVIRONMENT: this.tagBuilder.config.environment,
+        SESSION_TABLE_NAME: this.sessionTable.tableName,
+        SESSION_DURATION_MINUTES: process.env.SESSION_DURATION_MINUTES || '20',
+        USER_POOL_ID: this.userPool.userPoolId,
+        DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Logout Lambda
+    const logoutTags = this.tagBuilder.getLambdaTags('auth-logout');
+    Object.entries(logoutTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.logoutFunction).add(key, value);
+    });
+
+    // Complete Profile Lambda (invoked directly by the webapp)
+    this.completeProfileFunction = new lambda.Function(this, 'CompleteProfile', {
+      functionName: this.resourceNames.lambda('complete-profile'),
+      runtime: new lambda.Runtime('provided.al2023'),
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/complete-profile/'),
+      role: lambdaRole,
+      timeout: cdk.Duration.seconds(30),
+      memorySize: 128,
+      environment: {
+        APP_NAME: this.tagBuilder.config.appName,
+        ENVIRONMENT: this.tagBuilder.config.environment,
+        SESSION_TABLE_NAME: this.sessionTable.tableName,
+        SESSION_DURATION_MINUTES: process.env.SESSION_DURATION_MINUTES || '20',
+        OTP_TABLE_NAME: this.otpTable.tableName,
+        USERS_TABLE_NAME: this.usersTable.tableName,
+        DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Complete Profile Lambda
+    const completeProfileTags = this.tagBuilder.getLambdaTags('auth-complete-profile');
+    Object.entries(completeProfileTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.completeProfileFunction).add(key, value);
+    });
+  }
+
+  private createAdminLambdaFunctions(lambdaRole: iam.Role) {
+    const appName = this.tagBuilder.config.appName;
+    const environment = this.tagBuilder.config.environment;
+
+    // Review decisions and welcome emails go through the notification domain's queue
+    lambdaRole.addToPolicy(new iam.PolicyStatement({
+      effect: iam.Effect.ALLOW,
+      actions: ['sqs:SendMessage'],
+      resources: [
+        `arn:aws:sqs:${this.region}:${this.account}:${this.resourceNames.sqsQueue('email-queue')}`,
+      ],
+    }));
+
+    // Admin Review Lambda (invoked directly by the admin dashboard)
+    this.adminReviewFunction = new lambda.Function(this, 'AdminReview', {
+      functionName: this.resourceNames.lambda('admin-review'),
+      runtime: new lambda.Runtime('provided.al2023'),
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/admin-review/'),
+      role: lambdaRole,
+      timeout: cdk.Duration.seconds(30),
+      memorySize: 128,
+      environment: {
+        APP_NAME: appName,
+        ENVIRONMENT: environment,
+        OTP_TABLE_NAME: this.otpTable.tableName,
+        USERS_TABLE_NAME: this.usersTable.tableName,
+        EMAIL_QUEUE_URL: cdk.Fn.importValue(`${appName}-EmailQueueUrl-${environment}`),
+        DASHBOARD_URL: process.env.DASHBOARD_URL || 'https://appreciata.com/dashboard',
+        PROFILE_URL: process.env.PROFILE_URL || 'https://appreciata.com/profile',
+        DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Admin Review Lambda
+    const adminReviewTags = this.tagBuilder.getLambdaTags('auth-admin-review');
+    Object.entries(adminReviewTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.adminReviewFunction).add(key, value);
+    });
+
+    // Delete User Lambda (invoked directly by operators, see scripts/aws/delete-user.sh)
+    this.deleteUserFunction = new lambda.Function(this, 'DeleteUser', {
+      functionName: this.resourceNames.lambda('delete-user'),
+      runtime: new lambda.Runtime('provided.al2023'),
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/delete-user/'),
+      role: lambdaRole,
+      timeout: cdk.Duration.seconds(60),
+      memorySize: 128,
+      environment: {
+        APP_NAME: appName,
+        ENVIRONMENT: environment,
+        OTP_TABLE_NAME: this.otpTable.tableName,
+        USERS_TABLE_NAME: this.usersTable.tableName,
+        RATE_LIMIT_TABLE_NAME: this.rateLimitTable.tableName,
+        SESSION_TABLE_NAME: this.sessionTable.tableName,
+        USER_POOL_ID: this.userPool.userPoolId,
+        // Emails and counters are found the same way the auth challenges key them
+        EMAIL_SUBADDRESS_POLICY: process.env.EMAIL_SUBADDRESS_POLICY || 'preserve',
+        RATE_LIMIT_OTP_SEND: process.env.RATE_LIMIT_OTP_SEND || '3/15m,10/1d',
+        RATE_LIMIT_OTP_VERIFY: process.env.RATE_LIMIT_OTP_VERIFY || '10/15m',
+        RATE_LIMIT_OTP_RESEND: process.env.RATE_LIMIT_OTP_RESEND || '1/1m',
+        DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Delete User Lambda
+    const deleteUserTags = this.tagBuilder.getLambdaTags('auth-delete-user');
+    Object.entries(deleteUserTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.deleteUserFunction).add(key, value);
+    });
+
+    // Export User Data Lambda (invoked directly by operators for subject access requests)
+    this.exportUserDataFunction = new lambda.Function(this, 'ExportUserData', {
+      functionName: this.resourceNames.lambda('export-user-data'),
+      runtime: new lambda.Runtime('provided.al2023'),
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/export-user-data/'),
+      role: lambdaRole,
+      timeout: cdk.Duration.seconds(30),
+      memorySize: 128,
+      environment: {
+        APP_NAME: appName,
+        ENVIRONMENT: environment,
+        OTP_TABLE_NAME: this.otpTable.tableName,
+        USERS_TABLE_NAME: this.usersTable.tableName,
+        RATE_LIMIT_TABLE_NAME: this.rateLimitTable.tableName,
+        SESSION_TABLE_NAME: this.sessionTable.tableName,
+        // Emails and counters are found the same way the auth challenges key them
+        EMAIL_SUBADDRESS_POLICY: process.env.EMAIL_SUBADDRESS_POLICY || 'preserve',
+        RATE_LIMIT_OTP_SEND: process.env.RATE_LIMIT_OTP_SEND || '3/15m,10/1d',
+        RATE_LIMIT_OTP_VERIFY: process.env.RATE_LIMIT_OTP_VERIFY || '10/15m',
+        RATE_LIMIT_OTP_RESEND: process.env.RATE_LIMIT_OTP_RESEND || '1/1m',
+        DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Export User Data Lambda
+    const exportUserDataTags = this.tagBuilder.getLambdaTags('auth-export-user-data');
+    Object.entries(exportUserDataTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.exportUserDataFunction).add(key, value);
+    });
+  }
+
   private createCognitoUserPool(lambdaFunctions: any) {
 
     // User Pool
@@ -443,6 +744,9 @@ export class AuthenticationStack extends cdk.Stack {
         preSignUp: lambdaFunctions.preSignup,
       },
 
+      // The V2 pre-token-generation trigger (access token claims) needs the Essentials plan
+      featurePlan: cognito.FeaturePlan.ESSENTIALS,
+
       // Deletion protection
       deletionProtection: this.tagBuilder.config.environment === 'prod',
 
@@ -520,6 +824,26 @@ export class AuthenticationStack extends cdk.Stack {
       principal: new iam.ServicePrincipal('cognito-idp.amazonaws.com'),
       sourceArn: this.userPool.userPoolArn,
     });
+
+    // Pre-token-generation is attached separately: lambdaTriggers only supports the V1 event,
+    // which can't add claims to the access token
+    this.userPool.addTrigger(
+      cognito.UserPoolOperation.PRE_TOKEN_GENERATION_CONFIG,
+      lambdaFunctions.preTokenGeneration,
+      cognito.LambdaVersion.V2_0,
+    );
+
+    lambdaFunctions.preTokenGeneration.addPermission('CognitoInvokePreTokenGeneration', {
+      principal: new iam.ServicePrincipal('cognito-idp.amazonaws.com'),
+      sourceArn: this.userPool.userPoolArn,
+    });
+
+    // Members of this group get appre:role = admin in their tokens
+    new cognito.CfnUserPoolGroup(this, 'AdminsGroup', {
+      userPoolId: this.userPool.userPoolId,
+      groupName: 'admins',
+      description: 'Platform admins (appre:role = admin)',
+    });
   }
 
   private configurePasswordlessAuth() {
@@ -576,5 +900,41 @@ export class AuthenticationStack extends cdk.Stack {
       description: 'User Sessions DynamoDB Table Name',
       exportName: `${this.tagBuilder.config.appName}-SessionTable-${environment}`,
     });
+
+    new cdk.CfnOutput(this, 'SignupPolicyTableName', {
+      value: this.signupPolicyTable.tableName,
+      description: 'Sign-up Policy DynamoDB Table Name (runtime allow/deny lists)',
+      exportName: `${this.tagBuilder.config.appName}-SignupPolicyTable-${environment}`,
+    });
+
+    new cdk.CfnOutput(this, 'LogoutFunctionName', {
+      value: this.logoutFunction.functionName,
+      description: 'Logout Lambda Function Name (invoked by the webapp)',
+      exportName: `${this.tagBuilder.config.appName}-LogoutFunction-${environment}`,
+    });
+
+    new cdk.CfnOutput(this, 'CompleteProfileFunctionName', {
+      value: this.completeProfileFunction.functionName,
+      description: 'Complete Profile Lambda Function Name (invoked by the webapp)',
+      exportName: `${this.tagBuilder.config.appName}-CompleteProfileFunction-${environment}`,
+    });
+
+    new cdk.CfnOutput(this, 'AdminReviewFunctionName', {
+      value: this.adminReviewFunction.functionName,
+      description: 'Admin Review Lambda Function Name (invoked by the admin dashboard)',
+      exportName: `${this.tagBuilder.config.appName}-AdminReviewFunction-${environment}`,
+    });
+
+    new cdk.CfnOutput(this, 'DeleteUserFunctionName', {
+      value: this.deleteUserFunction.functionName,
+      description: 'Delete User Lambda Function Name (invoked by operators)',
+      exportName: `${this.tagBuilder.config.appName}-DeleteUserFunction-${environment}`,
+    });
+
+    new cdk.CfnOutput(this, 'ExportUserDataFunctionName', {
+      value: this.exportUserDataFunction.functionName,
+      description: 'Export User Data Lambda Function Name (invoked by operators)',
+      exportName: `${this.tagBuilder.config.appName}-ExportUserDataFunction-${environment}`,
+    });
   }
 }
\ No newline at end of file
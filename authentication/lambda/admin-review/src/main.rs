@@ -0,0 +1,205 @@
+use aws_config::BehaviorVersion;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use auth_shared::{AuthResult, ReviewService, UserPage, UserProfile};
+
+/// Admin review request, invoked directly by the admin dashboard
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum AdminReviewRequest {
+    /// Page through creators awaiting review, oldest first
+    ListPending {
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        cursor: Option<String>,
+    },
+    Approve {
+        user_id: String,
+        reviewer_id: String,
+    },
+    Reject {
+        user_id: String,
+        reviewer_id: String,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum AdminReviewResponse {
+    Page(UserPage),
+    User(UserProfile),
+}
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    reviews: ReviewService,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let reviews = ReviewService::from_env(
+            aws_sdk_dynamodb::Client::new(&config),
+            aws_sdk_sqs::Client::new(&config),
+        )?;
+        Ok(Self { reviews })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<AdminReviewRequest>,
+) -> Result<AdminReviewResponse, Error> {
+    let request = event.payload;
+
+    info!("Admin review request: {:?}", request);
+
+    match handle(request, &context.reviews).await {
+        Ok(response) => Ok(response),
+        Err(e) => {
+            error!("Admin review failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+async fn handle(
+    request: AdminReviewRequest,
+    reviews: &ReviewService,
+) -> AuthResult<AdminReviewResponse> {
+    match request {
+        AdminReviewRequest::ListPending { limit, cursor } => reviews
+            .list_pending(limit, cursor.as_deref())
+            .await
+            .map(AdminReviewResponse::Page),
+        AdminReviewRequest::Approve {
+            user_id,
+            reviewer_id,
+        } => reviews
+            .approve(&user_id, &reviewer_id)
+            .await
+            .map(AdminReviewResponse::User),
+        AdminReviewRequest::Reject {
+            user_id,
+            reviewer_id,
+            reason,
+        } => reviews
+            .reject(&user_id, &reviewer_id, &reason)
+            .await
+            .map(AdminReviewResponse::User),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{EmailAddress, InMemoryEmailQueue, InMemoryUserRepository, UserStatus};
+    use chrono::Utc;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn setup() -> (Arc<InMemoryEmailQueue>, ReviewService) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        for user_id in ["user-1", "user-2"] {
+            users.insert_user(UserProfile {
+                user_id: user_id.to_string(),
+                email: EmailAddress::parse(&format!("{}@example.com", user_id)).unwrap(),
+                status: UserStatus::AwaitingReview,
+                full_name: None,
+                content_description: None,
+                content_link: None,
+                stripe_account_id: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                reviewed_by: None,
+                reviewed_at: None,
+                rejection_reason: None,
+                welcome_pending: false,
+                review_email_pending: false,
+            });
+        }
+        let emails = Arc::new(InMemoryEmailQueue::new());
+        let reviews = ReviewService::new(
+            users,
+            emails.clone(),
+            "https://app.example.com/dashboard".to_string(),
+            "https://app.example.com/profile".to_string(),
+        );
+        (emails, reviews)
+    }
+
+    async fn invoke(reviews: &ReviewService, request: serde_json::Value) -> serde_json::Value {
+        let request: AdminReviewRequest = serde_json::from_value(request).unwrap();
+        let response = handle(request, reviews).await.unwrap();
+        serde_json::to_value(response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_pending() {
+        let (_, reviews) = setup();
+
+        let page = invoke(&reviews, json!({ "action": "list_pending", "limit": 1 })).await;
+        assert_eq!(page["users"].as_array().unwrap().len(), 1);
+        let cursor = page["next_cursor"].as_str().unwrap().to_string();
+
+        let page = invoke(
+            &reviews,
+            json!({ "action": "list_pending", "limit": 1, "cursor": cursor }),
+        )
+        .await;
+        assert_eq!(page["users"].as_array().unwrap().len(), 1);
+        assert!(page["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_approve_and_reject() {
+        let (emails, reviews) = setup();
+
+        let user = invoke(
+            &reviews,
+            json!({ "action": "approve", "user_id": "user-1", "reviewer_id": "admin-1" }),
+        )
+        .await;
+        assert_eq!(user["status"], "ACTIVE");
+        assert_eq!(user["reviewed_by"], "admin-1");
+
+        let user = invoke(
+            &reviews,
+            json!({
+                "action": "reject",
+                "user_id": "user-2",
+                "reviewer_id": "admin-1",
+                "reason": "Content link is broken"
+            }),
+        )
+        .await;
+        assert_eq!(user["status"], "REJECTED");
+        assert_eq!(user["rejection_reason"], "Content link is broken");
+        assert_eq!(emails.queued().len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_action_is_rejected() {
+        let result = serde_json::from_value::<AdminReviewRequest>(json!({ "action": "delete" }));
+        assert!(result.is_err());
+    }
+}
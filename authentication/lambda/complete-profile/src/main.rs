@@ -0,0 +1,166 @@
+use aws_config::BehaviorVersion;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use auth_shared::{
+    AuthError, AuthResult, ProfileDetails, ProfileService, SessionService, UserProfile,
+};
+
+/// Profile completion request, invoked directly by the webapp with the caller's session
+/// cookie value and the details from the profile form
+#[derive(Debug, Deserialize)]
+struct CompleteProfileRequest {
+    session_id: String,
+    #[serde(flatten)]
+    details: ProfileDetails,
+}
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    sessions: SessionService,
+    profiles: ProfileService,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
+        Ok(Self {
+            sessions: SessionService::from_env(dynamodb_client.clone())?,
+            profiles: ProfileService::from_env(dynamodb_client)?,
+        })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<CompleteProfileRequest>,
+) -> Result<UserProfile, Error> {
+    let request = event.payload;
+
+    info!("Profile completion requested");
+
+    match complete_profile(&request, &context.sessions, &context.profiles).await {
+        Ok(user) => {
+            info!("Profile completed for user: {}", user.user_id);
+            Ok(user)
+        }
+        Err(e) => {
+            error!("Profile completion failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Save the profile for the user the session belongs to
+async fn complete_profile(
+    request: &CompleteProfileRequest,
+    sessions: &SessionService,
+    profiles: &ProfileService,
+) -> AuthResult<UserProfile> {
+    let session = sessions
+        .validate_session(&request.session_id)
+        .await?
+        .ok_or_else(|| AuthError::InvalidSession("Session not found or expired".to_string()))?;
+
+    profiles
+        .complete_profile(&session.user_id, &request.details)
+        .await
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{
+        current_timestamp, EmailAddress, InMemorySessionStore, InMemoryUserRepository, Session,
+        SessionPolicy, UserRepository, UserStatus,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    async fn setup() -> (Arc<InMemoryUserRepository>, SessionService, ProfileService) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let email = EmailAddress::parse("ada@example.com").unwrap();
+        users.create_user(&email, "user-1").await.unwrap();
+        users
+            .transition_status(
+                "user-1",
+                UserStatus::RegistrationEmailNotVerified,
+                UserStatus::RegistrationNeedUserInfo,
+                "test",
+            )
+            .await
+            .unwrap();
+
+        let store = Arc::new(InMemorySessionStore::new());
+        let now = current_timestamp();
+        store.insert_session(Session {
+            session_id: "session-1".to_string(),
+            user_id: "user-1".to_string(),
+            email: "ada@example.com".to_string(),
+            user_status: "REGISTRATION_NEED_USER_INFO".to_string(),
+            given_name: None,
+            family_name: None,
+            created_at: now,
+            last_accessed: now,
+            expires_at: now + 20 * 60,
+            ip_address: None,
+            user_agent: None,
+        });
+
+        let sessions = SessionService::new(store, SessionPolicy::default());
+        (users.clone(), sessions, ProfileService::new(users))
+    }
+
+    fn request(session_id: &str) -> CompleteProfileRequest {
+        serde_json::from_value(json!({
+            "session_id": session_id,
+            "full_name": "Ada Lovelace",
+            "content_description": "Maths videos",
+            "content_link": "example.com/ada"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_complete_profile_for_session_user() {
+        let (users, sessions, profiles) = setup().await;
+
+        let user = complete_profile(&request("session-1"), &sessions, &profiles)
+            .await
+            .unwrap();
+
+        assert_eq!(user.user_id, "user-1");
+        assert_eq!(user.status, UserStatus::RegistrationNeedStripe);
+        assert_eq!(user.content_link.as_deref(), Some("https://example.com/ada"));
+        let stored = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(stored.status, UserStatus::RegistrationNeedStripe);
+    }
+
+    #[tokio::test]
+    async fn test_complete_profile_requires_valid_session() {
+        let (users, sessions, profiles) = setup().await;
+
+        let result = complete_profile(&request("missing"), &sessions, &profiles).await;
+
+        assert!(matches!(result, Err(AuthError::InvalidSession(_))));
+        let stored = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(stored.status, UserStatus::RegistrationNeedUserInfo);
+    }
+}
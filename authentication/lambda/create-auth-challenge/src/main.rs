@@ -1,22 +1,138 @@
 use aws_config::BehaviorVersion;
-use aws_lambda_events::event::cognito::CognitoEventUserPoolsCreateAuthChallenge;
+use aws_lambda_events::event::cognito::{
+    CognitoEventUserPoolsChallengeResult, CognitoEventUserPoolsCreateAuthChallenge,
+};
 use aws_sdk_cognitoidentityprovider::Client as CognitoClient;
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
 use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
 use tracing::{error, info, warn};
 
 use auth_shared::{
-    current_timestamp, generate_challenge_id, generate_otp, hash_otp, is_valid_email, AuthError,
-    AuthResult, DynamoDBService, OTPRecord, RateLimitService, SESService,
+    current_timestamp, generate_challenge_id, generate_otp, sub_addressing_from_env, AuthError,
+    AuthResult, ChallengeMetadata, ChallengeType, DynamoDBService, EmailAddress, EmailDelivery,
+    EmailRequest, EventCapture, OTPRecord, OtpDeliveryService, OtpHasher, OtpStore, RateLimitAction,
+    RateLimitService, RateLimitStore, SubAddressing, UserProfile, UserRepository,
 };
 
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    sub_addressing: SubAddressing,
+    hasher: OtpHasher,
+    rate_limits: Arc<dyn RateLimitStore>,
+    users: Arc<dyn UserRepository>,
+    otp_store: Arc<dyn OtpStore>,
+    otp_delivery: OtpDeliveryService,
+    /// Frontend page that receives magic links; only MAGIC_LINK challenges need it
+    magic_link_base_url: Option<String>,
+    cognito_client: CognitoClient,
+    capture: EventCapture,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        // Initialize AWS clients
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
+        let ses_client = aws_sdk_ses::Client::new(&config);
+        let sqs_client = aws_sdk_sqs::Client::new(&config);
+
+        // Initialize services using naming utilities
+        info!("Initializing RateLimitService...");
+        let rate_limit_service = RateLimitService::from_env(dynamodb_client.clone())
+            .map_err(|e| {
+                error!("Failed to initialize RateLimitService: {}", e);
+                AuthError::InternalError(format!("Failed to initialize RateLimitService: {}", e))
+            })?;
+
+        info!("Initializing DynamoDBService...");
+        let dynamodb_service = Arc::new(DynamoDBService::from_env(dynamodb_client)
+            .map_err(|e| {
+                error!("Failed to initialize DynamoDBService: {}", e);
+                AuthError::InternalError(format!("Failed to initialize DynamoDBService: {}", e))
+            })?);
+
+        info!("Initializing OtpDeliveryService...");
+        let otp_delivery = OtpDeliveryService::from_env(ses_client, sqs_client)
+            .map_err(|e| {
+                error!("Failed to initialize OtpDeliveryService: {}", e);
+                e
+            })?;
+
+        let hasher = OtpHasher::from_env().map_err(|e| {
+            error!("Failed to initialize OtpHasher: {}", e);
+            e
+        })?;
+
+        info!("All services initialized successfully");
+
+        Ok(Self {
+            sub_addressing: sub_addressing_from_env()?,
+            hasher,
+            rate_limits: Arc::new(rate_limit_service),
+            users: dynamodb_service.clone(),
+            otp_store: dynamodb_service,
+            otp_delivery,
+            magic_link_base_url: std::env::var("MAGIC_LINK_BASE_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            cognito_client: CognitoClient::new(&config),
+            capture: EventCapture::from_env("create-auth-challenge", aws_sdk_s3::Client::new(&config))?,
+        })
+    }
+
+    /// Build the sign-in link for a magic link token from MAGIC_LINK_BASE_URL
+    fn build_magic_link_url(&self, token: &str) -> AuthResult<String> {
+        let base_url = self.magic_link_base_url.as_deref().ok_or_else(|| {
+            error!("MAGIC_LINK_BASE_URL environment variable not set");
+            AuthError::InternalError("MAGIC_LINK_BASE_URL not set".to_string())
+        })?;
+        Ok(magic_link_url(base_url, token))
+    }
+}
+
+/// A request to sign in, as seen by create-auth-challenge
+struct ChallengeRequest<'a> {
+    email: EmailAddress,
+    challenge_type: ChallengeType,
+    /// Cognito user_name (the Cognito sub), used as the user_id for new users
+    cognito_user_id: Option<&'a str>,
+    /// Caller's IP, forwarded by the webapp in client metadata
+    client_ip: Option<IpAddr>,
+    /// The user explicitly asked for the code to be sent again
+    resend: bool,
+}
+
+impl ChallengeRequest<'_> {
+    /// Every rate limit that sending this challenge counts against, with the key it is tracked by
+    fn send_rate_limits(&self) -> Vec<(RateLimitAction, String)> {
+        let mut limits = vec![(RateLimitAction::OtpSend, self.email.to_string())];
+        if let Some(ip) = self.client_ip {
+            limits.push((RateLimitAction::OtpSendIp, ip.to_string()));
+        }
+        limits.push((RateLimitAction::OtpSendDomain, self.email.domain().to_string()));
+        // Resends have their own, tighter limit on top of the send limit
+        if self.resend {
+            limits.push((RateLimitAction::OtpResend, self.email.to_string()));
+        }
+        limits
+    }
+}
+
+/// Result of issuing a challenge, before the answer is delivered
+struct IssuedChallenge {
+    /// The expected answer: a 6-digit OTP or a signed magic link token
+    answer: String,
+    challenge_id: String,
+    user: UserProfile,
+}
+
 async fn confirm_user_in_cognito(
     email: &str,
     user_pool_id: &str,
-    config: &aws_config::SdkConfig,
+    cognito_client: &CognitoClient,
 ) -> AuthResult<()> {
-    let cognito_client = CognitoClient::new(config);
-
     info!("Confirming user: {} in pool: {}", email, user_pool_id);
 
     // Confirm the user (this changes their status from UNCONFIRMED to CONFIRMED)
@@ -49,11 +165,13 @@ async fn confirm_user_in_cognito(
 }
 
 async fn function_handler(
+    context: &AppContext,
     event: LambdaEvent<CognitoEventUserPoolsCreateAuthChallenge>,
 ) -> Result<CognitoEventUserPoolsCreateAuthChallenge, Error> {
+    context.capture.capture(&event.payload).await;
     let mut response_event = event.payload;
 
-    match handle_create_challenge(&mut response_event).await {
+    match handle_create_challenge(&mut response_event, context).await {
         Ok(_) => {
             info!("Successfully created auth challenge");
             Ok(response_event)
@@ -63,7 +181,8 @@ async fn function_handler(
             // Don't fail the Lambda - return empty challenge to let Cognito handle gracefully
             response_event.response.public_challenge_parameters = HashMap::<String, String>::new();
             response_event.response.private_challenge_parameters = HashMap::<String, String>::new();
-            response_event.response.challenge_metadata = Some("ERROR".to_string());
+            response_event.response.challenge_metadata =
+                Some(ChallengeMetadata::ERROR.to_string());
             Ok(response_event)
         }
     }
@@ -71,12 +190,13 @@ async fn function_handler(
 
 async fn handle_create_challenge(
     event: &mut CognitoEventUserPoolsCreateAuthChallenge,
+    context: &AppContext,
 ) -> AuthResult<()> {
     // Debug: Log the entire event structure
     info!("  - User attributes: {:?}", event.request.user_attributes);
 
     // Extract email from user attributes or client metadata
-    let email = if let Some(email) = event.request.user_attributes.get("email") {
+    let raw_email = if let Some(email) = event.request.user_attributes.get("email") {
         email
     } else if let Some(email) = event.request.client_metadata.get("email") {
         email
@@ -86,87 +206,209 @@ async fn handle_create_challenge(
         ));
     };
 
-    // Validate email format
-    if !is_valid_email(email) {
-        return Err(AuthError::ValidationError(
-            "Invalid email format".to_string(),
-        ));
-    }
+    // Validate the email and key everything by its canonical form
+    let email = EmailAddress::parse_with(raw_email, context.sub_addressing)?;
 
-    info!("Creating auth challenge for email: {}", email);
+    // Challenge type requested by the client, defaulting to an emailed OTP
+    let challenge_type = match event.request.client_metadata.get("challenge_type") {
+        Some(value) => value.parse::<ChallengeType>()?,
+        None => ChallengeType::default(),
+    };
+    // Set by the client when the user explicitly asks for the code to be sent again
+    let resend = event
+        .request
+        .client_metadata
+        .get("resend")
+        .is_some_and(|value| value == "true");
+    // Set server-side by the webapp from the incoming connection, never by the browser
+    let client_ip = match event.request.client_metadata.get("client_ip") {
+        Some(value) => Some(value.parse::<IpAddr>().map_err(|_| {
+            AuthError::ValidationError("Invalid client_ip in client metadata".to_string())
+        })?),
+        None => None,
+    };
 
-    // Log all environment variables for debugging
-    info!("=== ENVIRONMENT VARIABLES DEBUG ===");
-    for (key, value) in std::env::vars() {
-        if key.contains("TABLE") || key.contains("EMAIL") || key.contains("APP") || key.contains("ENVIRONMENT") {
-            info!("  {}={}", key, value);
+    // After a wrong answer Cognito asks for another challenge in the same session. Present the
+    // one the user is still answering, so a typo doesn't cost them a new email.
+    if let Some(previous) = previous_challenge(&event.request.session) {
+        let otp_store = context.otp_store.as_ref();
+        match reissue_challenge(&email, &previous, otp_store, context.users.as_ref()).await? {
+            Some(user) => {
+                info!(
+                    "Re-presenting {} challenge for email: {}",
+                    previous.challenge_type.as_str(),
+                    email
+                );
+                set_challenge_response(event, &email, &previous, &user, None);
+                return Ok(());
+            }
+            None => info!("Previous challenge can no longer be answered; issuing a new one"),
         }
     }
-    info!("=== END ENVIRONMENT VARIABLES DEBUG ===");
 
-    // Initialize AWS clients
-    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
-    let ses_client = aws_sdk_ses::Client::new(&config);
+    info!("Creating {} auth challenge for email: {}", challenge_type.as_str(), email);
 
-    // Get environment variables
-    let from_email = std::env::var("FROM_EMAIL")
-        .map_err(|e| {
-            error!("FROM_EMAIL environment variable not set: {:?}", e);
-            AuthError::InternalError("FROM_EMAIL not set".to_string())
-        })?;
+    let request = ChallengeRequest {
+        email: email.clone(),
+        challenge_type,
+        cognito_user_id: event.cognito_event_user_pools_header.user_name.as_deref(),
+        client_ip,
+        resend,
+    };
 
-    // Initialize services using naming utilities
-    info!("Initializing RateLimitService...");
-    let rate_limit_service = RateLimitService::from_env(dynamodb_client.clone())
-        .map_err(|e| {
-            error!("Failed to initialize RateLimitService: {}", e);
-            AuthError::InternalError(format!("Failed to initialize RateLimitService: {}", e))
-        })?;
-    
-    info!("Initializing DynamoDBService...");
-    let dynamodb_service = DynamoDBService::from_env(dynamodb_client.clone())
-        .map_err(|e| {
-            error!("Failed to initialize DynamoDBService: {}", e);
-            AuthError::InternalError(format!("Failed to initialize DynamoDBService: {}", e))
-        })?;
-    
-    info!("Initializing SESService...");
-    let ses_service = SESService::new(ses_client, from_email)
-        .map_err(|e| {
-            error!("Failed to initialize SESService: {}", e);
-            AuthError::InternalError(format!("Failed to initialize SESService: {}", e))
-        })?;
-    
-    info!("All services initialized successfully");
-
-    // Check rate limiting
-    info!("Checking rate limit for email: {}", email);
-    match rate_limit_service.check_rate_limit(email).await {
-        Ok(allowed) => {
-            if !allowed {
-                warn!("Rate limit exceeded for email: {}", email);
-
-                // Get reset time for user feedback
-                let reset_time = rate_limit_service.get_rate_limit_reset_time(email).await?;
-                let reset_minutes = reset_time.unwrap_or(0) / 60;
-
-                return Err(AuthError::RateLimitExceeded(format!(
-                    "Too many requests. Try again in {} minutes.",
-                    reset_minutes.max(1)
-                )));
+    let IssuedChallenge {
+        answer,
+        challenge_id,
+        user,
+    } = issue_challenge(
+        &request,
+        &context.hasher,
+        context.rate_limits.as_ref(),
+        context.users.as_ref(),
+        context.otp_store.as_ref(),
+    )
+    .await?;
+
+    // CRITICAL: Confirm the user BEFORE sending OTP
+    // This ensures the user is confirmed by the time they verify the OTP
+    if let Some(ref user_pool_id) = event.cognito_event_user_pools_header.user_pool_id {
+        // Cognito knows the user by the address they signed up with
+        match confirm_user_in_cognito(raw_email, user_pool_id, &context.cognito_client).await {
+            Ok(_) => {
+                info!("User confirmed successfully before OTP challenge");
+            }
+            Err(e) => {
+                warn!("Failed to confirm user before OTP challenge: {}", e);
+                // Continue anyway - the user might already be confirmed
             }
-            info!("Rate limit check passed for email: {}", email);
         }
-        Err(e) => {
-            error!("Rate limit check failed: {}", e);
-            return Err(e);
+    }
+
+    // Deliver the challenge answer by email
+    let email_request = match challenge_type {
+        ChallengeType::OtpEmail => EmailRequest::otp(email.to_string(), answer),
+        ChallengeType::MagicLink => EmailRequest::magic_link(
+            email.to_string(),
+            context.build_magic_link_url(&answer)?,
+            challenge_type.validity_seconds() / 60,
+        ),
+    };
+    let delivery = context.otp_delivery.deliver(email_request).await?;
+    info!("{} delivered via {} path", challenge_type.as_str(), delivery.as_str());
+
+    let metadata = ChallengeMetadata::new(challenge_type, challenge_id);
+    set_challenge_response(event, &email, &metadata, &user, Some(delivery));
+
+    info!("Auth challenge created successfully for email: {}", email);
+    Ok(())
+}
+
+/// Set the challenge parameters and metadata. `delivery` is None when an already delivered
+/// challenge is presented again.
+fn set_challenge_response(
+    event: &mut CognitoEventUserPoolsCreateAuthChallenge,
+    email: &EmailAddress,
+    metadata: &ChallengeMetadata,
+    user: &UserProfile,
+    delivery: Option<EmailDelivery>,
+) {
+    let challenge_type = metadata.challenge_type.as_str().to_string();
+
+    let mut public_params = HashMap::new();
+    public_params.insert("email".to_string(), email.to_string());
+    public_params.insert("challenge_type".to_string(), challenge_type.clone());
+    // A queued email can take a little longer to arrive, which the client may want to say
+    if let Some(delivery) = delivery {
+        public_params.insert("delivery".to_string(), delivery.as_str().to_string());
+    }
+
+    let mut private_params = HashMap::new();
+    private_params.insert("challenge_id".to_string(), metadata.challenge_id.clone());
+    private_params.insert("challenge_type".to_string(), challenge_type);
+    private_params.insert("user_id".to_string(), user.user_id.clone());
+    private_params.insert("user_status".to_string(), format!("{:?}", user.status));
+
+    event.response.public_challenge_parameters = public_params;
+    event.response.private_challenge_parameters = private_params;
+    event.response.challenge_metadata = Some(metadata.to_string());
+}
+
+/// The challenge most recently issued in this Cognito session, if any
+fn previous_challenge(
+    session: &[Option<CognitoEventUserPoolsChallengeResult>],
+) -> Option<ChallengeMetadata> {
+    session
+        .iter()
+        .rev()
+        .filter_map(|o| o.as_ref())
+        .find(|r| r.challenge_name.as_deref() == Some("CUSTOM_CHALLENGE"))
+        .and_then(|r| r.challenge_metadata.as_deref())
+        .and_then(ChallengeMetadata::parse)
+}
+
+/// Check the previous challenge can still be answered (not consumed, replaced, expired or
+/// locked out) and return the user it was issued to
+async fn reissue_challenge(
+    email: &EmailAddress,
+    previous: &ChallengeMetadata,
+    otp_store: &dyn OtpStore,
+    users: &dyn UserRepository,
+) -> AuthResult<Option<UserProfile>> {
+    let Some(record) = otp_store.get_otp(email).await? else {
+        return Ok(None);
+    };
+    let now = current_timestamp();
+    if record.challenge_id != previous.challenge_id
+        || record.is_locked(now)
+        || now > record.expires_at
+    {
+        return Ok(None);
+    }
+    users.get_user_by_email(email).await
+}
+
+fn magic_link_url(base_url: &str, token: &str) -> String {
+    let separator = if base_url.contains('?') { '&' } else { '?' };
+    format!("{}{}token={}", base_url, separator, token)
+}
+
+/// Check rate limits, find or create the user, and store a fresh challenge record.
+/// Delivery of the returned answer is left to the caller.
+async fn issue_challenge(
+    request: &ChallengeRequest<'_>,
+    hasher: &OtpHasher,
+    rate_limits: &dyn RateLimitStore,
+    users: &dyn UserRepository,
+    otp_store: &dyn OtpStore,
+) -> AuthResult<IssuedChallenge> {
+    let email = &request.email;
+    let &ChallengeRequest {
+        challenge_type,
+        cognito_user_id,
+        ..
+    } = request;
+
+    // Refuse to issue a new OTP while the email is locked out after too many wrong answers
+    if let Some(existing) = otp_store.get_otp(email).await? {
+        if existing.is_locked(current_timestamp()) {
+            warn!("Email is locked out after too many failed attempts: {}", email);
+            return Err(AuthError::TooManyAttempts(
+                "Too many failed attempts. Try again later.".to_string(),
+            ));
         }
     }
 
+    // Check and charge rate limits by email, and by client IP and email domain to stop spraying,
+    // in one atomic step so concurrent requests can't all slip under the limit
+    info!("Checking rate limits for email: {}", email);
+    rate_limits
+        .consume_rate_limits(&request.send_rate_limits())
+        .await?;
+    info!("Rate limit checks passed for email: {}", email);
+
     // Check if user exists, create if new registration
     info!("Checking if user exists for email: {}", email);
-    let user = match dynamodb_service.get_user_by_email(email).await {
+    let user = match users.get_user_by_email(email).await {
         Ok(user_opt) => match user_opt {
             Some(user) => {
                 info!("Existing user found for email: {}", email);
@@ -174,15 +416,10 @@ async fn handle_create_challenge(
             }
             None => {
                 info!("Creating new user for email: {}", email);
-                // Use the Cognito user_name (which is the Cognito sub) as the user_id
-                let cognito_user_id = event
-                    .cognito_event_user_pools_header
-                    .user_name
-                    .as_ref()
-                    .ok_or_else(|| {
-                        AuthError::InternalError("Cognito user_name not available".to_string())
-                    })?;
-                match dynamodb_service.create_user(email, cognito_user_id).await {
+                let cognito_user_id = cognito_user_id.ok_or_else(|| {
+                    AuthError::InternalError("Cognito user_name not available".to_string())
+                })?;
+                match users.create_user(email, cognito_user_id).await {
                     Ok(user) => {
                         info!("Successfully created new user for email: {}", email);
                         user
@@ -200,12 +437,17 @@ async fn handle_create_challenge(
         }
     };
 
-    // Generate OTP and challenge ID
-    let otp = generate_otp();
-    let otp_hash = hash_otp(&otp);
+    // Generate the challenge answer; only its hash is stored, so either kind is single use
     let challenge_id = generate_challenge_id();
     let now = current_timestamp();
-    let expires_at = now + (5 * 60); // 5 minutes
+    let expires_at = now + challenge_type.validity_seconds();
+    let answer = match challenge_type {
+        ChallengeType::OtpEmail => generate_otp(),
+        ChallengeType::MagicLink => {
+            hasher.sign_magic_link(email.as_str(), &challenge_id, expires_at)
+        }
+    };
+    let otp_hash = hasher.hash_otp(&answer, email.as_str(), &challenge_id);
     let ttl = expires_at + (60 * 60); // TTL 1 hour after expiration for cleanup
 
     // Store OTP record
@@ -217,46 +459,16 @@ async fn handle_create_challenge(
         ttl,
         challenge_id: challenge_id.clone(),
         attempts: 0,
+        locked_until: None,
     };
 
-    dynamodb_service.store_otp(&otp_record).await?;
-
-    // CRITICAL: Confirm the user BEFORE sending OTP
-    // This ensures the user is confirmed by the time they verify the OTP
-    if let Some(ref user_pool_id) = event.cognito_event_user_pools_header.user_pool_id {
-        match confirm_user_in_cognito(email, user_pool_id, &config).await {
-            Ok(_) => {
-                info!("User confirmed successfully before OTP challenge");
-            }
-            Err(e) => {
-                warn!("Failed to confirm user before OTP challenge: {}", e);
-                // Continue anyway - the user might already be confirmed
-            }
-        }
-    }
-
-    // Send OTP email
-    ses_service.send_otp_email(email, &otp).await?;
-
-    // Record this request for rate limiting
-    rate_limit_service.record_request(email).await?;
-
-    // Set response parameters
-    let mut public_params = HashMap::new();
-    public_params.insert("email".to_string(), email.clone());
-    public_params.insert("challenge_type".to_string(), "OTP_EMAIL".to_string());
-
-    let mut private_params = HashMap::new();
-    private_params.insert("challenge_id".to_string(), challenge_id);
-    private_params.insert("user_id".to_string(), user.user_id);
-    private_params.insert("user_status".to_string(), format!("{:?}", user.status));
-
-    event.response.public_challenge_parameters = public_params;
-    event.response.private_challenge_parameters = private_params;
-    event.response.challenge_metadata = Some("OTP_EMAIL_SENT".to_string());
+    otp_store.store_otp(&otp_record).await?;
 
-    info!("Auth challenge created successfully for email: {}", email);
-    Ok(())
+    Ok(IssuedChallenge {
+        answer,
+        challenge_id,
+        user,
+    })
 }
 
 #[tokio::main]
@@ -268,5 +480,523 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
-    run(service_fn(function_handler)).await
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{
+        InMemoryEmailSender, InMemoryOtpStore, InMemoryRateLimitStore, InMemoryUserRepository,
+        OtpDeliveryStrategy, OtpHashKey, RateLimitPolicies, RateLimitPolicy, UserStatus,
+    };
+
+    fn email(address: &str) -> EmailAddress {
+        EmailAddress::parse(address).unwrap()
+    }
+
+    fn request<'a>(
+        address: &str,
+        challenge_type: ChallengeType,
+        cognito_user_id: Option<&'a str>,
+    ) -> ChallengeRequest<'a> {
+        ChallengeRequest {
+            email: email(address),
+            challenge_type,
+            cognito_user_id,
+            client_ip: None,
+            resend: false,
+        }
+    }
+
+    fn hasher() -> OtpHasher {
+        OtpHasher::new(OtpHashKey::new(1, "test-secret-0123456789abcdefghijklmnop"), None)
+    }
+
+    /// A context backed by in-memory stores that sends email directly
+    fn context(sender: Arc<InMemoryEmailSender>) -> (Arc<InMemoryOtpStore>, AppContext) {
+        let otp_store = Arc::new(InMemoryOtpStore::new());
+        let cognito_config = aws_sdk_cognitoidentityprovider::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .build();
+        let context = AppContext {
+            sub_addressing: SubAddressing::default(),
+            hasher: hasher(),
+            rate_limits: Arc::new(InMemoryRateLimitStore::new()),
+            users: Arc::new(InMemoryUserRepository::new()),
+            otp_store: otp_store.clone(),
+            otp_delivery: OtpDeliveryService::new(OtpDeliveryStrategy::Direct, sender, None)
+                .unwrap(),
+            magic_link_base_url: Some("https://app.example.com/auth/magic-link".to_string()),
+            cognito_client: CognitoClient::from_conf(cognito_config),
+            capture: EventCapture::disabled("create-auth-challenge"),
+        };
+        (otp_store, context)
+    }
+
+    fn event(
+        address: &str,
+        challenge_type: &str,
+    ) -> LambdaEvent<CognitoEventUserPoolsCreateAuthChallenge> {
+        // No user pool ID, so the handler doesn't try to confirm the user in Cognito
+        let mut event = CognitoEventUserPoolsCreateAuthChallenge::default();
+        event.cognito_event_user_pools_header.user_name = Some(format!("sub-{}", address));
+        event.request.user_attributes.insert("email".to_string(), address.to_string());
+        event
+            .request
+            .client_metadata
+            .insert("challenge_type".to_string(), challenge_type.to_string());
+        LambdaEvent::new(event, lambda_runtime::Context::default())
+    }
+
+    #[tokio::test]
+    async fn test_handler_shares_prebuilt_context_across_invocations() {
+        let sender = Arc::new(InMemoryEmailSender::new());
+        let (otp_store, context) = context(sender.clone());
+
+        let response = function_handler(&context, event("otp@example.com", "OTP_EMAIL"))
+            .await
+            .unwrap()
+            .response;
+        assert_eq!(response.public_challenge_parameters["delivery"], "DIRECT");
+        let metadata = ChallengeMetadata::parse(&response.challenge_metadata.unwrap()).unwrap();
+        assert_eq!(metadata.challenge_type, ChallengeType::OtpEmail);
+
+        let response = function_handler(&context, event("link@example.com", "MAGIC_LINK"))
+            .await
+            .unwrap()
+            .response;
+        assert!(response.challenge_metadata.unwrap().starts_with("MAGIC_LINK_SENT:"));
+
+        let sent = sender.sent();
+        assert_eq!(sent.len(), 2);
+        let record = otp_store.get_otp(&email("otp@example.com")).await.unwrap().unwrap();
+        assert!(hasher().verify_otp(
+            &sent[0].template_data["otp"],
+            "otp@example.com",
+            &record.challenge_id,
+            &record.otp_hash
+        ));
+        assert!(sent[1].template_data["magicLinkUrl"]
+            .starts_with("https://app.example.com/auth/magic-link?token="));
+    }
+
+    #[tokio::test]
+    async fn test_retry_in_session_re_presents_challenge_without_new_email() {
+        let sender = Arc::new(InMemoryEmailSender::new());
+        let (otp_store, context) = context(sender.clone());
+
+        let first = function_handler(&context, event("otp@example.com", "OTP_EMAIL"))
+            .await
+            .unwrap()
+            .response;
+        let first_metadata = first.challenge_metadata.unwrap();
+
+        // Cognito asks again after a wrong answer, passing the session so far
+        let wrong_answer = |metadata: &str| CognitoEventUserPoolsChallengeResult {
+            challenge_name: Some("CUSTOM_CHALLENGE".to_string()),
+            challenge_result: false,
+            challenge_metadata: Some(metadata.to_string()),
+        };
+        let mut retry = event("otp@example.com", "OTP_EMAIL");
+        retry.payload.request.session = vec![Some(wrong_answer(&first_metadata))];
+        let second = function_handler(&context, retry).await.unwrap().response;
+
+        assert_eq!(second.challenge_metadata.as_deref(), Some(first_metadata.as_str()));
+        assert_eq!(
+            second.private_challenge_parameters["challenge_id"],
+            first.private_challenge_parameters["challenge_id"]
+        );
+        assert!(!second.public_challenge_parameters.contains_key("delivery"));
+        assert_eq!(sender.sent().len(), 1);
+
+        // Once the challenge is locked out it can't be presented again, and issuing a new one
+        // is refused too
+        otp_store
+            .lock_out(&email("otp@example.com"), current_timestamp() + 60)
+            .await
+            .unwrap();
+        let mut locked = event("otp@example.com", "OTP_EMAIL");
+        locked.payload.request.session = vec![Some(wrong_answer(&first_metadata))];
+        let third = function_handler(&context, locked).await.unwrap().response;
+        assert_eq!(third.challenge_metadata.as_deref(), Some(ChallengeMetadata::ERROR));
+        assert_eq!(sender.sent().len(), 1);
+    }
+
+    #[test]
+    fn test_challenge_metadata_round_trip() {
+        let metadata = ChallengeMetadata::new(ChallengeType::MagicLink, "challenge-1".to_string());
+        assert_eq!(metadata.to_string(), "MAGIC_LINK_SENT:challenge-1");
+        assert_eq!(ChallengeMetadata::parse("MAGIC_LINK_SENT:challenge-1"), Some(metadata));
+
+        for other in ["ERROR", "OTP_EMAIL_SENT", "OTP_EMAIL_SENT:", "SMS_SENT:challenge-1"] {
+            assert_eq!(ChallengeMetadata::parse(other), None, "{}", other);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_reports_errors_in_challenge_metadata() {
+        let sender = Arc::new(InMemoryEmailSender::new());
+        sender.fail(false);
+        let (_, context) = context(sender);
+
+        let response = function_handler(&context, event("otp@example.com", "OTP_EMAIL"))
+            .await
+            .unwrap()
+            .response;
+        assert_eq!(response.challenge_metadata.as_deref(), Some("ERROR"));
+        assert!(response.public_challenge_parameters.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_creates_user_and_stores_otp() {
+        let rate_limits = InMemoryRateLimitStore::new();
+        let users = InMemoryUserRepository::new();
+        let otp_store = InMemoryOtpStore::new();
+
+        let issued = issue_challenge(
+            &request("new@example.com", ChallengeType::OtpEmail, Some("cognito-sub-1")),
+            &hasher(),
+            &rate_limits,
+            &users,
+            &otp_store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(issued.user.user_id, "cognito-sub-1");
+        assert!(matches!(issued.user.status, UserStatus::RegistrationEmailNotVerified));
+
+        let record = otp_store.get_otp(&email("new@example.com")).await.unwrap().unwrap();
+        assert_eq!(record.challenge_id, issued.challenge_id);
+        assert_eq!(record.attempts, 0);
+        assert_eq!(record.expires_at - record.created_at, 5 * 60);
+        assert!(record.otp_hash.starts_with("v1:"));
+        assert!(hasher().verify_otp(
+            &issued.answer,
+            "new@example.com",
+            &issued.challenge_id,
+            &record.otp_hash
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_reuses_existing_user() {
+        let rate_limits = InMemoryRateLimitStore::new();
+        let users = InMemoryUserRepository::new();
+        let otp_store = InMemoryOtpStore::new();
+        users.create_user(&email("existing@example.com"), "user-1").await.unwrap();
+
+        let issued = issue_challenge(
+            &request("existing@example.com", ChallengeType::OtpEmail, None),
+            &hasher(),
+            &rate_limits,
+            &users,
+            &otp_store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(issued.user.user_id, "user-1");
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_keys_by_canonical_email() {
+        let rate_limits = InMemoryRateLimitStore::new();
+        let users = InMemoryUserRepository::new();
+        let otp_store = InMemoryOtpStore::new();
+        users.create_user(&email("bob@x.com"), "user-1").await.unwrap();
+
+        let issued = issue_challenge(
+            &request("Bob@X.com", ChallengeType::OtpEmail, Some("cognito-sub-2")),
+            &hasher(),
+            &rate_limits,
+            &users,
+            &otp_store,
+        )
+        .await
+        .unwrap();
+
+        // Same user, and the OTP is found under the lowercase address
+        assert_eq!(issued.user.user_id, "user-1");
+        let record = otp_store.get_otp(&email("bob@x.com")).await.unwrap().unwrap();
+        assert_eq!(record.email, "bob@x.com");
+        assert!(hasher().verify_otp(&issued.answer, "bob@x.com", &issued.challenge_id, &record.otp_hash));
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_adopts_row_the_email_lookup_missed() {
+        let rate_limits = InMemoryRateLimitStore::new();
+        let users = InMemoryUserRepository::new();
+        let otp_store = InMemoryOtpStore::new();
+        // The sub's row is under an email the lookup can't find, as legacy raw-case rows were
+        users.create_user(&email("old@example.com"), "cognito-sub-1").await.unwrap();
+        users
+            .transition_status(
+                "cognito-sub-1",
+                UserStatus::RegistrationEmailNotVerified,
+                UserStatus::RegistrationNeedUserInfo,
+                "test",
+            )
+            .await
+            .unwrap();
+
+        let issued = issue_challenge(
+            &request("new@example.com", ChallengeType::OtpEmail, Some("cognito-sub-1")),
+            &hasher(),
+            &rate_limits,
+            &users,
+            &otp_store,
+        )
+        .await
+        .unwrap();
+
+        // The existing user signs in with their progress kept, and is found by email from now on
+        assert_eq!(issued.user.user_id, "cognito-sub-1");
+        assert_eq!(issued.user.status, UserStatus::RegistrationNeedUserInfo);
+        let user = users.get_user_by_email(&email("new@example.com")).await.unwrap().unwrap();
+        assert_eq!(user.user_id, "cognito-sub-1");
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_requires_cognito_user_for_new_user() {
+        let result = issue_challenge(
+            &request("new@example.com", ChallengeType::OtpEmail, None),
+            &hasher(),
+            &InMemoryRateLimitStore::new(),
+            &InMemoryUserRepository::new(),
+            &InMemoryOtpStore::new(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AuthError::InternalError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_rate_limited() {
+        let rate_limits = InMemoryRateLimitStore::new();
+        let otp_store = InMemoryOtpStore::new();
+        for _ in 0..3 {
+            rate_limits
+                .consume_rate_limits(&[(RateLimitAction::OtpSend, "busy@example.com".to_string())])
+                .await
+                .unwrap();
+        }
+
+        let result = issue_challenge(
+            &request("busy@example.com", ChallengeType::OtpEmail, Some("cognito-sub-1")),
+            &hasher(),
+            &rate_limits,
+            &InMemoryUserRepository::new(),
+            &otp_store,
+        )
+        .await;
+
+        assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+        assert!(otp_store.get_otp(&email("busy@example.com")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_resend_has_its_own_limit() {
+        let rate_limits = InMemoryRateLimitStore::new();
+        let users = InMemoryUserRepository::new();
+        let otp_store = InMemoryOtpStore::new();
+        let resend = ChallengeRequest {
+            resend: true,
+            ..request("again@example.com", ChallengeType::OtpEmail, Some("sub"))
+        };
+
+        issue_challenge(&resend, &hasher(), &rate_limits, &users, &otp_store)
+            .await
+            .unwrap();
+        let result = issue_challenge(&resend, &hasher(), &rate_limits, &users, &otp_store).await;
+        assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+
+        // The refused resend was not charged to the send limit, so a plain send still fits
+        let send = request("again@example.com", ChallengeType::OtpEmail, Some("sub"));
+        for _ in 0..2 {
+            issue_challenge(&send, &hasher(), &rate_limits, &users, &otp_store)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_rate_limited_by_client_ip() {
+        let policies = RateLimitPolicies::default()
+            .with_policy(RateLimitAction::OtpSendIp, RateLimitPolicy::parse("2/1h").unwrap());
+        let rate_limits = InMemoryRateLimitStore::with_policies(policies);
+        let users = InMemoryUserRepository::new();
+        let otp_store = InMemoryOtpStore::new();
+        let client_ip = Some("203.0.113.7".parse().unwrap());
+
+        // Each address is new, so only the per-IP limit can stop the spray
+        for email in ["a@one.example", "b@two.example"] {
+            let request = ChallengeRequest {
+                client_ip,
+                ..request(email, ChallengeType::OtpEmail, Some(email))
+            };
+            issue_challenge(&request, &hasher(), &rate_limits, &users, &otp_store)
+                .await
+                .unwrap();
+        }
+
+        let blocked = ChallengeRequest {
+            client_ip,
+            ..request("c@three.example", ChallengeType::OtpEmail, Some("sub"))
+        };
+        let result = issue_challenge(&blocked, &hasher(), &rate_limits, &users, &otp_store).await;
+        assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+        assert!(otp_store.get_otp(&email("c@three.example")).await.unwrap().is_none());
+
+        // Another client is unaffected
+        let other = ChallengeRequest {
+            client_ip: Some("198.51.100.1".parse().unwrap()),
+            ..request("c@three.example", ChallengeType::OtpEmail, Some("sub"))
+        };
+        assert!(issue_challenge(&other, &hasher(), &rate_limits, &users, &otp_store)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_rate_limited_by_email_domain() {
+        let rate_limits = InMemoryRateLimitStore::with_policies(
+            RateLimitPolicies::default()
+                .with_policy(RateLimitAction::OtpSendDomain, RateLimitPolicy::parse("1/1h").unwrap()),
+        );
+        rate_limits
+            .consume_rate_limits(&[(RateLimitAction::OtpSendDomain, "victim.example".to_string())])
+            .await
+            .unwrap();
+
+        let result = issue_challenge(
+            &request("Someone@Victim.Example", ChallengeType::OtpEmail, Some("sub")),
+            &hasher(),
+            &rate_limits,
+            &InMemoryUserRepository::new(),
+            &InMemoryOtpStore::new(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+    }
+
+    #[tokio::test]
+    async fn test_issue_challenge_refused_while_locked_out() {
+        let rate_limits = InMemoryRateLimitStore::new();
+        let users = InMemoryUserRepository::new();
+        let otp_store = InMemoryOtpStore::new();
+
+        issue_challenge(
+            &request("locked@example.com", ChallengeType::OtpEmail, Some("sub")),
+            &hasher(),
+            &rate_limits,
+            &users,
+            &otp_store,
+        )
+        .await
+        .unwrap();
+        let now = current_timestamp();
+        otp_store.lock_out(&email("locked@example.com"), now + 60).await.unwrap();
+
+        let result = issue_challenge(
+            &request("locked@example.com", ChallengeType::OtpEmail, Some("sub")),
+            &hasher(),
+            &rate_limits,
+            &users,
+            &otp_store,
+        )
+        .await;
+        assert!(matches!(result, Err(AuthError::TooManyAttempts(_))));
+
+        // Once the lockout has passed a new OTP can be issued
+        otp_store.lock_out(&email("locked@example.com"), now - 1).await.unwrap();
+        let issued = issue_challenge(
+            &request("locked@example.com", ChallengeType::OtpEmail, Some("sub")),
+            &hasher(),
+            &rate_limits,
+            &users,
+            &otp_store,
+        )
+        .await
+        .unwrap();
+        let record = otp_store.get_otp(&email("locked@example.com")).await.unwrap().unwrap();
+        assert_eq!(record.challenge_id, issued.challenge_id);
+        assert_eq!(record.locked_until, None);
+    }
+
+    #[tokio::test]
+    async fn test_issue_magic_link_challenge() {
+        let otp_store = InMemoryOtpStore::new();
+
+        let issued = issue_challenge(
+            &request("link@example.com", ChallengeType::MagicLink, Some("sub")),
+            &hasher(),
+            &InMemoryRateLimitStore::new(),
+            &InMemoryUserRepository::new(),
+            &otp_store,
+        )
+        .await
+        .unwrap();
+
+        let record = otp_store.get_otp(&email("link@example.com")).await.unwrap().unwrap();
+        assert_eq!(record.expires_at - record.created_at, 15 * 60);
+        assert!(hasher().verify_magic_link(
+            &issued.answer,
+            "link@example.com",
+            &issued.challenge_id,
+            record.created_at
+        ));
+        assert!(hasher().verify_otp(
+            &issued.answer,
+            "link@example.com",
+            &issued.challenge_id,
+            &record.otp_hash
+        ));
+    }
+
+    /// Replays the captured events in fixtures/create-auth-challenge against empty in-memory stores
+    #[tokio::test]
+    async fn test_replay_captured_events() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/create-auth-challenge");
+
+        let replayed = auth_shared::replay_fixtures(&fixtures, |mut event| async move {
+            // Never confirm replayed users in a real user pool
+            event["userPoolId"] = serde_json::Value::Null;
+            let event = serde_json::from_value(event).unwrap();
+            let (_, context) = context(Arc::new(InMemoryEmailSender::new()));
+
+            let response = function_handler(&context, LambdaEvent::new(event, Default::default()))
+                .await
+                .unwrap()
+                .response;
+
+            // Challenge IDs are generated per run
+            let mut rendered = serde_json::to_string(&response).unwrap();
+            if let Some(id) = response.private_challenge_parameters.get("challenge_id") {
+                rendered = rendered.replace(id.as_str(), "<challenge_id>");
+            }
+            serde_json::from_str(&rendered).unwrap()
+        })
+        .await
+        .unwrap_or_else(|failures| panic!("{}", failures));
+        assert!(replayed > 0);
+    }
+
+    #[test]
+    fn test_magic_link_url() {
+        assert_eq!(
+            magic_link_url("https://app.example.com/auth/magic-link", "v1.2.abc"),
+            "https://app.example.com/auth/magic-link?token=v1.2.abc"
+        );
+        assert_eq!(
+            magic_link_url("https://app.example.com/auth?src=email", "v1.2.abc"),
+            "https://app.example.com/auth?src=email&token=v1.2.abc"
+        );
+    }
 }
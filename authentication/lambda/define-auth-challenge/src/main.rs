@@ -1,19 +1,37 @@
+use aws_config::BehaviorVersion;
 use aws_lambda_events::{
     cognito::CognitoEventUserPoolsChallengeResult,
     event::cognito::CognitoEventUserPoolsDefineAuthChallenge,
 };
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
 use tracing::{error, info};
-use serde_json;
 
-use auth_shared::AuthResult;
+use auth_shared::{AuthResult, ChallengeAttemptPolicy, ChallengeMetadata, EventCapture};
+
+/// Configuration and clients built once per container and shared by every invocation
+struct AppContext {
+    attempts: ChallengeAttemptPolicy,
+    capture: EventCapture,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        Ok(Self {
+            attempts: ChallengeAttemptPolicy::from_env()?,
+            capture: EventCapture::from_env("define-auth-challenge", aws_sdk_s3::Client::new(&config))?,
+        })
+    }
+}
 
 async fn function_handler(
+    context: &AppContext,
     event: LambdaEvent<CognitoEventUserPoolsDefineAuthChallenge>,
 ) -> Result<CognitoEventUserPoolsDefineAuthChallenge, Error> {
+    context.capture.capture(&event.payload).await;
     let mut response_event = event.payload;
 
-    match handle_define_challenge(&mut response_event).await {
+    match handle_define_challenge(&mut response_event, &context.attempts).await {
         Ok(_) => {
             info!("Successfully defined auth challenge");
             Ok(response_event)
@@ -32,26 +50,50 @@ async fn function_handler(
 
 const CUSTOM: &str = "CUSTOM_CHALLENGE";
 
+/// What Cognito should do next in the sign-in session
+#[derive(Debug, PartialEq, Eq)]
+enum NextStep {
+    /// Present a custom challenge: the first one, or the same one again after a wrong answer
+    Challenge,
+    IssueTokens,
+    Fail,
+}
 
+/// Decide the next step from the custom challenges answered so far in this session.
+/// Every answer but the last was wrong, or tokens would already have been issued.
+fn next_step(
+    session: &[Option<CognitoEventUserPoolsChallengeResult>],
+    attempts: &ChallengeAttemptPolicy,
+) -> NextStep {
+    let answers: Vec<&CognitoEventUserPoolsChallengeResult> = session
+        .iter()
+        .filter_map(|o| o.as_ref())
+        .filter(|r| r.challenge_name.as_deref() == Some(CUSTOM))
+        .collect();
+
+    let Some(last) = answers.last() else {
+        return NextStep::Challenge;
+    };
+    if last.challenge_result {
+        return NextStep::IssueTokens;
+    }
+    // create-auth-challenge couldn't issue the challenge (e.g. rate limited), so asking again
+    // would fail the same way
+    if last.challenge_metadata.as_deref() == Some(ChallengeMetadata::ERROR) {
+        return NextStep::Fail;
+    }
+    if attempts.allows_retry(answers.len()) {
+        NextStep::Challenge
+    } else {
+        NextStep::Fail
+    }
+}
 
 async fn handle_define_challenge(
     event: &mut CognitoEventUserPoolsDefineAuthChallenge,
+    attempts: &ChallengeAttemptPolicy,
 ) -> AuthResult<()> {
-    // Debug: Log the entire event structure
-    info!("  - User attributes: {:?}", event.request.user_attributes);
-    //info!("  - Session: {:?}", event.request.session);
-    //info!("  - Client metadata: {:?}", event.request.client_metadata);
-    //info!("  - User not found: {:?}", event.request.user_not_found);
-    //info!("  - Challenge name: {:?}", event.response.challenge_name);
-    //info!("  - Issue tokens: {:?}", event.response.issue_tokens);
-    //info!("  - Fail authentication: {:?}", event.response.fail_authentication);
-    
-    // Try to serialize the entire event for debugging
-    match serde_json::to_string_pretty(&event) {
-        Ok(json) => info!("Full event JSON: {}", json),
-        Err(e) => info!("Failed to serialize event: {}", e),
-    }
-
+    // Full events are recorded, redacted, by EVENT_CAPTURE rather than logged here
     // Extract email from various sources
     let email = if let Some(email) = event.request.user_attributes.get("email") {
         email.clone()
@@ -67,43 +109,10 @@ async fn handle_define_challenge(
 
     info!("Defining auth challenge for email: {}", email);
 
-    // NOTE: In aws_lambda_events, this is typically Vec<Option<CognitoEventUserPoolsChallengeResult>>
-    let session: &[Option<CognitoEventUserPoolsChallengeResult>] = &event.request.session;
-
-    // Have we ever issued a CUSTOM_CHALLENGE?
-    let has_custom_challenge = session
-        .iter()
-        .filter_map(|o| o.as_ref())
-        .any(|r| r.challenge_name.as_deref() == Some(CUSTOM));
-
-    // Find the most recent CUSTOM_CHALLENGE entry (if any)
-    let last_custom = session
-        .iter()
-        .rev()
-        .filter_map(|o| o.as_ref())
-        .find(|r| r.challenge_name.as_deref() == Some(CUSTOM));
-
-    // Debug session analysis
-    //info!("Session analysis:");
-    //info!("  - has_custom_challenge: {}", has_custom_challenge);
-    //info!("  - last_custom challenge_result: {:?}", last_custom.and_then(|r| Some(r.challenge_result)));
-    //info!("  - Session entries count: {}", session.len());
-    /* 
-    for (i, entry) in session.iter().enumerate() {
-        if let Some(entry) = entry {
-            info!("  - Session[{}]: challenge_name={:?}, challenge_result={:?}", 
-                  i, entry.challenge_name, entry.challenge_result);
-        }
-    }
-    */
-
-    match (
-        has_custom_challenge,
-        last_custom.and_then(|r| Some(r.challenge_result)),
-    ) {
-        // First time — issue a custom challenge
-        (false, _) => {
-            info!("🔄 BRANCH: Issuing first {CUSTOM} for email: {}", email);
+    match next_step(&event.request.session, attempts) {
+        // First time, or a wrong answer with attempts left — issue a custom challenge
+        NextStep::Challenge => {
+            info!("🔄 BRANCH: Issuing {CUSTOM} for email: {} ({} answers so far)", email, event.request.session.len());
             event.response.challenge_name = Some(CUSTOM.to_string());
             event.response.issue_tokens = false;
             event.response.fail_authentication = false;
@@ -113,7 +122,7 @@ async fn handle_define_challenge(
         // Last CUSTOM_CHALLENGE succeeded — issue tokens
         // We trust that verify-auth-challenge properly validated the OTP and set email_verified=true
         // Don't rely on event attributes due to timing issues
-        (true, Some(true)) => {
+        NextStep::IssueTokens => {
             info!("🎉 BRANCH: Previous {CUSTOM} succeeded for {}; issuing tokens (trusting OTP verification)", email);
             info!("Note: Event attributes may be stale due to timing, but OTP was verified successfully");
             event.response.challenge_name = None;
@@ -122,25 +131,14 @@ async fn handle_define_challenge(
             info!("✅ SET: issue_tokens=true, fail_authentication=false");
         }
 
-        // Last CUSTOM_CHALLENGE failed — fail auth (your chosen policy)
-        (true, Some(false)) => {
-            info!("❌ BRANCH: Previous {CUSTOM} failed; failing auth for {}", email);
+        // Out of attempts, or the challenge couldn't be issued — fail auth
+        NextStep::Fail => {
+            info!("❌ BRANCH: Previous {CUSTOM} failed with no attempts left; failing auth for {}", email);
             event.response.challenge_name = None;
             event.response.issue_tokens = false;
             event.response.fail_authentication = true;
             info!("✅ SET: issue_tokens=false, fail_authentication=true");
         }
-
-        // Unexpected/missing state — safe fail
-        _ => {
-            error!("⚠️ BRANCH: Unexpected challenge state for {}", email);
-            error!("Session state: has_custom_challenge={}, last_result={:?}", 
-                   has_custom_challenge, last_custom.and_then(|r| Some(r.challenge_result)));
-            event.response.challenge_name = None;
-            event.response.issue_tokens = false;
-            event.response.fail_authentication = true;
-            info!("✅ SET: issue_tokens=false, fail_authentication=true (fallback)");
-        }
     }
 
     // Final response logging
@@ -160,5 +158,100 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
-    run(service_fn(function_handler)).await
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(correct: bool, metadata: &str) -> Option<CognitoEventUserPoolsChallengeResult> {
+        Some(CognitoEventUserPoolsChallengeResult {
+            challenge_name: Some(CUSTOM.to_string()),
+            challenge_result: correct,
+            challenge_metadata: Some(metadata.to_string()),
+        })
+    }
+
+    fn wrong() -> Option<CognitoEventUserPoolsChallengeResult> {
+        answer(false, "OTP_EMAIL_SENT:challenge-1")
+    }
+
+    #[test]
+    fn test_wrong_answers_are_retried_up_to_the_limit() {
+        let attempts = ChallengeAttemptPolicy { max_attempts: 3 };
+
+        assert_eq!(next_step(&[], &attempts), NextStep::Challenge);
+        assert_eq!(next_step(&[wrong()], &attempts), NextStep::Challenge);
+        assert_eq!(next_step(&[wrong(), wrong()], &attempts), NextStep::Challenge);
+        assert_eq!(next_step(&[wrong(), wrong(), wrong()], &attempts), NextStep::Fail);
+
+        let correct = answer(true, "OTP_EMAIL_SENT:challenge-1");
+        assert_eq!(next_step(&[wrong(), wrong(), correct], &attempts), NextStep::IssueTokens);
+    }
+
+    #[test]
+    fn test_single_attempt_fails_on_first_wrong_answer() {
+        let attempts = ChallengeAttemptPolicy::single_attempt();
+
+        assert_eq!(next_step(&[], &attempts), NextStep::Challenge);
+        assert_eq!(next_step(&[wrong()], &attempts), NextStep::Fail);
+        assert_eq!(
+            next_step(&[answer(true, "OTP_EMAIL_SENT:challenge-1")], &attempts),
+            NextStep::IssueTokens
+        );
+    }
+
+    #[test]
+    fn test_challenge_that_was_never_issued_is_not_retried() {
+        let attempts = ChallengeAttemptPolicy::default();
+        assert_eq!(next_step(&[answer(false, ChallengeMetadata::ERROR)], &attempts), NextStep::Fail);
+    }
+
+    #[tokio::test]
+    async fn test_handler_reissues_challenge_after_wrong_answer() {
+        let context = AppContext {
+            attempts: ChallengeAttemptPolicy::default(),
+            capture: EventCapture::disabled("define-auth-challenge"),
+        };
+        let mut event = CognitoEventUserPoolsDefineAuthChallenge::default();
+        event.request.session = vec![wrong()];
+
+        let response = function_handler(&context, LambdaEvent::new(event, Default::default()))
+            .await
+            .unwrap()
+            .response;
+        assert_eq!(response.challenge_name.as_deref(), Some(CUSTOM));
+        assert!(!response.issue_tokens);
+        assert!(!response.fail_authentication);
+    }
+
+    /// Replays the captured events in fixtures/define-auth-challenge with the default policy
+    #[tokio::test]
+    async fn test_replay_captured_events() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/define-auth-challenge");
+        let context = AppContext {
+            attempts: ChallengeAttemptPolicy::default(),
+            capture: EventCapture::disabled("define-auth-challenge"),
+        };
+
+        let replayed = auth_shared::replay_fixtures(&fixtures, |event| {
+            let context = &context;
+            async move {
+                let event = serde_json::from_value(event).unwrap();
+                let response =
+                    function_handler(context, LambdaEvent::new(event, Default::default()))
+                        .await
+                        .unwrap()
+                        .response;
+                serde_json::to_value(response).unwrap()
+            }
+        })
+        .await
+        .unwrap_or_else(|failures| panic!("{}", failures));
+        assert!(replayed > 0);
+    }
 }
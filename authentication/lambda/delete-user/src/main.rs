@@ -0,0 +1,127 @@
+use aws_config::BehaviorVersion;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use tracing::{error, info};
+
+use auth_shared::{
+    sub_addressing_from_env, AccountDeletionService, AuthResult, DeletionReport, DeletionTarget,
+    SubAddressing,
+};
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    sub_addressing: SubAddressing,
+    deletion: AccountDeletionService,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let deletion = AccountDeletionService::from_env(
+            aws_sdk_dynamodb::Client::new(&config),
+            aws_sdk_cognitoidentityprovider::Client::new(&config),
+        )?;
+        Ok(Self {
+            sub_addressing: sub_addressing_from_env()?,
+            deletion,
+        })
+    }
+}
+
+/// Erase an account, invoked directly by an operator with `{"email": ...}` or `{"user_id": ...}`.
+/// Safe to invoke again if a previous run failed part-way.
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<DeletionTarget>,
+) -> Result<DeletionReport, Error> {
+    // Accounts are keyed by canonical email, so apply the same sub-addressing policy as sign-in
+    let target = match event.payload {
+        DeletionTarget::Email(email) => {
+            DeletionTarget::Email(email.with_sub_addressing(context.sub_addressing))
+        }
+        target => target,
+    };
+
+    info!("Account deletion request: {:?}", target);
+
+    match context.deletion.delete_account(&target).await {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error!("Account deletion failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{
+        EmailAddress, InMemoryIdentityProvider, InMemoryOtpStore, InMemoryRateLimitStore,
+        InMemorySessionStore, InMemoryUserRepository, SessionPolicy, SessionService, UserRepository,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn email(address: &str) -> EmailAddress {
+        EmailAddress::parse(address).unwrap()
+    }
+
+    #[test]
+    fn test_request_shapes() {
+        let target: DeletionTarget =
+            serde_json::from_value(json!({ "email": "Creator@Example.com" })).unwrap();
+        assert_eq!(target, DeletionTarget::Email(email("creator@example.com")));
+
+        let target: DeletionTarget = serde_json::from_value(json!({ "user_id": "user-1" })).unwrap();
+        assert_eq!(target, DeletionTarget::UserId("user-1".to_string()));
+
+        assert!(serde_json::from_value::<DeletionTarget>(json!({ "username": "x" })).is_err());
+        assert!(serde_json::from_value::<DeletionTarget>(json!({ "email": "not-an-email" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_report_shape() {
+        let users = Arc::new(InMemoryUserRepository::new());
+        users.create_user(&email("creator@example.com"), "user-1").await.unwrap();
+        let service = AccountDeletionService::new(
+            users,
+            Arc::new(InMemoryOtpStore::new()),
+            Arc::new(InMemoryRateLimitStore::new()),
+            SessionService::new(Arc::new(InMemorySessionStore::new()), SessionPolicy::default()),
+            Arc::new(InMemoryIdentityProvider::new()),
+            SubAddressing::default(),
+        );
+
+        let report = service
+            .delete_account(&DeletionTarget::Email(email("creator@example.com")))
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(report).unwrap(),
+            json!({
+                "user_id": "user-1",
+                "email": "creator@example.com",
+                "user_deleted": true,
+                "otp_deleted": false,
+                "rate_limits_deleted": 0,
+                "sessions_deleted": 0,
+                "identities_deleted": 1
+            })
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use aws_config::BehaviorVersion;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use auth_shared::{
+    sub_addressing_from_env, AuthResult, DataExportService, EmailAddress, SubAddressing,
+    UserDataExport,
+};
+
+/// Subject access request, invoked directly by an operator
+#[derive(Debug, Deserialize)]
+struct ExportUserDataRequest {
+    email: String,
+}
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    sub_addressing: SubAddressing,
+    exports: DataExportService,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        Ok(Self {
+            sub_addressing: sub_addressing_from_env()?,
+            exports: DataExportService::from_env(aws_sdk_dynamodb::Client::new(&config))?,
+        })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<ExportUserDataRequest>,
+) -> Result<UserDataExport, Error> {
+    let request = event.payload;
+
+    info!("Data export request for: {}", request.email);
+    let email = EmailAddress::parse_with(&request.email, context.sub_addressing)?;
+
+    match context.exports.export_user_data(&email).await {
+        Ok(export) => Ok(export),
+        Err(e) => {
+            error!("Data export failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{
+        InMemoryOtpStore, InMemoryRateLimitStore, InMemorySessionStore, InMemoryUserRepository,
+        UserRepository,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_export_bundle_shape() {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let email = EmailAddress::parse("creator@example.com").unwrap();
+        users.create_user(&email, "user-1").await.unwrap();
+        let service = DataExportService::new(
+            users,
+            Arc::new(InMemoryOtpStore::new()),
+            Arc::new(InMemoryRateLimitStore::new()),
+            Arc::new(InMemorySessionStore::new()),
+        );
+
+        let request: ExportUserDataRequest =
+            serde_json::from_value(json!({ "email": "creator@example.com" })).unwrap();
+        let export = service
+            .export_user_data(&EmailAddress::parse(&request.email).unwrap())
+            .await
+            .unwrap();
+        let bundle = serde_json::to_value(export).unwrap();
+
+        assert_eq!(bundle["export_version"], 2);
+        assert_eq!(bundle["email"], "creator@example.com");
+        assert_eq!(bundle["user"]["user_id"], "user-1");
+        assert_eq!(bundle["user"]["status"], "REGISTRATION_EMAIL_NOT_VERIFIED");
+        for key in ["exported_at", "status_history", "sessions", "pending_otp", "rate_limits", "notifications"] {
+            assert!(bundle.get(key).is_some(), "{}", key);
+        }
+    }
+}
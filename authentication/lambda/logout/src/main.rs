@@ -0,0 +1,207 @@
+use aws_config::BehaviorVersion;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use auth_shared::{AuthError, AuthResult, CognitoService, IdentityProvider, SessionService};
+
+/// Logout request, invoked directly by the webapp with the caller's session cookie value
+#[derive(Debug, Deserialize)]
+struct LogoutRequest {
+    session_id: String,
+    /// Sign out everywhere: revoke every session for the user and their Cognito tokens
+    #[serde(default)]
+    all_sessions: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct LogoutResponse {
+    revoked_sessions: usize,
+    global_sign_out: bool,
+}
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    sessions: SessionService,
+    identity: CognitoService,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        Ok(Self {
+            sessions: SessionService::from_env(aws_sdk_dynamodb::Client::new(&config))?,
+            identity: CognitoService::from_env(aws_sdk_cognitoidentityprovider::Client::new(
+                &config,
+            ))?,
+        })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<LogoutRequest>,
+) -> Result<LogoutResponse, Error> {
+    let request = event.payload;
+
+    info!("Logout requested (all_sessions: {})", request.all_sessions);
+
+    match logout(&request, &context.sessions, &context.identity).await {
+        Ok(response) => {
+            info!("Logout complete: {:?}", response);
+            Ok(response)
+        }
+        Err(e) => {
+            error!("Logout failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Remove the caller's session, or with `all_sessions` sign the user out of Cognito and
+/// remove every one of their sessions so other devices are cut off
+async fn logout(
+    request: &LogoutRequest,
+    sessions: &SessionService,
+    identity: &dyn IdentityProvider,
+) -> AuthResult<LogoutResponse> {
+    if !request.all_sessions {
+        let revoked = sessions.revoke_session(&request.session_id).await?;
+        return Ok(LogoutResponse {
+            revoked_sessions: usize::from(revoked),
+            global_sign_out: false,
+        });
+    }
+
+    // Signing out everywhere requires a live session to identify the user
+    let session = sessions
+        .validate_session(&request.session_id)
+        .await?
+        .ok_or_else(|| AuthError::InvalidSession("Session not found or expired".to_string()))?;
+
+    // Revoke Cognito tokens first so no device can mint a new session after we clear them,
+    // and so a Cognito failure leaves the caller's session in place to retry with. The Cognito
+    // username is the sub, which is the session's user_id.
+    identity.global_sign_out(&session.user_id).await?;
+    let revoked_sessions = sessions.revoke_user_sessions(&session.user_id).await?;
+
+    Ok(LogoutResponse {
+        revoked_sessions,
+        global_sign_out: true,
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{
+        current_timestamp, InMemoryIdentityProvider, InMemorySessionStore, Session, SessionPolicy,
+        SessionStore,
+    };
+    use std::sync::Arc;
+
+    fn seed(store: &InMemorySessionStore, session_id: &str, user_id: &str) {
+        let now = current_timestamp();
+        store.insert_session(Session {
+            session_id: session_id.to_string(),
+            user_id: user_id.to_string(),
+            email: format!("{}@example.com", user_id),
+            user_status: "ACTIVE".to_string(),
+            given_name: None,
+            family_name: None,
+            created_at: now,
+            last_accessed: now,
+            expires_at: now + 20 * 60,
+            ip_address: None,
+            user_agent: None,
+        });
+    }
+
+    fn setup() -> (Arc<InMemorySessionStore>, SessionService, InMemoryIdentityProvider) {
+        let store = Arc::new(InMemorySessionStore::new());
+        seed(&store, "laptop", "user-1");
+        seed(&store, "phone", "user-1");
+        seed(&store, "other", "user-2");
+        let sessions = SessionService::new(store.clone(), SessionPolicy::default());
+        (store, sessions, InMemoryIdentityProvider::new())
+    }
+
+    fn request(session_id: &str, all_sessions: bool) -> LogoutRequest {
+        LogoutRequest {
+            session_id: session_id.to_string(),
+            all_sessions,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logout_removes_only_current_session() {
+        let (store, sessions, identity) = setup();
+
+        let response = logout(&request("laptop", false), &sessions, &identity)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response,
+            LogoutResponse {
+                revoked_sessions: 1,
+                global_sign_out: false
+            }
+        );
+        assert!(store.get_session("laptop").await.unwrap().is_none());
+        assert!(store.get_session("phone").await.unwrap().is_some());
+        assert!(identity.signed_out_users().is_empty());
+
+        // Logging out again is harmless
+        let response = logout(&request("laptop", false), &sessions, &identity)
+            .await
+            .unwrap();
+        assert_eq!(response.revoked_sessions, 0);
+    }
+
+    #[tokio::test]
+    async fn test_logout_everywhere_revokes_all_user_sessions() {
+        let (store, sessions, identity) = setup();
+
+        let response = logout(&request("laptop", true), &sessions, &identity)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response,
+            LogoutResponse {
+                revoked_sessions: 2,
+                global_sign_out: true
+            }
+        );
+        assert!(store.get_session("laptop").await.unwrap().is_none());
+        assert!(store.get_session("phone").await.unwrap().is_none());
+        assert!(store.get_session("other").await.unwrap().is_some());
+        assert_eq!(identity.signed_out_users(), vec!["user-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_logout_everywhere_requires_valid_session() {
+        let (_store, sessions, identity) = setup();
+
+        let result = logout(&request("missing", true), &sessions, &identity).await;
+
+        assert!(matches!(result, Err(AuthError::InvalidSession(_))));
+        assert!(identity.signed_out_users().is_empty());
+    }
+}
@@ -1,36 +1,69 @@
+use aws_config::BehaviorVersion;
 use aws_lambda_events::event::cognito::CognitoEventUserPoolsPreSignup;
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+use auth_shared::{AuthError, AuthResult, EventCapture, SignupPolicyService, SignupRejection};
+
+/// Sign-up policy shipped with the function; lists in the sign-up policy table are added on top
+const SIGNUP_POLICY: &str = include_str!("../signup-policy.json");
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    signup_policy: SignupPolicyService,
+    capture: EventCapture,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let signup_policy =
+            SignupPolicyService::from_env(aws_sdk_dynamodb::Client::new(&config), SIGNUP_POLICY)?;
+        Ok(Self {
+            signup_policy,
+            capture: EventCapture::from_env("pre-signup", aws_sdk_s3::Client::new(&config))?,
+        })
+    }
+}
 
 async fn function_handler(
+    context: &AppContext,
     event: LambdaEvent<CognitoEventUserPoolsPreSignup>,
 ) -> Result<CognitoEventUserPoolsPreSignup, Error> {
+    context.capture.capture(&event.payload).await;
     let mut response_event = event.payload;
 
-    match handle_pre_signup(&mut response_event).await {
+    match handle_pre_signup(&mut response_event, &context.signup_policy).await {
         Ok(_) => {
             info!("Successfully handled pre-signup");
             Ok(response_event)
         }
+        Err(AuthError::SignupRejected(rejection)) => {
+            // Cognito passes the message through to the client, so lead with the code
+            warn!("Sign-up rejected: {}", rejection.code());
+            Err(rejection.to_string().into())
+        }
         Err(e) => {
+            // Fail closed: a sign-up we couldn't check is one we haven't allowed
             error!("Failed to handle pre-signup: {}", e);
-            // For pre-signup, we should allow the signup to continue
-            // even if there are errors, to avoid blocking user registration
-            Ok(response_event)
+            Err(e.into())
         }
     }
 }
 
 async fn handle_pre_signup(
     event: &mut CognitoEventUserPoolsPreSignup,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Extract email from user attributes
-    let email = event
+    signup_policy: &SignupPolicyService,
+) -> AuthResult<()> {
+    // Passwordless sign-up uses the email as the username, so fall back to it
+    let raw_email = event
         .request
         .user_attributes
         .get("email")
-        .ok_or("Email not found in user attributes")?;
+        .or(event.cognito_event_user_pools_header.user_name.as_ref())
+        .ok_or(AuthError::SignupRejected(SignupRejection::InvalidEmail))?;
 
+    let email = signup_policy.check_signup(raw_email).await?;
     info!("Pre-signup trigger for email: {}", email);
 
     // Auto-confirm the user for passwordless auth
@@ -51,5 +84,107 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
-    run(service_fn(function_handler)).await
-}
\ No newline at end of file
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{EmailAddress, SignupMode, SignupPolicyDocument, SubAddressing};
+
+    fn service(environment: &str) -> SignupPolicyService {
+        let policy = SignupPolicyDocument::parse(SIGNUP_POLICY)
+            .unwrap()
+            .for_environment(environment)
+            .unwrap();
+        SignupPolicyService::new(policy, None, SubAddressing::Preserve)
+    }
+
+    fn event(email: Option<&str>, user_name: &str) -> CognitoEventUserPoolsPreSignup {
+        let mut event = CognitoEventUserPoolsPreSignup::default();
+        event.cognito_event_user_pools_header.user_name = Some(user_name.to_string());
+        if let Some(email) = email {
+            event.request.user_attributes.insert("email".to_string(), email.to_string());
+        }
+        event
+    }
+
+    async fn rejection(environment: &str, mut event: CognitoEventUserPoolsPreSignup) -> Option<String> {
+        match handle_pre_signup(&mut event, &service(environment)).await {
+            Ok(()) => {
+                assert!(event.response.auto_confirm_user);
+                None
+            }
+            Err(AuthError::SignupRejected(rejection)) => {
+                assert!(!event.response.auto_confirm_user);
+                Some(rejection.code().to_string())
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_bundled_policy_parses_for_every_environment() {
+        let document = SignupPolicyDocument::parse(SIGNUP_POLICY).unwrap();
+        for environment in ["dev", "test", "prod"] {
+            let policy = document.for_environment(environment).unwrap();
+            let expected = if environment == "dev" { SignupMode::Allowlist } else { SignupMode::Open };
+            assert_eq!(policy.mode, expected, "{}", environment);
+
+            let disposable = EmailAddress::parse("someone@mailinator.com").unwrap();
+            assert_eq!(policy.check(&disposable), Err(SignupRejection::EmailNotAllowed));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_environment_auto_confirms_allowed_email() {
+        assert_eq!(rejection("prod", event(Some("Ada@Example.com"), "ada@example.com")).await, None);
+        // The username stands in when the email attribute is missing
+        assert_eq!(rejection("prod", event(None, "ada@example.com")).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_rejections_carry_their_codes() {
+        assert_eq!(
+            rejection("prod", event(Some("spam@mailinator.com"), "spam@mailinator.com")).await.as_deref(),
+            Some("EMAIL_NOT_ALLOWED")
+        );
+        assert_eq!(
+            rejection("prod", event(None, "not-an-email")).await.as_deref(),
+            Some("INVALID_EMAIL")
+        );
+        assert_eq!(
+            rejection("dev", event(Some("ada@example.com"), "ada@example.com")).await.as_deref(),
+            Some("SIGNUP_INVITE_ONLY")
+        );
+        assert_eq!(rejection("dev", event(Some("team@appreciata.com"), "team@appreciata.com")).await, None);
+    }
+
+    /// Replays the captured events in fixtures/pre-signup against the bundled prod policy
+    #[tokio::test]
+    async fn test_replay_captured_events() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/pre-signup");
+        let context = AppContext {
+            signup_policy: service("prod"),
+            capture: EventCapture::disabled("pre-signup"),
+        };
+
+        let replayed = auth_shared::replay_fixtures(&fixtures, |event| {
+            let context = &context;
+            async move {
+                let event = serde_json::from_value(event).unwrap();
+                match function_handler(context, LambdaEvent::new(event, Default::default())).await {
+                    Ok(event) => serde_json::to_value(event.response).unwrap(),
+                    // Cognito shows the rejection message to the client
+                    Err(e) => serde_json::json!({ "error": e.to_string() }),
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|failures| panic!("{}", failures));
+        assert!(replayed > 0);
+    }
+}
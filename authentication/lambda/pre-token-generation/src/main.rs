@@ -0,0 +1,309 @@
+use aws_config::BehaviorVersion;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::{error, info};
+
+use auth_shared::{
+    sub_addressing_from_env, AuthError, AuthResult, DynamoDBService, EmailAddress, EventCapture,
+    PlatformClaims, SubAddressing, UserProfile, UserRepository,
+};
+use std::sync::Arc;
+
+// aws_lambda_events only models the V1 event, which can't touch the access token, so the V2
+// event is defined here. Fields we don't use are passed through untouched.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupConfiguration {
+    #[serde(default)]
+    pub groups_to_override: Vec<String>,
+    #[serde(default)]
+    pub iam_roles_to_override: Vec<String>,
+    pub preferred_role: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreTokenGenerationRequest {
+    pub user_attributes: HashMap<String, String>,
+    #[serde(default)]
+    pub group_configuration: GroupConfiguration,
+    pub scopes: Option<Vec<String>>,
+    pub client_metadata: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenGeneration {
+    pub claims_to_add_or_override: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClaimsAndScopeOverrideDetails {
+    pub id_token_generation: Option<TokenGeneration>,
+    pub access_token_generation: Option<TokenGeneration>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreTokenGenerationResponse {
+    pub claims_and_scope_override_details: Option<ClaimsAndScopeOverrideDetails>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreTokenGenerationEvent {
+    pub version: String,
+    pub region: String,
+    pub user_pool_id: String,
+    pub user_name: String,
+    pub caller_context: HashMap<String, Value>,
+    pub trigger_source: String,
+    pub request: PreTokenGenerationRequest,
+    pub response: PreTokenGenerationResponse,
+}
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    users: Arc<dyn UserRepository>,
+    sub_addressing: SubAddressing,
+    capture: EventCapture,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let users = DynamoDBService::from_env(aws_sdk_dynamodb::Client::new(&config))?;
+        Ok(Self {
+            users: Arc::new(users),
+            sub_addressing: sub_addressing_from_env()?,
+            capture: EventCapture::from_env("pre-token-generation", aws_sdk_s3::Client::new(&config))?,
+        })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<PreTokenGenerationEvent>,
+) -> Result<PreTokenGenerationEvent, Error> {
+    context.capture.capture(&event.payload).await;
+    let mut response_event = event.payload;
+
+    info!("User: {}", response_event.user_name);
+    info!("Trigger source: {}", response_event.trigger_source);
+
+    let users = context.users.as_ref();
+    match handle_pre_token_generation(&mut response_event, users, context.sub_addressing).await {
+        Ok(()) => Ok(response_event),
+        Err(e) => {
+            // Fail closed: downstream services trust these claims, so a token without them
+            // must not be issued
+            error!("Failed to add platform claims: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Add the platform claims to both the ID and access tokens. Runs on sign-in and on every
+/// refresh, so a status change reaches the caller's tokens at the next refresh.
+async fn handle_pre_token_generation(
+    event: &mut PreTokenGenerationEvent,
+    users: &dyn UserRepository,
+    sub_addressing: SubAddressing,
+) -> AuthResult<()> {
+    let user = find_user(event, users, sub_addressing).await?;
+
+    let claims = PlatformClaims::new(&user, &event.request.group_configuration.groups_to_override);
+    info!(
+        "Issuing tokens for {} with status {} and role {}",
+        claims.user_id,
+        claims.status.as_str(),
+        claims.role.as_str()
+    );
+
+    let claims = claims.to_claims();
+    event.response.claims_and_scope_override_details = Some(ClaimsAndScopeOverrideDetails {
+        id_token_generation: Some(TokenGeneration {
+            claims_to_add_or_override: claims.clone(),
+        }),
+        access_token_generation: Some(TokenGeneration {
+            claims_to_add_or_override: claims,
+        }),
+    });
+    Ok(())
+}
+
+/// The users row behind the Cognito user. The Cognito user_name is the sub, which
+/// create-auth-challenge uses as the user_id of the row it creates; Cognito users that share
+/// that row's canonical email (a sub-address under the strip policy, or another spelling of an
+/// internationalised domain) are found by their email instead.
+async fn find_user(
+    event: &PreTokenGenerationEvent,
+    users: &dyn UserRepository,
+    sub_addressing: SubAddressing,
+) -> AuthResult<UserProfile> {
+    if let Some(user) = users.get_user_by_id(&event.user_name).await? {
+        return Ok(user);
+    }
+
+    let not_found = || AuthError::UserNotFound(event.user_name.clone());
+    let raw_email = event.request.user_attributes.get("email").ok_or_else(not_found)?;
+    let email = EmailAddress::parse_with(raw_email, sub_addressing)?;
+    users.get_user_by_email(&email).await?.ok_or_else(not_found)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    info!("Starting pre-token-generation Lambda function");
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{EmailAddress, InMemoryUserRepository, UserStatus};
+    use serde_json::json;
+
+    fn event(user_name: &str, groups: &[&str]) -> PreTokenGenerationEvent {
+        event_for(user_name, "creator@example.com", groups)
+    }
+
+    fn event_for(user_name: &str, email: &str, groups: &[&str]) -> PreTokenGenerationEvent {
+        serde_json::from_value(json!({
+            "version": "2",
+            "triggerSource": "TokenGeneration_Authentication",
+            "region": "eu-west-2",
+            "userPoolId": "eu-west-2_example",
+            "userName": user_name,
+            "callerContext": { "awsSdkVersion": "aws-sdk-unknown-unknown", "clientId": "client-1" },
+            "request": {
+                "userAttributes": { "sub": user_name, "email": email },
+                "groupConfiguration": {
+                    "groupsToOverride": groups,
+                    "iamRolesToOverride": [],
+                    "preferredRole": null
+                },
+                "scopes": ["aws.cognito.signin.user.admin"]
+            },
+            "response": { "claimsAndScopeOverrideDetails": null }
+        }))
+        .unwrap()
+    }
+
+    async fn users() -> InMemoryUserRepository {
+        let users = InMemoryUserRepository::new();
+        let email = EmailAddress::parse("creator@example.com").unwrap();
+        users.create_user(&email, "cognito-sub-1").await.unwrap();
+        users
+            .transition_status(
+                "cognito-sub-1",
+                UserStatus::RegistrationEmailNotVerified,
+                UserStatus::RegistrationNeedUserInfo,
+                "test",
+            )
+            .await
+            .unwrap();
+        users
+    }
+
+    #[tokio::test]
+    async fn test_claims_added_to_both_tokens() {
+        let users = users().await;
+        let mut event = event("cognito-sub-1", &["admins"]);
+        handle_pre_token_generation(&mut event, &users, SubAddressing::default()).await.unwrap();
+
+        let response = serde_json::to_value(&event.response).unwrap();
+        let details = &response["claimsAndScopeOverrideDetails"];
+        for token in ["idTokenGeneration", "accessTokenGeneration"] {
+            let claims = &details[token]["claimsToAddOrOverride"];
+            assert_eq!(claims["appre:user_id"], "cognito-sub-1", "{}", token);
+            assert_eq!(claims["appre:status"], "REGISTRATION_NEED_USER_INFO", "{}", token);
+            assert_eq!(claims["appre:role"], "admin", "{}", token);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_members_of_no_group_are_creators() {
+        let users = users().await;
+        let mut event = event("cognito-sub-1", &[]);
+        handle_pre_token_generation(&mut event, &users, SubAddressing::default()).await.unwrap();
+
+        let details = event.response.claims_and_scope_override_details.unwrap();
+        let claims = details.access_token_generation.unwrap().claims_to_add_or_override;
+        assert_eq!(claims["appre:role"], "creator");
+    }
+
+    #[tokio::test]
+    async fn test_sub_sharing_another_subs_row_is_found_by_email() {
+        let users = users().await;
+        // A second Cognito user whose address canonicalises to the row cognito-sub-1 created
+        let mut event = event_for("cognito-sub-2", "creator+tips@example.com", &[]);
+        handle_pre_token_generation(&mut event, &users, SubAddressing::Strip)
+            .await
+            .unwrap();
+
+        let details = event.response.claims_and_scope_override_details.unwrap();
+        let claims = details.id_token_generation.unwrap().claims_to_add_or_override;
+        assert_eq!(claims["appre:user_id"], "cognito-sub-1");
+        assert_eq!(claims["appre:status"], "REGISTRATION_NEED_USER_INFO");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_user_fails_closed() {
+        let users = users().await;
+        let mut event = event_for("cognito-sub-2", "someone@example.com", &[]);
+        let result = handle_pre_token_generation(&mut event, &users, SubAddressing::default()).await;
+        assert!(matches!(result, Err(AuthError::UserNotFound(_))));
+        assert!(event.response.claims_and_scope_override_details.is_none());
+    }
+
+    /// Replays the captured events in fixtures/pre-token-generation, each for a user seeded from
+    /// the event's userName and email part way through registration
+    #[tokio::test]
+    async fn test_replay_captured_events() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/pre-token-generation");
+
+        let replayed = auth_shared::replay_fixtures(&fixtures, |event| async move {
+            let event: PreTokenGenerationEvent = serde_json::from_value(event).unwrap();
+            let users = InMemoryUserRepository::new();
+            let email = EmailAddress::parse(&event.request.user_attributes["email"]).unwrap();
+            users.create_user(&email, &event.user_name).await.unwrap();
+            users
+                .transition_status(
+                    &event.user_name,
+                    UserStatus::RegistrationEmailNotVerified,
+                    UserStatus::RegistrationNeedUserInfo,
+                    "test",
+                )
+                .await
+                .unwrap();
+            let context = AppContext {
+                users: Arc::new(users),
+                sub_addressing: SubAddressing::default(),
+                capture: EventCapture::disabled("pre-token-generation"),
+            };
+
+            match function_handler(&context, LambdaEvent::new(event, Default::default())).await {
+                Ok(event) => serde_json::to_value(event.response).unwrap(),
+                Err(e) => json!({ "error": e.to_string() }),
+            }
+        })
+        .await
+        .unwrap_or_else(|failures| panic!("{}", failures));
+        assert!(replayed > 0);
+    }
+}
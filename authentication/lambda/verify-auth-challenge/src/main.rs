@@ -1,17 +1,72 @@
 use aws_config::BehaviorVersion;
+use aws_sdk_cognitoidentityprovider::Client as CognitoClient;
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::{error, info, warn};
 
-use auth_shared::{current_timestamp, verify_otp, AuthError, AuthResult, DynamoDBService};
+use auth_shared::{
+    current_timestamp, sub_addressing_from_env, AuthError, AuthResult, ChallengeType,
+    DynamoDBService, EmailAddress, EmailVerificationService, EventCapture, OtpHasher, OtpPolicy, OtpStore,
+    RateLimitAction, RateLimitService, RateLimitStore, SubAddressing,
+};
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    sub_addressing: SubAddressing,
+    policy: OtpPolicy,
+    hasher: OtpHasher,
+    rate_limits: Arc<dyn RateLimitStore>,
+    otp_store: Arc<dyn OtpStore>,
+    verification: EmailVerificationService,
+    cognito_client: CognitoClient,
+    capture: EventCapture,
+}
+
+impl AppContext {
+    async fn from_env() -> AuthResult<Self> {
+        // Initialize AWS clients
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
+
+        // Initialize services using naming utilities; one DynamoDBService backs both the OTP
+        // store and the user repository
+        let rate_limit_service = RateLimitService::from_env(dynamodb_client.clone())?;
+        let dynamodb_service = Arc::new(DynamoDBService::from_env(dynamodb_client)
+            .map_err(|e| AuthError::InternalError(format!("Failed to initialize DynamoDBService: {}", e)))?);
+        let verification = EmailVerificationService::from_env(
+            dynamodb_service.clone(),
+            aws_sdk_sqs::Client::new(&config),
+        )?;
+
+        Ok(Self {
+            sub_addressing: sub_addressing_from_env()?,
+            policy: OtpPolicy::from_env()?,
+            hasher: OtpHasher::from_env()?,
+            rate_limits: Arc::new(rate_limit_service),
+            otp_store: dynamodb_service,
+            verification,
+            cognito_client: CognitoClient::new(&config),
+            capture: EventCapture::from_env("verify-auth-challenge", aws_sdk_s3::Client::new(&config))?,
+        })
+    }
+}
+
+/// The challenge Cognito issued for this session, as recorded by create-auth-challenge
+struct PendingChallenge<'a> {
+    email: &'a EmailAddress,
+    challenge_id: &'a str,
+    challenge_type: ChallengeType,
+}
 
 // Custom structs to handle Cognito's null values properly
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct CognitoVerifyAuthChallengeRequest {
     pub user_attributes: HashMap<String, String>,
+    pub private_challenge_parameters: Option<HashMap<String, String>>,
     pub challenge_answer: Option<String>,
     pub client_metadata: Option<HashMap<String, String>>,
 }
@@ -36,15 +91,17 @@ struct CognitoVerifyAuthChallengeEvent {
 }
 
 async fn function_handler(
+    context: &AppContext,
     event: LambdaEvent<CognitoVerifyAuthChallengeEvent>,
 ) -> Result<CognitoVerifyAuthChallengeEvent, Error> {
+    context.capture.capture(&event.payload).await;
     let mut response_event = event.payload;
 
     info!("Received verify auth challenge event");
     info!("User: {}", response_event.user_name);
     info!("Trigger source: {}", response_event.trigger_source);
 
-    let is_correct = match handle_verify_challenge(&response_event).await {
+    let is_correct = match handle_verify_challenge(&response_event, context).await {
         Ok(result) => {
             info!("Challenge verification result: {}", result);
             result
@@ -68,9 +125,12 @@ async fn function_handler(
 
 
 
-async fn handle_verify_challenge(event: &CognitoVerifyAuthChallengeEvent) -> AuthResult<bool> {
+async fn handle_verify_challenge(
+    event: &CognitoVerifyAuthChallengeEvent,
+    context: &AppContext,
+) -> AuthResult<bool> {
     // Extract email from user attributes or client metadata
-    let email = if let Some(email) = event.request.user_attributes.get("email") {
+    let raw_email = if let Some(email) = event.request.user_attributes.get("email") {
         email
     } else if let Some(client_metadata) = &event.request.client_metadata {
         if let Some(email) = client_metadata.get("email") {
@@ -91,24 +151,122 @@ async fn handle_verify_challenge(event: &CognitoVerifyAuthChallengeEvent) -> Aut
             AuthError::ValidationError("Challenge answer not provided".to_string())
         })?;
 
+    // The answer must belong to the challenge issued for this Cognito session
+    let private_params = event.request.private_challenge_parameters.as_ref();
+    let challenge_id = private_params
+        .and_then(|params| params.get("challenge_id"))
+        .ok_or_else(|| {
+            AuthError::ValidationError(
+                "Challenge ID not found in private challenge parameters".to_string(),
+            )
+        })?;
+    // Sessions started before magic links existed carry no challenge type
+    let challenge_type = match private_params.and_then(|params| params.get("challenge_type")) {
+        Some(value) => value.parse::<ChallengeType>()?,
+        None => ChallengeType::default(),
+    };
+    // Keyed exactly as create-auth-challenge keyed the OTP
+    let email = EmailAddress::parse_with(raw_email, context.sub_addressing)?;
+    let challenge = PendingChallenge {
+        email: &email,
+        challenge_id,
+        challenge_type,
+    };
+
     info!("Verifying challenge for email: {}", email);
 
-    // Validate OTP format (should be 6 digits)
-    if challenge_answer.len() != 6 || !challenge_answer.chars().all(|c| c.is_ascii_digit()) {
-        warn!("Invalid OTP format for email: {}", email);
+    if !verify_challenge_answer(
+        &challenge,
+        challenge_answer,
+        &context.policy,
+        &context.hasher,
+        context.rate_limits.as_ref(),
+        context.otp_store.as_ref(),
+        &context.verification,
+    )
+    .await?
+    {
         return Ok(false);
     }
 
-    // Initialize AWS clients
-    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
+    // User should already be confirmed by create-auth-challenge
+    // Now set email_verified=true since they proved email ownership with the OTP or magic link
+    info!("Setting email_verified=true for user: {} after OTP verification", email);
+    match context
+        .cognito_client
+        .admin_update_user_attributes()
+        .user_pool_id(&event.user_pool_id)
+        .username(&event.user_name)
+        .user_attributes(
+            aws_sdk_cognitoidentityprovider::types::AttributeType::builder()
+                .name("email_verified")
+                .value("true")
+                .build()
+                .map_err(|e| {
+                    AuthError::InternalError(format!("Failed to build attribute: {}", e))
+                })?,
+        )
+        .send()
+        .await
+    {
+        Ok(_) => {
+            info!("Successfully set email_verified=true for user: {}", email);
+        }
+        Err(e) => {
+            error!("Failed to set email_verified for user {}: {:?}", email, e);
+            // Don't fail the authentication - the OTP was valid
+            warn!("Continuing with authentication despite email_verified update failure");
+        }
+    }
 
-    // Initialize service using naming utilities
-    let dynamodb_service = DynamoDBService::from_env(dynamodb_client)
-        .map_err(|e| AuthError::InternalError(format!("Failed to initialize DynamoDBService: {}", e)))?;
+    info!("OTP verification successful for email: {}", email);
+    Ok(true)
+}
+
+/// Check the submitted answer against the stored OTP or magic link for this email and challenge.
+/// A valid answer is consumed and a new registration advanced and welcomed;
+/// wrong answers are counted and lock the email out once the policy limit is hit.
+async fn verify_challenge_answer(
+    challenge: &PendingChallenge<'_>,
+    challenge_answer: &str,
+    policy: &OtpPolicy,
+    hasher: &OtpHasher,
+    rate_limits: &dyn RateLimitStore,
+    otp_store: &dyn OtpStore,
+    verification: &EmailVerificationService,
+) -> AuthResult<bool> {
+    let PendingChallenge {
+        email,
+        challenge_id,
+        challenge_type,
+    } = *challenge;
+
+    // Every submission counts towards the verify rate limit, across challenges
+    rate_limits
+        .consume_rate_limits(&[(RateLimitAction::OtpVerify, email.to_string())])
+        .await?;
+
+    // Validate the answer format: 6 digits for an OTP, a valid unexpired signature for a magic link
+    let well_formed = match challenge_type {
+        ChallengeType::OtpEmail => {
+            challenge_answer.len() == 6 && challenge_answer.chars().all(|c| c.is_ascii_digit())
+        }
+        ChallengeType::MagicLink => {
+            hasher.verify_magic_link(
+                challenge_answer,
+                email.as_str(),
+                challenge_id,
+                current_timestamp(),
+            )
+        }
+    };
+    if !well_formed {
+        warn!("Invalid {} answer format for email: {}", challenge_type.as_str(), email);
+        return Ok(false);
+    }
 
     // Retrieve OTP record
-    let otp_record = match dynamodb_service.get_otp(email).await? {
+    let otp_record = match otp_store.get_otp(email).await? {
         Some(record) => record,
         None => {
             warn!("No OTP record found for email: {}", email);
@@ -116,72 +274,59 @@ async fn handle_verify_challenge(event: &CognitoVerifyAuthChallengeEvent) -> Aut
         }
     };
 
-    // Check if OTP has expired
+    // A stale OTP from another session must not satisfy this challenge
+    if otp_record.challenge_id != challenge_id {
+        warn!("OTP challenge ID mismatch for email: {}", email);
+        return Ok(false);
+    }
+
+    // Reject everything while locked out or once the attempt limit has been used up
     let now = current_timestamp();
+    if otp_record.is_locked(now) || otp_record.attempts >= policy.max_attempts {
+        warn!("OTP locked after too many failed attempts for email: {}", email);
+        return Ok(false);
+    }
+
+    // Check if OTP has expired
     if now > otp_record.expires_at {
         warn!("OTP expired for email: {}", email);
         // Clean up expired OTP
-        let _ = dynamodb_service.delete_otp(email).await;
+        let _ = otp_store.consume_otp(email, challenge_id).await;
         return Ok(false);
     }
 
     // Verify OTP using constant-time comparison
-    if !verify_otp(challenge_answer, &otp_record.otp_hash) {
+    if !hasher.verify_otp(challenge_answer, email.as_str(), challenge_id, &otp_record.otp_hash) {
         warn!("Invalid OTP provided for email: {}", email);
 
-        // TODO: Implement attempt counting and lockout after too many failed attempts
-        // For now, we'll just return false
+        let attempts = otp_store.increment_attempts(email, challenge_id).await?;
+        if attempts >= policy.max_attempts {
+            warn!(
+                "Too many failed attempts ({}) for email: {}; locking out for {} seconds",
+                attempts, email, policy.lockout_seconds
+            );
+            otp_store.lock_out(email, now + policy.lockout_seconds).await?;
+        }
         return Ok(false);
     }
 
-    // OTP is valid - clean up the record
-    dynamodb_service.delete_otp(email).await?;
+    // OTP is valid - consume it atomically so concurrent verifications cannot both succeed
+    if !otp_store.consume_otp(email, challenge_id).await? {
+        warn!("OTP already consumed for email: {}", email);
+        return Ok(false);
+    }
 
-    // Update user status in DynamoDB to need user info (next step after email verification)
-    if let Err(e) = dynamodb_service
-        .update_user_status_to_need_user_info(email)
-        .await
-    {
+    // A first verification moves a new registration on to the user info step and queues the
+    // welcome email; returning users keep their status. A welcome that failed to queue stays
+    // pending and is retried on the next sign-in.
+    if let Err(e) = verification.email_verified(email, "verify-auth-challenge").await {
         warn!(
-            "Failed to update user status in DynamoDB for {}: {}",
+            "Failed to record email verification for {}: {}",
             email, e
         );
         // Don't fail the authentication - the OTP was valid
     }
 
-    // User should already be confirmed by create-auth-challenge
-    // Now set email_verified=true since they proved email ownership with OTP
-    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let cognito_client = aws_sdk_cognitoidentityprovider::Client::new(&config);
-    
-    info!("Setting email_verified=true for user: {} after OTP verification", email);
-    match cognito_client
-        .admin_update_user_attributes()
-        .user_pool_id(&event.user_pool_id)
-        .username(email)
-        .user_attributes(
-            aws_sdk_cognitoidentityprovider::types::AttributeType::builder()
-                .name("email_verified")
-                .value("true")
-                .build()
-                .map_err(|e| {
-                    AuthError::InternalError(format!("Failed to build attribute: {}", e))
-                })?,
-        )
-        .send()
-        .await
-    {
-        Ok(_) => {
-            info!("Successfully set email_verified=true for user: {}", email);
-        }
-        Err(e) => {
-            error!("Failed to set email_verified for user {}: {:?}", email, e);
-            // Don't fail the authentication - the OTP was valid
-            warn!("Continuing with authentication despite email_verified update failure");
-        }
-    }
-
-    info!("OTP verification successful for email: {}", email);
     Ok(true)
 }
 
@@ -196,5 +341,392 @@ async fn main() -> Result<(), Error> {
 
     info!("Starting verify-auth-challenge Lambda function");
 
-    run(service_fn(function_handler)).await
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{
+        InMemoryEmailQueue, InMemoryOtpStore, InMemoryRateLimitStore, InMemoryUserRepository,
+        OTPRecord, OtpHashKey, RateLimitPolicies, RateLimitPolicy, UserRepository, UserStatus,
+    };
+    use std::sync::Arc;
+
+    const EMAIL: &str = "user@example.com";
+
+    fn email() -> EmailAddress {
+        EmailAddress::parse(EMAIL).unwrap()
+    }
+
+    fn hasher() -> OtpHasher {
+        OtpHasher::new(OtpHashKey::new(1, "test-secret-0123456789abcdefghijklmnop"), None)
+    }
+
+    async fn seed(otp: &str, expires_in: i64) -> (InMemoryOtpStore, Arc<InMemoryUserRepository>) {
+        let otp_store = InMemoryOtpStore::new();
+        let users = Arc::new(InMemoryUserRepository::new());
+        users.create_user(&email(), "user-1").await.unwrap();
+
+        let now = current_timestamp();
+        otp_store
+            .store_otp(&OTPRecord {
+                email: email(),
+                otp_hash: hasher().hash_otp(otp, EMAIL, "challenge-1"),
+                created_at: now,
+                expires_at: now + expires_in,
+                ttl: now + expires_in + 3600,
+                challenge_id: "challenge-1".to_string(),
+                attempts: 0,
+                locked_until: None,
+            })
+            .await
+            .unwrap();
+
+        (otp_store, users)
+    }
+
+    fn verification(
+        users: &Arc<InMemoryUserRepository>,
+        emails: &Arc<InMemoryEmailQueue>,
+    ) -> EmailVerificationService {
+        EmailVerificationService::new(
+            users.clone(),
+            emails.clone(),
+            "https://app.example.com/dashboard".to_string(),
+        )
+    }
+
+    async fn verify_as(
+        challenge: &PendingChallenge<'_>,
+        answer: &str,
+        otp_store: &InMemoryOtpStore,
+        users: &Arc<InMemoryUserRepository>,
+    ) -> bool {
+        verify_challenge_answer(
+            challenge,
+            answer,
+            &OtpPolicy::default(),
+            &hasher(),
+            &InMemoryRateLimitStore::new(),
+            otp_store,
+            &verification(users, &Arc::new(InMemoryEmailQueue::new())),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn verify(
+        answer: &str,
+        otp_store: &InMemoryOtpStore,
+        users: &Arc<InMemoryUserRepository>,
+    ) -> bool {
+        let challenge = PendingChallenge {
+            email: &email(),
+            challenge_id: "challenge-1",
+            challenge_type: ChallengeType::OtpEmail,
+        };
+        verify_as(&challenge, answer, otp_store, users).await
+    }
+
+    #[tokio::test]
+    async fn test_handler_uses_prebuilt_context() {
+        let (otp_store, users) = seed("123456", 300).await;
+        let otp_store = Arc::new(otp_store);
+        let cognito_config = aws_sdk_cognitoidentityprovider::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .build();
+        let context = AppContext {
+            sub_addressing: SubAddressing::default(),
+            policy: OtpPolicy::default(),
+            hasher: hasher(),
+            rate_limits: Arc::new(InMemoryRateLimitStore::new()),
+            otp_store: otp_store.clone(),
+            verification: verification(&users, &Arc::new(InMemoryEmailQueue::new())),
+            cognito_client: CognitoClient::from_conf(cognito_config),
+            capture: EventCapture::disabled("verify-auth-challenge"),
+        };
+        let event: CognitoVerifyAuthChallengeEvent = serde_json::from_value(serde_json::json!({
+            "version": "1",
+            "region": "eu-west-2",
+            "userPoolId": "eu-west-2_example",
+            "userName": "user-1",
+            "callerContext": { "awsSdkVersion": "aws-sdk-unknown-unknown", "clientId": "client-1" },
+            "triggerSource": "VerifyAuthChallengeResponse_Authentication",
+            "request": {
+                "userAttributes": { "email": EMAIL },
+                "privateChallengeParameters": { "challenge_id": "challenge-1" },
+                "challengeAnswer": "654321",
+                "clientMetadata": null
+            },
+            "response": { "answerCorrect": null }
+        }))
+        .unwrap();
+
+        // A wrong answer never reaches Cognito, so the unconfigured client is not called
+        let response = function_handler(&context, LambdaEvent::new(event, Default::default()))
+            .await
+            .unwrap()
+            .response;
+        assert_eq!(response.answer_correct, Some(false));
+        assert_eq!(otp_store.get_otp(&email()).await.unwrap().unwrap().attempts, 1);
+    }
+
+    /// Replays the captured events in fixtures/verify-auth-challenge against empty in-memory
+    /// stores. Captured answers are redacted, so these only ever exercise the rejection path.
+    #[tokio::test]
+    async fn test_replay_captured_events() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/verify-auth-challenge");
+
+        let replayed = auth_shared::replay_fixtures(&fixtures, |event| async move {
+            let users = Arc::new(InMemoryUserRepository::new());
+            let cognito_config = aws_sdk_cognitoidentityprovider::Config::builder()
+                .behavior_version(BehaviorVersion::latest())
+                .build();
+            let context = AppContext {
+                sub_addressing: SubAddressing::default(),
+                policy: OtpPolicy::default(),
+                hasher: hasher(),
+                rate_limits: Arc::new(InMemoryRateLimitStore::new()),
+                otp_store: Arc::new(InMemoryOtpStore::new()),
+                verification: verification(&users, &Arc::new(InMemoryEmailQueue::new())),
+                cognito_client: CognitoClient::from_conf(cognito_config),
+                capture: EventCapture::disabled("verify-auth-challenge"),
+            };
+
+            let event = serde_json::from_value(event).unwrap();
+            let response = function_handler(&context, LambdaEvent::new(event, Default::default()))
+                .await
+                .unwrap()
+                .response;
+            serde_json::to_value(response).unwrap()
+        })
+        .await
+        .unwrap_or_else(|failures| panic!("{}", failures));
+        assert!(replayed > 0);
+    }
+
+    #[tokio::test]
+    async fn test_correct_otp_is_consumed_and_advances_status() {
+        let (otp_store, users) = seed("123456", 300).await;
+
+        assert!(verify("123456", &otp_store, &users).await);
+        assert!(otp_store.get_otp(&email()).await.unwrap().is_none());
+
+        let user = users.get_user_by_email(&email()).await.unwrap().unwrap();
+        assert!(matches!(user.status, UserStatus::RegistrationNeedUserInfo));
+        let history = users.get_status_history("user-1").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].actor, "verify-auth-challenge");
+
+        // The OTP cannot be replayed
+        assert!(!verify("123456", &otp_store, &users).await);
+    }
+
+    #[tokio::test]
+    async fn test_first_verification_queues_welcome_email() {
+        let (otp_store, users) = seed("123456", 300).await;
+        let emails = Arc::new(InMemoryEmailQueue::new());
+        let challenge = PendingChallenge {
+            email: &email(),
+            challenge_id: "challenge-1",
+            challenge_type: ChallengeType::OtpEmail,
+        };
+
+        let verified = verify_challenge_answer(
+            &challenge,
+            "123456",
+            &OtpPolicy::default(),
+            &hasher(),
+            &InMemoryRateLimitStore::new(),
+            &otp_store,
+            &verification(&users, &emails),
+        )
+        .await
+        .unwrap();
+
+        assert!(verified);
+        let queued = emails.queued();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].recipient, EMAIL);
+        assert_eq!(queued[0].template_data["dashboardUrl"], "https://app.example.com/dashboard");
+    }
+
+    #[tokio::test]
+    async fn test_returning_user_keeps_status() {
+        let (otp_store, users) = seed("123456", 300).await;
+        let mut user = users.get_user_by_email(&email()).await.unwrap().unwrap();
+        user.status = UserStatus::Active;
+        users.insert_user(user);
+
+        assert!(verify("123456", &otp_store, &users).await);
+
+        let user = users.get_user_by_email(&email()).await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::Active);
+        assert!(users.get_status_history("user-1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wrong_otp_is_rejected_and_counted() {
+        let (otp_store, users) = seed("123456", 300).await;
+
+        assert!(!verify("654321", &otp_store, &users).await);
+        let record = otp_store.get_otp(&email()).await.unwrap().unwrap();
+        assert_eq!(record.attempts, 1);
+        assert_eq!(record.locked_until, None);
+
+        let user = users.get_user_by_email(&email()).await.unwrap().unwrap();
+        assert!(matches!(user.status, UserStatus::RegistrationEmailNotVerified));
+
+        // A correct answer within the limit still succeeds
+        assert!(verify("123456", &otp_store, &users).await);
+    }
+
+    #[tokio::test]
+    async fn test_lockout_after_max_attempts() {
+        let (otp_store, users) = seed("123456", 300).await;
+        let policy = OtpPolicy::default();
+
+        for _ in 0..policy.max_attempts {
+            assert!(!verify("654321", &otp_store, &users).await);
+        }
+
+        let record = otp_store.get_otp(&email()).await.unwrap().unwrap();
+        assert_eq!(record.attempts, policy.max_attempts);
+        assert!(record.is_locked(current_timestamp()));
+
+        // The correct OTP is no longer accepted
+        assert!(!verify("123456", &otp_store, &users).await);
+        let user = users.get_user_by_email(&email()).await.unwrap().unwrap();
+        assert!(matches!(user.status, UserStatus::RegistrationEmailNotVerified));
+    }
+
+    #[tokio::test]
+    async fn test_expired_otp_is_rejected_and_deleted() {
+        let (otp_store, users) = seed("123456", -1).await;
+
+        assert!(!verify("123456", &otp_store, &users).await);
+        assert!(otp_store.get_otp(&email()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_answer_is_rejected() {
+        let (otp_store, users) = seed("123456", 300).await;
+
+        assert!(!verify("12345", &otp_store, &users).await);
+        assert!(!verify("12345a", &otp_store, &users).await);
+
+        // Malformed answers don't count towards the lockout
+        let record = otp_store.get_otp(&email()).await.unwrap().unwrap();
+        assert_eq!(record.attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_otp_from_another_challenge_is_rejected() {
+        let (otp_store, users) = seed("123456", 300).await;
+
+        let challenge = PendingChallenge {
+            email: &email(),
+            challenge_id: "challenge-2",
+            challenge_type: ChallengeType::OtpEmail,
+        };
+        let result = verify_as(&challenge, "123456", &otp_store, &users).await;
+
+        assert!(!result);
+        let record = otp_store.get_otp(&email()).await.unwrap().unwrap();
+        assert_eq!(record.attempts, 0);
+        let user = users.get_user_by_email(&email()).await.unwrap().unwrap();
+        assert!(matches!(user.status, UserStatus::RegistrationEmailNotVerified));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_verifications_only_one_succeeds() {
+        let (otp_store, users) = seed("123456", 300).await;
+
+        let (first, second) = tokio::join!(
+            verify("123456", &otp_store, &users),
+            verify("123456", &otp_store, &users)
+        );
+
+        assert!(first ^ second);
+    }
+
+    #[tokio::test]
+    async fn test_magic_link_is_single_use() {
+        let token = hasher().sign_magic_link(EMAIL, "challenge-1", current_timestamp() + 900);
+        let (otp_store, users) = seed(&token, 900).await;
+        let challenge = PendingChallenge {
+            email: &email(),
+            challenge_id: "challenge-1",
+            challenge_type: ChallengeType::MagicLink,
+        };
+
+        // A tampered link is rejected without counting as an attempt
+        let tampered = format!("{}0", token);
+        assert!(!verify_as(&challenge, &tampered, &otp_store, &users).await);
+        assert_eq!(otp_store.get_otp(&email()).await.unwrap().unwrap().attempts, 0);
+
+        assert!(verify_as(&challenge, &token, &otp_store, &users).await);
+        let user = users.get_user_by_email(&email()).await.unwrap().unwrap();
+        assert!(matches!(user.status, UserStatus::RegistrationNeedUserInfo));
+
+        // The link cannot be replayed
+        assert!(!verify_as(&challenge, &token, &otp_store, &users).await);
+    }
+
+    #[tokio::test]
+    async fn test_otp_answer_is_not_accepted_for_magic_link_challenge() {
+        let (otp_store, users) = seed("123456", 300).await;
+        let challenge = PendingChallenge {
+            email: &email(),
+            challenge_id: "challenge-1",
+            challenge_type: ChallengeType::MagicLink,
+        };
+
+        let result = verify_as(&challenge, "123456", &otp_store, &users).await;
+
+        assert!(!result);
+        assert!(otp_store.get_otp(&email()).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_verify_rate_limit_spans_challenges() {
+        let (otp_store, users) = seed("123456", 300).await;
+        let rate_limits = InMemoryRateLimitStore::with_policies(
+            RateLimitPolicies::default()
+                .with_policy(RateLimitAction::OtpVerify, RateLimitPolicy::parse("2/15m").unwrap()),
+        );
+        let challenge = PendingChallenge {
+            email: &email(),
+            challenge_id: "challenge-1",
+            challenge_type: ChallengeType::OtpEmail,
+        };
+        let (policy, hasher) = (OtpPolicy::default(), hasher());
+        let verification = verification(&users, &Arc::new(InMemoryEmailQueue::new()));
+        let submit = |answer: &'static str| {
+            verify_challenge_answer(
+                &challenge,
+                answer,
+                &policy,
+                &hasher,
+                &rate_limits,
+                &otp_store,
+                &verification,
+            )
+        };
+
+        assert!(!submit("654321").await.unwrap());
+        assert!(!submit("654321").await.unwrap());
+
+        // The correct answer is refused once the verify limit is used up
+        assert!(matches!(
+            submit("123456").await,
+            Err(AuthError::RateLimitExceeded(_))
+        ));
+        assert!(otp_store.get_otp(&email()).await.unwrap().is_some());
+    }
 }
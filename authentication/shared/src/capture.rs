@@ -0,0 +1,457 @@
+use aws_sdk_s3::{primitives::ByteStream, Client as S3Client};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::{generate_challenge_id, AuthError, AuthResult};
+
+/// Values of these keys are secret or personal and are replaced outright
+const SECRET_KEYS: &[&str] = &[
+    "challengeAnswer",
+    "otp",
+    "magicLinkUrl",
+    "phone_number",
+    "name",
+    "given_name",
+    "family_name",
+    "birthdate",
+    "address",
+];
+
+/// Values of these keys identify the user; they are replaced by placeholders that stay the same
+/// wherever the original value appeared in the event
+const IDENTIFIER_KEYS: &[&str] = &["sub", "userName", "user_id", "cognito:username"];
+
+/// Values of these keys are IP addresses, replaced by one reserved for documentation so the
+/// redacted event still parses
+const ADDRESS_KEYS: &[&str] = &["client_ip", "sourceIp"];
+
+const REDACTED: &str = "REDACTED";
+const REDACTED_ADDRESS: &str = "192.0.2.1";
+
+/// Where captured trigger events are written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureTarget {
+    /// One JSON file per event under `<dir>/<function>/`, for local runs
+    Directory(PathBuf),
+    /// One `EVENT_CAPTURE <function> <json>` log line per event, collected from CloudWatch with
+    /// scripts/aws/capture-events.sh
+    Log,
+    /// One object per event under `<prefix>/<function>/` in the bucket, for deployed lambdas
+    S3 { bucket: String, prefix: String },
+}
+
+impl CaptureTarget {
+    /// Parse an EVENT_CAPTURE value: `off` (or empty) captures nothing, `log` writes log lines,
+    /// `dir:<path>` writes fixture files and `s3://<bucket>[/<prefix>]` writes objects
+    pub fn parse(value: &str) -> AuthResult<Option<Self>> {
+        let value = value.trim();
+        if matches!(value, "" | "off") {
+            return Ok(None);
+        }
+        if value == "log" {
+            return Ok(Some(CaptureTarget::Log));
+        }
+        if let Some(dir) = value.strip_prefix("dir:").filter(|dir| !dir.is_empty()) {
+            return Ok(Some(CaptureTarget::Directory(dir.into())));
+        }
+        if let Some(location) = value.strip_prefix("s3://") {
+            let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+            if !bucket.is_empty() {
+                return Ok(Some(CaptureTarget::S3 {
+                    bucket: bucket.to_string(),
+                    prefix: prefix.trim_matches('/').to_string(),
+                }));
+            }
+        }
+        Err(AuthError::InternalError(format!(
+            "EVENT_CAPTURE must be off, log, dir:<path> or s3://<bucket>/<prefix>, not '{}'",
+            value
+        )))
+    }
+}
+
+/// Opt-in recording of redacted trigger events, which become fixtures for the replay tests
+#[derive(Debug, Clone)]
+pub struct EventCapture {
+    function: String,
+    target: Option<CaptureTarget>,
+    /// Only needed for an S3 target
+    s3_client: Option<S3Client>,
+}
+
+impl EventCapture {
+    pub fn new(function: &str, target: Option<CaptureTarget>) -> Self {
+        Self {
+            function: function.to_string(),
+            target,
+            s3_client: None,
+        }
+    }
+
+    pub fn disabled(function: &str) -> Self {
+        Self::new(function, None)
+    }
+
+    /// Create EventCapture from the optional EVENT_CAPTURE environment variable, capturing
+    /// nothing when it is unset. The S3 client is only used for an S3 target.
+    pub fn from_env(function: &str, s3_client: S3Client) -> AuthResult<Self> {
+        let target = match std::env::var("EVENT_CAPTURE") {
+            Ok(value) => CaptureTarget::parse(&value)?,
+            Err(_) => None,
+        };
+        if let Some(target) = &target {
+            tracing::warn!("Capturing redacted {} events to {:?}", function, target);
+        }
+        Ok(Self {
+            s3_client: Some(s3_client),
+            ..Self::new(function, target)
+        })
+    }
+
+    /// Record a redacted copy of the event. Capture never fails the invocation: write errors
+    /// are logged and the invocation carries on.
+    pub async fn capture<T: Serialize>(&self, event: &T) {
+        let Some(target) = &self.target else {
+            return;
+        };
+        let mut value = match serde_json::to_value(event) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("Failed to serialize event for capture: {}", e);
+                return;
+            }
+        };
+        redact_event(&mut value);
+
+        match target {
+            CaptureTarget::Log => tracing::info!("EVENT_CAPTURE {} {}", self.function, value),
+            CaptureTarget::Directory(dir) => match self.write(dir, &value) {
+                Ok(path) => tracing::info!("Captured event to {}", path.display()),
+                Err(e) => tracing::warn!("Failed to write captured event: {}", e),
+            },
+            CaptureTarget::S3 { bucket, prefix } => {
+                let key = object_key(prefix, &self.function, &file_name(&value));
+                match self.put(bucket, &key, &value).await {
+                    Ok(()) => tracing::info!("Captured event to s3://{}/{}", bucket, key),
+                    Err(e) => tracing::warn!("Failed to upload captured event: {}", e),
+                }
+            }
+        }
+    }
+
+    fn write(&self, dir: &Path, event: &Value) -> std::io::Result<PathBuf> {
+        let dir = dir.join(&self.function);
+        std::fs::create_dir_all(&dir)?;
+
+        let path = dir.join(file_name(event));
+        std::fs::write(&path, format!("{:#}\n", event))?;
+        Ok(path)
+    }
+
+    async fn put(&self, bucket: &str, key: &str, event: &Value) -> Result<(), String> {
+        let client = self
+            .s3_client
+            .as_ref()
+            .ok_or_else(|| "no S3 client configured".to_string())?;
+        client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .content_type("application/json")
+            .body(ByteStream::from(format!("{:#}\n", event).into_bytes()))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// Name for a captured event: its trigger source and a short random suffix
+fn file_name(event: &Value) -> String {
+    let trigger = event
+        .get("triggerSource")
+        .and_then(Value::as_str)
+        .unwrap_or("event");
+    format!("{}-{}.json", trigger, &generate_challenge_id()[..8])
+}
+
+/// Object key for a captured event, laid out like the fixture directories so a synced prefix
+/// can be copied straight into fixtures/
+fn object_key(prefix: &str, function: &str, file_name: &str) -> String {
+    [prefix, function, file_name]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Strip everything personal or secret from a trigger event. Email addresses become
+/// `userN@<domain>`, keeping the domain the sign-up policy checks, and user identifiers `user-N`,
+/// numbered in order of appearance so a value repeated across fields is still recognisably the
+/// same after redaction.
+pub fn redact_event(event: &mut Value) {
+    Redactor::default().redact(event, None);
+}
+
+#[derive(Default)]
+struct Redactor {
+    emails: HashMap<String, String>,
+    identifiers: HashMap<String, String>,
+}
+
+impl Redactor {
+    fn redact(&mut self, value: &mut Value, key: Option<&str>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    self.redact(value, Some(key));
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.redact(item, key);
+                }
+            }
+            Value::String(text) if !text.is_empty() => {
+                let key = key.unwrap_or_default();
+                if SECRET_KEYS.contains(&key) {
+                    *text = REDACTED.to_string();
+                } else if ADDRESS_KEYS.contains(&key) {
+                    *text = REDACTED_ADDRESS.to_string();
+                } else if text.contains('@') {
+                    let domain = text.rsplit('@').next().unwrap_or_default().to_lowercase();
+                    *text = placeholder(&mut self.emails, text, |n| format!("user{}@{}", n, domain));
+                } else if IDENTIFIER_KEYS.contains(&key) {
+                    *text = placeholder(&mut self.identifiers, text, |n| format!("user-{}", n));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn placeholder(
+    seen: &mut HashMap<String, String>,
+    value: &str,
+    make: impl Fn(usize) -> String,
+) -> String {
+    let next = seen.len() + 1;
+    seen.entry(value.to_lowercase())
+        .or_insert_with(|| make(next))
+        .clone()
+}
+
+/// A captured event checked in as a replay fixture, with the response snapshot beside it
+#[derive(Debug)]
+pub struct Fixture {
+    pub name: String,
+    pub event: Value,
+    snapshot_path: PathBuf,
+}
+
+/// Load every fixture in `dir`: `<name>.json` files, each with its snapshot at `<name>.snap.json`
+pub fn load_fixtures(dir: &Path) -> AuthResult<Vec<Fixture>> {
+    let io_error = |e: std::io::Error| {
+        AuthError::InternalError(format!("Failed to read fixtures in {}: {}", dir.display(), e))
+    };
+
+    let mut fixtures = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+            .filter(|name| !name.ends_with(".snap"))
+        else {
+            continue;
+        };
+
+        let contents = std::fs::read_to_string(&path).map_err(io_error)?;
+        let event = serde_json::from_str(&contents).map_err(|e| {
+            AuthError::InternalError(format!("Invalid fixture {}: {}", path.display(), e))
+        })?;
+        fixtures.push(Fixture {
+            name: name.to_string(),
+            event,
+            snapshot_path: dir.join(format!("{}.snap.json", name)),
+        });
+    }
+
+    fixtures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fixtures)
+}
+
+impl Fixture {
+    /// Compare a replayed response with the snapshot. With UPDATE_SNAPSHOTS set the snapshot is
+    /// rewritten instead, so changes show up for review in the diff.
+    pub fn check_snapshot(&self, response: &Value) -> Result<(), String> {
+        let rendered = format!("{:#}\n", response);
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            return std::fs::write(&self.snapshot_path, rendered)
+                .map_err(|e| format!("{}: failed to write snapshot: {}", self.name, e));
+        }
+
+        let expected = std::fs::read_to_string(&self.snapshot_path).map_err(|_| {
+            format!("{}: no snapshot yet; rerun with UPDATE_SNAPSHOTS=1", self.name)
+        })?;
+        let expected: Value = serde_json::from_str(&expected)
+            .map_err(|e| format!("{}: invalid snapshot: {}", self.name, e))?;
+        if &expected != response {
+            return Err(format!(
+                "{}: response differs from snapshot\n--- expected\n{:#}\n--- actual\n{}",
+                self.name, expected, rendered
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Feed every fixture in `dir` through `replay` and check each response against its snapshot,
+/// returning how many were replayed or every mismatch. Used by the lambdas' replay tests.
+pub async fn replay_fixtures<F, Fut>(dir: &Path, mut replay: F) -> Result<usize, String>
+where
+    F: FnMut(Value) -> Fut,
+    Fut: Future<Output = Value>,
+{
+    let fixtures = load_fixtures(dir).map_err(|e| e.to_string())?;
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        let response = replay(fixture.event.clone()).await;
+        if let Err(e) = fixture.check_snapshot(&response) {
+            failures.push(e);
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(fixtures.len())
+    } else {
+        Err(failures.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_event() {
+        let mut event = json!({
+            "triggerSource": "VerifyAuthChallengeResponse_Authentication",
+            "userPoolId": "eu-west-2_example",
+            "userName": "2f0c6c5e-91b1-4d51-9a43-2c8e7c0e5d11",
+            "request": {
+                "userAttributes": {
+                    "sub": "2f0c6c5e-91b1-4d51-9a43-2c8e7c0e5d11",
+                    "email": "Jane.Doe@Company.com",
+                    "given_name": "Jane"
+                },
+                "privateChallengeParameters": { "challenge_id": "challenge-1" },
+                "challengeAnswer": "123456",
+                "clientMetadata": {
+                    "email": "jane.doe@company.com",
+                    "invited_by": "someone@else.com",
+                    "client_ip": "198.51.100.7"
+                }
+            }
+        });
+
+        redact_event(&mut event);
+
+        assert_eq!(event["userName"], "user-1");
+        let request = &event["request"];
+        assert_eq!(request["userAttributes"]["sub"], "user-1");
+        assert_eq!(request["userAttributes"]["email"], "user1@company.com");
+        assert_eq!(request["userAttributes"]["given_name"], "REDACTED");
+        assert_eq!(request["challengeAnswer"], "REDACTED");
+        assert_eq!(request["clientMetadata"]["email"], "user1@company.com");
+        assert_eq!(request["clientMetadata"]["invited_by"], "user2@else.com");
+        assert_eq!(request["clientMetadata"]["client_ip"], "192.0.2.1");
+        // Nothing personal about these
+        assert_eq!(event["userPoolId"], "eu-west-2_example");
+        assert_eq!(request["privateChallengeParameters"]["challenge_id"], "challenge-1");
+    }
+
+    #[test]
+    fn test_parse_capture_target() {
+        assert_eq!(CaptureTarget::parse("off").unwrap(), None);
+        assert_eq!(CaptureTarget::parse("").unwrap(), None);
+        assert_eq!(CaptureTarget::parse("log").unwrap(), Some(CaptureTarget::Log));
+        assert_eq!(
+            CaptureTarget::parse("dir:fixtures").unwrap(),
+            Some(CaptureTarget::Directory("fixtures".into()))
+        );
+        assert_eq!(
+            CaptureTarget::parse("s3://capture-bucket/events/test/").unwrap(),
+            Some(CaptureTarget::S3 {
+                bucket: "capture-bucket".to_string(),
+                prefix: "events/test".to_string(),
+            })
+        );
+        assert_eq!(
+            CaptureTarget::parse("s3://capture-bucket").unwrap(),
+            Some(CaptureTarget::S3 {
+                bucket: "capture-bucket".to_string(),
+                prefix: String::new(),
+            })
+        );
+        for invalid in ["dir:", "s3://", "s3:///events", "on"] {
+            assert!(CaptureTarget::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_object_key() {
+        assert_eq!(
+            object_key("events/test", "pre-signup", "PreSignUp_SignUp-1a2b3c4d.json"),
+            "events/test/pre-signup/PreSignUp_SignUp-1a2b3c4d.json"
+        );
+        assert_eq!(
+            object_key("", "pre-signup", "PreSignUp_SignUp-1a2b3c4d.json"),
+            "pre-signup/PreSignUp_SignUp-1a2b3c4d.json"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_upload_does_not_fail_capture() {
+        // Without a client the upload fails; capture logs it and returns as usual
+        let capture = EventCapture::new(
+            "pre-signup",
+            Some(CaptureTarget::S3 {
+                bucket: "capture-bucket".to_string(),
+                prefix: String::new(),
+            }),
+        );
+        capture.capture(&json!({ "triggerSource": "PreSignUp_SignUp" })).await;
+    }
+
+    #[tokio::test]
+    async fn test_captured_events_load_as_fixtures() {
+        let dir = std::env::temp_dir().join(format!("event-capture-{}", generate_challenge_id()));
+        let capture = EventCapture::new(
+            "pre-signup",
+            Some(CaptureTarget::Directory(dir.clone())),
+        );
+        capture.capture(&json!({
+            "triggerSource": "PreSignUp_SignUp",
+            "request": { "userAttributes": { "email": "someone@mailinator.com" } }
+        }))
+        .await;
+
+        let fixtures = load_fixtures(&dir.join("pre-signup")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(fixtures.len(), 1);
+        assert!(fixtures[0].name.starts_with("PreSignUp_SignUp-"));
+        assert_eq!(
+            fixtures[0].event["request"]["userAttributes"]["email"],
+            "user1@mailinator.com"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::{AuthError, AuthResult, UserProfile, UserStatus};
+
+/// Token claim carrying the user's UserStatus, as stored in DynamoDB
+pub const STATUS_CLAIM: &str = "appre:status";
+
+/// Token claim carrying the platform user ID (the users table key)
+pub const USER_ID_CLAIM: &str = "appre:user_id";
+
+/// Token claim carrying the user's PlatformRole
+pub const ROLE_CLAIM: &str = "appre:role";
+
+/// Cognito group whose members are platform admins
+pub const ADMIN_GROUP: &str = "admins";
+
+/// What a signed-in user may do on the platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlatformRole {
+    Creator,
+    Admin,
+}
+
+impl PlatformRole {
+    /// Admins are members of ADMIN_GROUP; everyone else is a creator
+    pub fn from_groups<S: AsRef<str>>(groups: &[S]) -> Self {
+        if groups.iter().any(|group| group.as_ref() == ADMIN_GROUP) {
+            PlatformRole::Admin
+        } else {
+            PlatformRole::Creator
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlatformRole::Creator => "creator",
+            PlatformRole::Admin => "admin",
+        }
+    }
+}
+
+/// The platform claims added to ID and access tokens by the pre-token-generation trigger, so
+/// downstream services can authorise a caller without reading the users table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformClaims {
+    pub user_id: String,
+    pub status: UserStatus,
+    pub role: PlatformRole,
+}
+
+impl PlatformClaims {
+    pub fn new<S: AsRef<str>>(user: &UserProfile, groups: &[S]) -> Self {
+        Self {
+            user_id: user.user_id.clone(),
+            status: user.status,
+            role: PlatformRole::from_groups(groups),
+        }
+    }
+
+    /// The claims to add to a token
+    pub fn to_claims(&self) -> HashMap<String, String> {
+        HashMap::from([
+            (USER_ID_CLAIM.to_string(), self.user_id.clone()),
+            (STATUS_CLAIM.to_string(), self.status.as_str().to_string()),
+            (ROLE_CLAIM.to_string(), self.role.as_str().to_string()),
+        ])
+    }
+
+    /// Read the claims back from a verified token's claim set
+    pub fn from_claims(claims: &HashMap<String, String>) -> AuthResult<Self> {
+        let claim = |name: &str| {
+            claims
+                .get(name)
+                .ok_or_else(|| AuthError::InvalidSession(format!("Token is missing the {} claim", name)))
+        };
+
+        let role = match claim(ROLE_CLAIM)?.as_str() {
+            "creator" => PlatformRole::Creator,
+            "admin" => PlatformRole::Admin,
+            other => {
+                return Err(AuthError::InvalidSession(format!("Unknown role claim: {}", other)))
+            }
+        };
+
+        Ok(Self {
+            user_id: claim(USER_ID_CLAIM)?.clone(),
+            status: claim(STATUS_CLAIM)?.parse()?,
+            role,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_from_groups() {
+        assert_eq!(PlatformRole::from_groups::<&str>(&[]), PlatformRole::Creator);
+        assert_eq!(PlatformRole::from_groups(&["beta", "admins"]), PlatformRole::Admin);
+        assert_eq!(PlatformRole::from_groups(&["Admins"]), PlatformRole::Creator);
+    }
+
+    #[test]
+    fn test_claims_round_trip() {
+        let claims = PlatformClaims {
+            user_id: "user-1".to_string(),
+            status: UserStatus::AwaitingReview,
+            role: PlatformRole::Admin,
+        };
+        let encoded = claims.to_claims();
+        assert_eq!(encoded["appre:status"], "AWAITING_REVIEW");
+        assert_eq!(encoded["appre:user_id"], "user-1");
+        assert_eq!(encoded["appre:role"], "admin");
+        assert_eq!(PlatformClaims::from_claims(&encoded).unwrap(), claims);
+
+        let mut missing = encoded.clone();
+        missing.remove(STATUS_CLAIM);
+        assert!(matches!(PlatformClaims::from_claims(&missing), Err(AuthError::InvalidSession(_))));
+    }
+}
@@ -0,0 +1,296 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+
+use crate::{AuthError, AuthResult, OTPRecord, RateLimitRecord, UserProfile};
+
+/// Attribute recording which shape of a model an item was written with
+pub const SCHEMA_VERSION_ATTRIBUTE: &str = "schema_version";
+
+/// A DynamoDB item as the SDK hands it over
+pub type Item = HashMap<String, AttributeValue>;
+
+/// A model stored as a whole DynamoDB item, encoded from its serde derives.
+///
+/// Adding an optional field to the model is all it takes to store it: items written before the
+/// field existed decode with it unset. Anything serde can't bridge on its own (a renamed or
+/// reshaped attribute) goes in `upgrade` together with a bump of `SCHEMA_VERSION`.
+pub trait DynamoItem: Serialize + DeserializeOwned {
+    /// Version written with every item; bump it when the stored shape changes incompatibly
+    const SCHEMA_VERSION: u32;
+
+    /// Rewrite an item written at an older version into the current shape before decoding.
+    /// Items from before versioning was introduced are version 0.
+    fn upgrade(_item: &mut Item, _version: u32) {}
+
+    /// Encode the model as an item, stamped with the current schema version
+    fn to_item(&self) -> AuthResult<Item> {
+        let mut item: Item = serde_dynamo::to_item(self).map_err(|e| {
+            tracing::error!("Failed to encode item: {}", e);
+            AuthError::InternalError(format!("Failed to encode item: {}", e))
+        })?;
+        item.insert(
+            SCHEMA_VERSION_ATTRIBUTE.to_string(),
+            AttributeValue::N(Self::SCHEMA_VERSION.to_string()),
+        );
+        Ok(item)
+    }
+
+    /// Decode an item written at this or any earlier schema version
+    fn from_item(mut item: Item) -> AuthResult<Self> {
+        let version = item
+            .remove(SCHEMA_VERSION_ATTRIBUTE)
+            .and_then(|v| v.as_n().ok().and_then(|n| n.parse::<u32>().ok()))
+            .unwrap_or(0);
+        if version < Self::SCHEMA_VERSION {
+            Self::upgrade(&mut item, version);
+        } else if version > Self::SCHEMA_VERSION {
+            // Written by a newer deployment mid-rollout; fields we don't know are ignored
+            tracing::warn!(
+                "Decoding item at schema version {} with reader at version {}",
+                version,
+                Self::SCHEMA_VERSION
+            );
+        }
+
+        serde_dynamo::from_item(item).map_err(|e| {
+            tracing::error!("Failed to decode item: {}", e);
+            AuthError::InternalError(format!("Failed to decode item: {}", e))
+        })
+    }
+}
+
+impl DynamoItem for OTPRecord {
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl DynamoItem for UserProfile {
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl DynamoItem for RateLimitRecord {
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+/// Encode a single value (e.g. a status history entry) as an attribute for an update expression
+pub fn to_attribute<T: Serialize>(value: &T) -> AuthResult<AttributeValue> {
+    serde_dynamo::to_attribute_value(value)
+        .map_err(|e| AuthError::InternalError(format!("Failed to encode attribute: {}", e)))
+}
+
+/// Decode a single attribute read back from an item
+pub fn from_attribute<T: DeserializeOwned>(value: AttributeValue) -> AuthResult<T> {
+    serde_dynamo::from_attribute_value(value)
+        .map_err(|e| AuthError::InternalError(format!("Failed to decode attribute: {}", e)))
+}
+
+/// Emails read back from items. Decoding goes through `EmailAddress::from_stored`, so an address
+/// written before parsing was strict comes back lowercased instead of making the item unreadable.
+pub mod stored_email {
+    use notifications_shared::EmailAddress;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &EmailAddress, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<EmailAddress, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(EmailAddress::from_stored(&value))
+    }
+}
+
+/// Timestamps stored as `DateTime::to_rfc3339` strings. GSI sort keys and page cursors compare
+/// these strings directly, so every writer has to produce exactly this format.
+pub mod rfc3339 {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(serde::de::Error::custom)
+    }
+
+    /// The same format for optional timestamps
+    pub mod option {
+        use chrono::{DateTime, Utc};
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            value: &Option<DateTime<Utc>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<DateTime<Utc>>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|value| {
+                    DateTime::parse_from_rfc3339(&value)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .map_err(serde::de::Error::custom)
+                })
+                .transpose()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EmailAddress, StatusChange, UserStatus};
+    use chrono::{TimeZone, Utc};
+
+    fn user() -> UserProfile {
+        let created_at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        UserProfile {
+            user_id: "user-1".to_string(),
+            email: EmailAddress::parse("creator@example.com").unwrap(),
+            status: UserStatus::RegistrationNeedStripe,
+            full_name: Some("Ada Lovelace".to_string()),
+            content_description: None,
+            content_link: None,
+            stripe_account_id: None,
+            created_at,
+            updated_at: created_at,
+            reviewed_by: None,
+            reviewed_at: None,
+            rejection_reason: None,
+            welcome_pending: false,
+            review_email_pending: false,
+        }
+    }
+
+    #[test]
+    fn test_user_profile_round_trips_in_existing_attribute_format() {
+        let user = user();
+        let item = user.to_item().unwrap();
+
+        assert_eq!(item["schema_version"], AttributeValue::N("1".to_string()));
+        assert_eq!(item["status"], AttributeValue::S("REGISTRATION_NEED_STRIPE".to_string()));
+        assert_eq!(item["created_at"], AttributeValue::S(user.created_at.to_rfc3339()));
+        // Unset fields must be absent, not NULL, for attribute_not_exists conditions
+        assert!(!item.contains_key("stripe_account_id"));
+        assert!(!item.contains_key("reviewed_at"));
+
+        let decoded = UserProfile::from_item(item).unwrap();
+        assert_eq!(decoded.user_id, user.user_id);
+        assert_eq!(decoded.status, user.status);
+        assert_eq!(decoded.full_name, user.full_name);
+        assert_eq!(decoded.created_at, user.created_at);
+        assert_eq!(decoded.reviewed_at, None);
+    }
+
+    #[test]
+    fn test_decodes_items_written_before_versioning() {
+        // The shape create_user and store_otp wrote by hand, with history appended later
+        let user_item = Item::from([
+            ("user_id".to_string(), AttributeValue::S("user-1".to_string())),
+            ("email".to_string(), AttributeValue::S("creator@example.com".to_string())),
+            ("status".to_string(), AttributeValue::S("AWAITING_REVIEW".to_string())),
+            ("created_at".to_string(), AttributeValue::S("2024-03-01T12:00:00+00:00".to_string())),
+            ("updated_at".to_string(), AttributeValue::S("2024-03-02T12:00:00+00:00".to_string())),
+            ("status_history".to_string(), AttributeValue::L(Vec::new())),
+        ]);
+        let user = UserProfile::from_item(user_item).unwrap();
+        assert_eq!(user.status, UserStatus::AwaitingReview);
+        assert_eq!(user.full_name, None);
+
+        let otp_item = Item::from([
+            ("email".to_string(), AttributeValue::S("creator@example.com".to_string())),
+            ("otp_hash".to_string(), AttributeValue::S("1:abc".to_string())),
+            ("created_at".to_string(), AttributeValue::N("1700000000".to_string())),
+            ("expires_at".to_string(), AttributeValue::N("1700000300".to_string())),
+            ("ttl".to_string(), AttributeValue::N("1700000300".to_string())),
+            ("challenge_id".to_string(), AttributeValue::S("challenge-1".to_string())),
+            ("attempts".to_string(), AttributeValue::N("2".to_string())),
+        ]);
+        let record = OTPRecord::from_item(otp_item).unwrap();
+        assert_eq!(record.attempts, 2);
+        assert_eq!(record.locked_until, None);
+
+        let mut round_trip = record.to_item().unwrap();
+        assert_eq!(round_trip.remove("created_at"), Some(AttributeValue::N("1700000000".to_string())));
+        assert!(!round_trip.contains_key("locked_until"));
+    }
+
+    #[test]
+    fn test_decodes_rate_limit_counters_with_and_without_version() {
+        let mut item = Item::from([
+            ("email".to_string(), AttributeValue::S("otp_send#900#creator@example.com".to_string())),
+            ("request_timestamp".to_string(), AttributeValue::N("1700000100".to_string())),
+            ("request_count".to_string(), AttributeValue::N("2".to_string())),
+            ("ttl".to_string(), AttributeValue::N("1700001000".to_string())),
+        ]);
+        let record = RateLimitRecord::from_item(item.clone()).unwrap();
+        assert_eq!(record.key, "otp_send#900#creator@example.com");
+        assert_eq!(record.window_start, 1700000100);
+        assert_eq!(record.request_count, 2);
+
+        item.insert("schema_version".to_string(), AttributeValue::N("1".to_string()));
+        assert_eq!(RateLimitRecord::from_item(item).unwrap(), record);
+        assert_eq!(record.to_item().unwrap()["request_timestamp"], AttributeValue::N("1700000100".to_string()));
+    }
+
+    #[test]
+    fn test_decodes_legacy_emails_the_parser_rejects() {
+        // The old check only wanted an '@', a '.' and six characters; this has a single-label domain
+        let legacy = "Ada.Lovelace@Intranet";
+        assert!(EmailAddress::parse(legacy).is_err());
+
+        let user_item = Item::from([
+            ("user_id".to_string(), AttributeValue::S("user-1".to_string())),
+            ("email".to_string(), AttributeValue::S(legacy.to_string())),
+            ("status".to_string(), AttributeValue::S("AWAITING_REVIEW".to_string())),
+            ("created_at".to_string(), AttributeValue::S("2024-03-01T12:00:00+00:00".to_string())),
+            ("updated_at".to_string(), AttributeValue::S("2024-03-02T12:00:00+00:00".to_string())),
+        ]);
+        let user = UserProfile::from_item(user_item).unwrap();
+        assert_eq!(user.email, "ada.lovelace@intranet");
+        assert_eq!(user.to_item().unwrap()["email"], AttributeValue::S("ada.lovelace@intranet".to_string()));
+
+        let otp_item = Item::from([
+            ("email".to_string(), AttributeValue::S(legacy.to_string())),
+            ("otp_hash".to_string(), AttributeValue::S("1:abc".to_string())),
+            ("created_at".to_string(), AttributeValue::N("1700000000".to_string())),
+            ("expires_at".to_string(), AttributeValue::N("1700000300".to_string())),
+            ("ttl".to_string(), AttributeValue::N("1700000300".to_string())),
+            ("challenge_id".to_string(), AttributeValue::S("challenge-1".to_string())),
+            ("attempts".to_string(), AttributeValue::N("0".to_string())),
+        ]);
+        assert_eq!(OTPRecord::from_item(otp_item).unwrap().email, "ada.lovelace@intranet");
+    }
+
+    #[test]
+    fn test_status_change_attribute_matches_stored_strings() {
+        let change = StatusChange {
+            from: UserStatus::AwaitingReview,
+            to: UserStatus::Active,
+            actor: "admin-1".to_string(),
+            changed_at: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+        };
+        let attribute = to_attribute(&change).unwrap();
+        let entry = attribute.as_m().unwrap();
+        assert_eq!(entry["to"], AttributeValue::S(UserStatus::Active.as_str().to_string()));
+        assert_eq!(entry["changed_at"], AttributeValue::S("2024-03-01T12:00:00+00:00".to_string()));
+
+        assert_eq!(from_attribute::<StatusChange>(attribute).unwrap(), change);
+    }
+
+    #[test]
+    fn test_missing_required_attribute_is_an_error() {
+        let item = Item::from([("email".to_string(), AttributeValue::S("a@example.com".to_string()))]);
+        assert!(matches!(OTPRecord::from_item(item), Err(AuthError::InternalError(_))));
+    }
+}
@@ -1,3 +1,4 @@
+use notifications_shared::InvalidEmailAddress;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,21 +12,41 @@ pub enum AuthError {
     #[error("OTP expired")]
     OTPExpired,
     
+    #[error("Too many failed attempts: {0}")]
+    TooManyAttempts(String),
+    
+    #[error("Invalid session: {0}")]
+    InvalidSession(String),
+    
     #[error("User not found: {0}")]
     UserNotFound(String),
     
+    #[error("Invalid status transition: {0}")]
+    InvalidStatusTransition(String),
+    
     #[error("Email delivery failed: {0}")]
     EmailDeliveryFailed(String),
     
+    /// Delivery failed for a reason that may clear up on its own (throttling, timeouts)
+    #[error("Email delivery temporarily unavailable: {0}")]
+    EmailDeliveryUnavailable(String),
+    
     #[error("DynamoDB error: {0}")]
     DynamoDBError(String),
     
+    /// A transaction was cancelled only because a concurrent one touched the same items
+    #[error("Transaction conflict: {0}")]
+    TransactionConflict(String),
+    
     #[error("SES error: {0}")]
     SESError(String),
     
     #[error("Validation error: {0}")]
     ValidationError(String),
     
+    #[error("Sign-up rejected: {0}")]
+    SignupRejected(crate::SignupRejection),
+    
     #[error("Internal error: {0}")]
     InternalError(String),
 }
@@ -42,4 +63,10 @@ impl From<aws_sdk_ses::Error> for AuthError {
     }
 }
 
+impl From<InvalidEmailAddress> for AuthError {
+    fn from(err: InvalidEmailAddress) -> Self {
+        AuthError::ValidationError(err.to_string())
+    }
+}
+
 pub type AuthResult<T> = Result<T, AuthError>;
\ No newline at end of file
@@ -1,11 +1,23 @@
 pub mod models;
 pub mod services;
+pub mod repositories;
 pub mod utils;
 pub mod errors;
 pub mod naming;
+pub mod policy;
+pub mod codec;
+pub mod claims;
+pub mod capture;
 
 pub use models::*;
 pub use services::*;
+pub use repositories::*;
 pub use utils::*;
 pub use errors::*;
-pub use naming::*;
\ No newline at end of file
+pub use naming::*;
+pub use policy::*;
+pub use codec::*;
+pub use claims::*;
+pub use capture::*;
+
+pub use notifications_shared::{EmailAddress, EmailRequest, SubAddressing};
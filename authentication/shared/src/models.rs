@@ -1,9 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::{AuthError, RateLimitWindow};
+use notifications_shared::EmailAddress;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UserStatus {
+    #[default]
     #[serde(rename = "REGISTRATION_EMAIL_NOT_VERIFIED")]
     RegistrationEmailNotVerified,
     #[serde(rename = "REGISTRATION_NEED_USER_INFO")]
@@ -18,46 +23,479 @@ pub enum UserStatus {
     Rejected,
 }
 
-impl Default for UserStatus {
-    fn default() -> Self {
-        UserStatus::RegistrationEmailNotVerified
+impl UserStatus {
+    /// The status as stored in DynamoDB and sessions
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::RegistrationEmailNotVerified => "REGISTRATION_EMAIL_NOT_VERIFIED",
+            UserStatus::RegistrationNeedUserInfo => "REGISTRATION_NEED_USER_INFO",
+            UserStatus::RegistrationNeedStripe => "REGISTRATION_NEED_STRIPE",
+            UserStatus::AwaitingReview => "AWAITING_REVIEW",
+            UserStatus::Active => "ACTIVE",
+            UserStatus::Rejected => "REJECTED",
+        }
+    }
+
+    /// Statuses a user in this status may move to next
+    pub fn allowed_transitions(&self) -> &'static [UserStatus] {
+        match self {
+            UserStatus::RegistrationEmailNotVerified => &[UserStatus::RegistrationNeedUserInfo],
+            UserStatus::RegistrationNeedUserInfo => &[UserStatus::RegistrationNeedStripe],
+            UserStatus::RegistrationNeedStripe => &[UserStatus::AwaitingReview],
+            UserStatus::AwaitingReview => &[UserStatus::Active, UserStatus::Rejected],
+            UserStatus::Active => &[],
+            // A rejected user can fix their profile and ask for another review
+            UserStatus::Rejected => &[UserStatus::AwaitingReview],
+        }
+    }
+
+    pub fn can_transition_to(&self, to: UserStatus) -> bool {
+        self.allowed_transitions().contains(&to)
     }
 }
 
+impl FromStr for UserStatus {
+    type Err = AuthError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "REGISTRATION_EMAIL_NOT_VERIFIED" => Ok(UserStatus::RegistrationEmailNotVerified),
+            "REGISTRATION_NEED_USER_INFO" => Ok(UserStatus::RegistrationNeedUserInfo),
+            "REGISTRATION_NEED_STRIPE" => Ok(UserStatus::RegistrationNeedStripe),
+            "AWAITING_REVIEW" => Ok(UserStatus::AwaitingReview),
+            "ACTIVE" => Ok(UserStatus::Active),
+            "REJECTED" => Ok(UserStatus::Rejected),
+            _ => Err(AuthError::InternalError(format!("Invalid status: {}", value))),
+        }
+    }
+}
+
+/// One entry in a user's status history, kept on the user's item for auditing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub from: UserStatus,
+    pub to: UserStatus,
+    /// Who made the change: the Lambda for automatic steps, or the reviewing admin
+    pub actor: String,
+    #[serde(with = "crate::codec::rfc3339")]
+    pub changed_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserProfile {
     pub user_id: String,
-    pub email: String,
+    #[serde(with = "crate::codec::stored_email")]
+    pub email: EmailAddress,
     pub status: UserStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub full_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub content_description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub content_link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub stripe_account_id: Option<String>,
+    #[serde(with = "crate::codec::rfc3339")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::codec::rfc3339")]
     pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reviewed_by: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::codec::rfc3339::option"
+    )]
     pub reviewed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rejection_reason: Option<String>,
+    /// Set when the email is verified and cleared once the welcome email is queued, so a
+    /// welcome that failed to queue is retried on the next sign-in
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub welcome_pending: bool,
+    /// Set with the review decision and cleared once the approved or rejected email is queued,
+    /// so an email that failed to queue is sent when the review call is retried
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub review_email_pending: bool,
+}
+
+impl UserProfile {
+    /// First word of the user's full name for email greetings, or "there" before they've given one
+    pub fn first_name(&self) -> &str {
+        self.full_name
+            .as_deref()
+            .and_then(|name| name.split_whitespace().next())
+            .unwrap_or("there")
+    }
+}
+
+/// An admin's decision on a creator awaiting review
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewDecision {
+    Approve,
+    Reject { reason: String },
+}
+
+impl ReviewDecision {
+    /// The status the decision moves the user to
+    pub fn status(&self) -> UserStatus {
+        match self {
+            ReviewDecision::Approve => UserStatus::Active,
+            ReviewDecision::Reject { .. } => UserStatus::Rejected,
+        }
+    }
+}
+
+/// Which account to erase: by email, or by user ID when the email isn't known
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionTarget {
+    Email(EmailAddress),
+    UserId(String),
+}
+
+/// What an account deletion removed. Every count is zero on a re-run of a deletion that
+/// already completed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeletionReport {
+    /// The user ID, if the target was given by it or the users row was still there
+    pub user_id: Option<String>,
+    /// The email, if the target was given by it or the users row was still there
+    pub email: Option<EmailAddress>,
+    pub user_deleted: bool,
+    pub otp_deleted: bool,
+    pub rate_limits_deleted: usize,
+    pub sessions_deleted: usize,
+    /// Cognito users deleted: the one that created the users row and any others whose email
+    /// canonicalises to the same address
+    pub identities_deleted: usize,
+}
+
+/// Creator details collected once the email is verified
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileDetails {
+    pub full_name: String,
+    pub content_description: String,
+    pub content_link: String,
+}
+
+/// One page of a user listing
+#[derive(Debug, Clone, Serialize)]
+pub struct UserPage {
+    pub users: Vec<UserProfile>,
+    /// Opaque cursor for the next page, or None on the last page
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OTPRecord {
-    pub email: String,
+    #[serde(with = "crate::codec::stored_email")]
+    pub email: EmailAddress,
     pub otp_hash: String,
     pub created_at: i64,
     pub expires_at: i64,
     pub ttl: i64,
     pub challenge_id: String,
     pub attempts: u8,
+    /// Set once too many wrong answers were given; no OTP is issued or accepted until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RateLimitRecord {
+impl OTPRecord {
+    /// Whether the email is locked out at the given timestamp
+    pub fn is_locked(&self, now: i64) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+/// A row in the user-sessions table, shared with the webapp (see docs/session-management.md)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub session_id: String,
+    pub user_id: String,
     pub email: String,
-    pub request_timestamp: i64,
+    pub user_status: String,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub created_at: i64,
+    /// Last time the sliding expiry was extended
+    pub last_accessed: i64,
+    /// Application-level expiry and DynamoDB TTL attribute
+    pub expires_at: i64,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl Session {
+    /// Whether the session has expired at the given timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at < now
+    }
+}
+
+/// Custom auth challenge types, selected via the `challenge_type` client metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChallengeType {
+    /// 6-digit code sent by email
+    #[default]
+    #[serde(rename = "OTP_EMAIL")]
+    OtpEmail,
+    /// Signed, single-use sign-in link sent by email
+    #[serde(rename = "MAGIC_LINK")]
+    MagicLink,
+}
+
+impl ChallengeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeType::OtpEmail => "OTP_EMAIL",
+            ChallengeType::MagicLink => "MAGIC_LINK",
+        }
+    }
+
+    /// How long the challenge answer stays valid, in seconds
+    pub fn validity_seconds(&self) -> i64 {
+        match self {
+            ChallengeType::OtpEmail => 5 * 60,
+            ChallengeType::MagicLink => 15 * 60,
+        }
+    }
+}
+
+impl FromStr for ChallengeType {
+    type Err = AuthError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "OTP_EMAIL" => Ok(ChallengeType::OtpEmail),
+            "MAGIC_LINK" => Ok(ChallengeType::MagicLink),
+            _ => Err(AuthError::ValidationError(format!(
+                "Unsupported challenge type: {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Which path a challenge email took to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailDelivery {
+    /// Sent through SES while the challenge was created
+    Direct,
+    /// Handed to the notification queue, to be sent shortly
+    Queued,
+}
+
+impl EmailDelivery {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailDelivery::Direct => "DIRECT",
+            EmailDelivery::Queued => "QUEUED",
+        }
+    }
+}
+
+/// What create-auth-challenge records as the challenge_metadata of each challenge it issues.
+/// Cognito hands it back in the session on later triggers, which is how a retry within the same
+/// session finds the challenge the user is still answering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeMetadata {
+    pub challenge_type: ChallengeType,
+    pub challenge_id: String,
+}
+
+impl ChallengeMetadata {
+    /// Metadata recorded when a challenge could not be issued
+    pub const ERROR: &'static str = "ERROR";
+
+    pub fn new(challenge_type: ChallengeType, challenge_id: String) -> Self {
+        Self {
+            challenge_type,
+            challenge_id,
+        }
+    }
+
+    /// Parse metadata written by `to_string`, returning None for anything else (including
+    /// ERROR and the bare `<type>_SENT` recorded before challenge IDs were included)
+    pub fn parse(value: &str) -> Option<Self> {
+        let (sent, challenge_id) = value.split_once(':')?;
+        let challenge_type = sent.strip_suffix("_SENT")?.parse().ok()?;
+        if challenge_id.is_empty() {
+            return None;
+        }
+        Some(Self::new(challenge_type, challenge_id.to_string()))
+    }
+}
+
+impl std::fmt::Display for ChallengeMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_SENT:{}", self.challenge_type.as_str(), self.challenge_id)
+    }
+}
+
+/// Why the pre-signup trigger turned a sign-up away. Cognito hands the message to the client as
+/// `PreSignUp failed with error <CODE>: <message>.`, so the code is what clients match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignupRejection {
+    InvalidEmail,
+    /// The address or its domain is on the deny list, e.g. a disposable email provider
+    EmailNotAllowed,
+    /// Sign-ups are limited to the allow list and the address isn't on it
+    InviteOnly,
+}
+
+impl SignupRejection {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SignupRejection::InvalidEmail => "INVALID_EMAIL",
+            SignupRejection::EmailNotAllowed => "EMAIL_NOT_ALLOWED",
+            SignupRejection::InviteOnly => "SIGNUP_INVITE_ONLY",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            SignupRejection::InvalidEmail => "The email address is not valid",
+            SignupRejection::EmailNotAllowed => "Sign-ups from this email address are not allowed",
+            SignupRejection::InviteOnly => "Sign-ups are currently by invitation only",
+        }
+    }
+}
+
+impl std::fmt::Display for SignupRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+/// A fixed-window request counter. Stored as one rate-limits item per counter (see
+/// `RateLimitRecord`), with the key in the `email` partition key, the window start in the
+/// `request_timestamp` sort key, the count in `request_count`, and a TTL at the end of the window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitCounter {
+    /// `<action>#<window seconds>#<key>` (see `rate_limit_key`)
+    pub key: String,
+    pub window: RateLimitWindow,
+    pub window_start: i64,
+}
+
+impl RateLimitCounter {
+    /// When the window closes and the counter stops applying
+    pub fn window_end(&self) -> i64 {
+        self.window_start + self.window.window_seconds
+    }
+}
+
+/// A rate-limits item as stored: one counter window. Items are written by the conditional
+/// `ADD` in `RateLimitService`, never put whole, but are decoded through this.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitRecord {
+    /// The counter key (see `rate_limit_key`); the attribute is named for the original email key
+    #[serde(rename = "email")]
+    pub key: String,
+    /// Window start, the sort key
+    #[serde(rename = "request_timestamp")]
+    pub window_start: i64,
+    pub request_count: i64,
     pub ttl: i64,
 }
 
+/// Requests counted against one rate limit window, as reported in a data export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitUsage {
+    pub action: String,
+    pub window_seconds: i64,
+    pub window_start: i64,
+    pub request_count: usize,
+}
+
+/// Version of the `UserDataExport` layout; bump it when sections are added, or fields are
+/// renamed or removed
+pub const USER_DATA_EXPORT_VERSION: u32 = 2;
+
+/// A session as exported to its owner. The session ID is a bearer credential, so it is left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionExport {
+    pub user_status: String,
+    pub created_at: i64,
+    pub last_accessed: i64,
+    pub expires_at: i64,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl From<&Session> for SessionExport {
+    fn from(session: &Session) -> Self {
+        Self {
+            user_status: session.user_status.clone(),
+            created_at: session.created_at,
+            last_accessed: session.last_accessed,
+            expires_at: session.expires_at,
+            ip_address: session.ip_address.clone(),
+            user_agent: session.user_agent.clone(),
+        }
+    }
+}
+
+/// A pending OTP challenge as exported to its owner, without the code hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OtpExport {
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub attempts: u8,
+    pub locked_until: Option<i64>,
+}
+
+impl From<&OTPRecord> for OtpExport {
+    fn from(record: &OTPRecord) -> Self {
+        Self {
+            created_at: record.created_at,
+            expires_at: record.expires_at,
+            attempts: record.attempts,
+            locked_until: record.locked_until,
+        }
+    }
+}
+
+/// Notification delivery records for the email. The notification domain sends queued emails
+/// straight to SES and keeps no per-recipient record, so the section states that none are stored
+/// rather than being left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationsExport {
+    pub delivery_records_stored: bool,
+    pub delivery_records: Vec<serde_json::Value>,
+    pub note: String,
+}
+
+impl NotificationsExport {
+    pub fn none_stored() -> Self {
+        Self {
+            delivery_records_stored: false,
+            delivery_records: Vec::new(),
+            note: "No per-recipient delivery records are stored: emails are sent from the \
+                   notification queue straight to SES"
+                .to_string(),
+        }
+    }
+}
+
+/// Everything the authentication domain holds about one email, for a subject access request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDataExport {
+    /// USER_DATA_EXPORT_VERSION at the time of export
+    pub export_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub email: EmailAddress,
+    /// None if the email never signed up, or the account has been deleted
+    pub user: Option<UserProfile>,
+    pub status_history: Vec<StatusChange>,
+    pub sessions: Vec<SessionExport>,
+    pub pending_otp: Option<OtpExport>,
+    pub rate_limits: Vec<RateLimitUsage>,
+    pub notifications: NotificationsExport,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CognitoEvent {
     pub request: CognitoRequest,
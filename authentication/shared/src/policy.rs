@@ -0,0 +1,718 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::{
+    rate_limit_key, AuthError, AuthResult, EmailAddress, RateLimitCounter, SignupRejection,
+    SubAddressing,
+};
+
+/// Default number of wrong answers allowed before an OTP is invalidated
+pub const DEFAULT_OTP_MAX_ATTEMPTS: u8 = 3;
+
+/// Default lockout duration in minutes after too many wrong answers
+pub const DEFAULT_OTP_LOCKOUT_MINUTES: i64 = 15;
+
+/// Default number of answers a user may give within one sign-in session
+pub const DEFAULT_CHALLENGE_MAX_ATTEMPTS: u8 = 3;
+
+/// Default session duration in minutes
+pub const DEFAULT_SESSION_DURATION_MINUTES: i64 = 20;
+
+/// Sessions are only extended in DynamoDB if last_accessed is older than this (5 minutes)
+pub const SESSION_UPDATE_THRESHOLD_SECONDS: i64 = 5 * 60;
+
+/// Create the email sub-addressing policy from the optional EMAIL_SUBADDRESS_POLICY environment
+/// variable (`preserve` or `strip`, preserving by default)
+pub fn sub_addressing_from_env() -> Result<SubAddressing, AuthError> {
+    SubAddressing::from_env().map_err(|e| AuthError::InternalError(e.to_string()))
+}
+
+/// How OTP and magic link emails reach the user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OtpDeliveryStrategy {
+    /// Send through SES in the request; any failure fails the challenge
+    Direct,
+    /// Hand the email to the notification queue
+    Queued,
+    /// Send through SES, queueing the email instead if SES fails transiently
+    #[default]
+    DirectWithFallback,
+}
+
+impl OtpDeliveryStrategy {
+    /// Read the optional OTP_DELIVERY_STRATEGY environment variable (`direct`, `queued` or
+    /// `fallback`, falling back by default)
+    pub fn from_env() -> Result<Self, AuthError> {
+        match std::env::var("OTP_DELIVERY_STRATEGY") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+impl FromStr for OtpDeliveryStrategy {
+    type Err = AuthError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "direct" => Ok(OtpDeliveryStrategy::Direct),
+            "queued" => Ok(OtpDeliveryStrategy::Queued),
+            "fallback" => Ok(OtpDeliveryStrategy::DirectWithFallback),
+            other => Err(AuthError::InternalError(format!(
+                "OTP_DELIVERY_STRATEGY must be direct, queued or fallback, not '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Policy for OTP verification attempts and lockout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtpPolicy {
+    /// Wrong answers allowed before the OTP is invalidated and the email locked out
+    pub max_attempts: u8,
+    /// How long the email is locked out for, in seconds
+    pub lockout_seconds: i64,
+}
+
+impl Default for OtpPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_OTP_MAX_ATTEMPTS,
+            lockout_seconds: DEFAULT_OTP_LOCKOUT_MINUTES * 60,
+        }
+    }
+}
+
+impl OtpPolicy {
+    /// Create OtpPolicy from optional OTP_MAX_ATTEMPTS and OTP_LOCKOUT_MINUTES environment variables
+    pub fn from_env() -> Result<Self, AuthError> {
+        let mut policy = Self::default();
+
+        if let Ok(value) = std::env::var("OTP_MAX_ATTEMPTS") {
+            policy.max_attempts = value
+                .parse()
+                .ok()
+                .filter(|attempts| *attempts > 0)
+                .ok_or_else(|| {
+                    AuthError::InternalError(
+                        "OTP_MAX_ATTEMPTS must be a positive integer".to_string(),
+                    )
+                })?;
+        }
+
+        if let Ok(value) = std::env::var("OTP_LOCKOUT_MINUTES") {
+            let minutes: i64 = value
+                .parse()
+                .ok()
+                .filter(|minutes| *minutes > 0)
+                .ok_or_else(|| {
+                    AuthError::InternalError(
+                        "OTP_LOCKOUT_MINUTES must be a positive integer".to_string(),
+                    )
+                })?;
+            policy.lockout_seconds = minutes * 60;
+        }
+
+        Ok(policy)
+    }
+}
+
+/// How many answers a user may give within one Cognito sign-in session before it fails.
+/// A wrong answer below the limit gets the same challenge again rather than a new email.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChallengeAttemptPolicy {
+    pub max_attempts: u8,
+}
+
+impl Default for ChallengeAttemptPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_CHALLENGE_MAX_ATTEMPTS,
+        }
+    }
+}
+
+impl ChallengeAttemptPolicy {
+    /// Fail the session on the first wrong answer
+    pub fn single_attempt() -> Self {
+        Self { max_attempts: 1 }
+    }
+
+    /// Create ChallengeAttemptPolicy from the optional CHALLENGE_MAX_ATTEMPTS environment
+    /// variable; 1 fails the session on the first wrong answer
+    pub fn from_env() -> Result<Self, AuthError> {
+        let Ok(value) = std::env::var("CHALLENGE_MAX_ATTEMPTS") else {
+            return Ok(Self::default());
+        };
+        value
+            .parse()
+            .ok()
+            .filter(|attempts| *attempts > 0)
+            .map(|max_attempts| Self { max_attempts })
+            .ok_or_else(|| {
+                AuthError::InternalError(
+                    "CHALLENGE_MAX_ATTEMPTS must be a positive integer".to_string(),
+                )
+            })
+    }
+
+    /// Whether a session that has seen this many wrong answers may try again
+    pub fn allows_retry(&self, wrong_answers: usize) -> bool {
+        wrong_answers < usize::from(self.max_attempts)
+    }
+}
+
+/// Sliding expiry policy for user sessions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionPolicy {
+    /// How long a session lives without activity, in seconds
+    pub duration_seconds: i64,
+    /// Minimum time between sliding expiry updates, in seconds
+    pub update_threshold_seconds: i64,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            duration_seconds: DEFAULT_SESSION_DURATION_MINUTES * 60,
+            update_threshold_seconds: SESSION_UPDATE_THRESHOLD_SECONDS,
+        }
+    }
+}
+
+impl SessionPolicy {
+    /// Create SessionPolicy from the optional SESSION_DURATION_MINUTES environment variable
+    pub fn from_env() -> Result<Self, AuthError> {
+        let mut policy = Self::default();
+
+        if let Ok(value) = std::env::var("SESSION_DURATION_MINUTES") {
+            let minutes: i64 = value
+                .parse()
+                .ok()
+                .filter(|minutes| *minutes > 0)
+                .ok_or_else(|| {
+                    AuthError::InternalError(
+                        "SESSION_DURATION_MINUTES must be a positive integer".to_string(),
+                    )
+                })?;
+            policy.duration_seconds = minutes * 60;
+        }
+
+        Ok(policy)
+    }
+}
+
+/// Actions that are rate limited independently, each against its own key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitAction {
+    /// Sending a new OTP or magic link, keyed by email
+    OtpSend,
+    /// Submitting an answer to a challenge, keyed by email
+    OtpVerify,
+    /// Explicitly asking for the code to be sent again, keyed by email
+    OtpResend,
+    /// Sending a new OTP or magic link, keyed by the client IP
+    OtpSendIp,
+    /// Sending a new OTP or magic link, keyed by the recipient's email domain
+    OtpSendDomain,
+}
+
+impl RateLimitAction {
+    pub const ALL: [RateLimitAction; 5] = [
+        RateLimitAction::OtpSend,
+        RateLimitAction::OtpVerify,
+        RateLimitAction::OtpResend,
+        RateLimitAction::OtpSendIp,
+        RateLimitAction::OtpSendDomain,
+    ];
+
+    /// Actions keyed by the user's own email, whose counters are personal data
+    pub const EMAIL_KEYED: [RateLimitAction; 3] = [
+        RateLimitAction::OtpSend,
+        RateLimitAction::OtpVerify,
+        RateLimitAction::OtpResend,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitAction::OtpSend => "otp_send",
+            RateLimitAction::OtpVerify => "otp_verify",
+            RateLimitAction::OtpResend => "otp_resend",
+            RateLimitAction::OtpSendIp => "otp_send_ip",
+            RateLimitAction::OtpSendDomain => "otp_send_domain",
+        }
+    }
+
+    /// Environment variable overriding this action's policy, e.g. RATE_LIMIT_OTP_SEND=3/15m,10/1d
+    pub fn env_var(&self) -> String {
+        format!("RATE_LIMIT_{}", self.as_str().to_uppercase())
+    }
+}
+
+/// A single "at most N requests per window" limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitWindow {
+    pub max_requests: usize,
+    pub window_seconds: i64,
+}
+
+impl RateLimitWindow {
+    pub const fn new(max_requests: usize, window_seconds: i64) -> Self {
+        Self {
+            max_requests,
+            window_seconds,
+        }
+    }
+
+    /// Parse a window such as `3/15m` (units: s, m, h, d)
+    pub fn parse(spec: &str) -> Result<Self, AuthError> {
+        let invalid = || AuthError::InternalError(format!("Invalid rate limit window: {}", spec));
+
+        let (max_requests, duration) = spec.trim().split_once('/').ok_or_else(invalid)?;
+        let max_requests: usize = max_requests.parse().map_err(|_| invalid())?;
+
+        let unit_seconds = match duration.chars().last() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let amount: i64 = duration[..duration.len() - 1].parse().map_err(|_| invalid())?;
+
+        if max_requests == 0 || amount <= 0 {
+            return Err(invalid());
+        }
+        Ok(Self::new(max_requests, amount * unit_seconds))
+    }
+
+    /// Start of the fixed window containing `now`; windows are aligned to the Unix epoch
+    pub fn window_start(&self, now: i64) -> i64 {
+        now - now.rem_euclid(self.window_seconds)
+    }
+}
+
+/// Rate limit policy made of one or more windows that must all be satisfied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    pub windows: Vec<RateLimitWindow>,
+}
+
+impl RateLimitPolicy {
+    pub fn new(windows: Vec<RateLimitWindow>) -> Self {
+        Self { windows }
+    }
+
+    /// Default policy for an action
+    pub fn for_action(action: RateLimitAction) -> Self {
+        const MINUTE: i64 = 60;
+        const HOUR: i64 = 60 * MINUTE;
+        const DAY: i64 = 24 * HOUR;
+        match action {
+            RateLimitAction::OtpSend => Self::new(vec![
+                RateLimitWindow::new(3, 15 * MINUTE),
+                RateLimitWindow::new(10, DAY),
+            ]),
+            RateLimitAction::OtpVerify => Self::new(vec![RateLimitWindow::new(10, 15 * MINUTE)]),
+            RateLimitAction::OtpResend => Self::new(vec![RateLimitWindow::new(1, MINUTE)]),
+            // Loose enough for a shared office or carrier NAT, tight enough to stop spraying
+            RateLimitAction::OtpSendIp => Self::new(vec![
+                RateLimitWindow::new(20, HOUR),
+                RateLimitWindow::new(100, DAY),
+            ]),
+            // Large providers share a domain across many users; tune to real traffic
+            RateLimitAction::OtpSendDomain => Self::new(vec![RateLimitWindow::new(200, HOUR)]),
+        }
+    }
+
+    /// Parse a comma-separated list of windows such as `3/15m,10/1d`. Each window length may
+    /// appear once, since windows of the same length share one counter item.
+    pub fn parse(spec: &str) -> Result<Self, AuthError> {
+        let windows = spec
+            .split(',')
+            .map(RateLimitWindow::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, window) in windows.iter().enumerate() {
+            if windows[..i].iter().any(|earlier| earlier.window_seconds == window.window_seconds) {
+                return Err(AuthError::InternalError(format!(
+                    "Duplicate rate limit window length in: {}",
+                    spec
+                )));
+            }
+        }
+        Ok(Self::new(windows))
+    }
+}
+
+/// Rate limit policies for every action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitPolicies {
+    policies: HashMap<RateLimitAction, RateLimitPolicy>,
+}
+
+impl Default for RateLimitPolicies {
+    fn default() -> Self {
+        Self {
+            policies: RateLimitAction::ALL
+                .into_iter()
+                .map(|action| (action, RateLimitPolicy::for_action(action)))
+                .collect(),
+        }
+    }
+}
+
+impl RateLimitPolicies {
+    /// Create RateLimitPolicies from optional RATE_LIMIT_<ACTION> environment variables
+    pub fn from_env() -> Result<Self, AuthError> {
+        let mut policies = Self::default();
+        for action in RateLimitAction::ALL {
+            if let Ok(spec) = std::env::var(action.env_var()) {
+                policies = policies.with_policy(action, RateLimitPolicy::parse(&spec)?);
+            }
+        }
+        Ok(policies)
+    }
+
+    /// Replace the policy for one action
+    pub fn with_policy(mut self, action: RateLimitAction, policy: RateLimitPolicy) -> Self {
+        self.policies.insert(action, policy);
+        self
+    }
+
+    pub fn policy(&self, action: RateLimitAction) -> &RateLimitPolicy {
+        &self.policies[&action]
+    }
+
+    /// Partition keys of the counters kept for the key under each action's windows, across
+    /// every window start
+    pub fn counter_keys(&self, actions: &[RateLimitAction], key: &str) -> Vec<String> {
+        self.counter_partitions(actions, key)
+            .into_iter()
+            .map(|(_, _, counter_key)| counter_key)
+            .collect()
+    }
+
+    /// Like `counter_keys`, alongside the action and window each partition belongs to
+    pub fn counter_partitions(
+        &self,
+        actions: &[RateLimitAction],
+        key: &str,
+    ) -> Vec<(RateLimitAction, RateLimitWindow, String)> {
+        actions
+            .iter()
+            .flat_map(|action| {
+                self.policy(*action)
+                    .windows
+                    .iter()
+                    .map(move |window| (*action, *window, rate_limit_key(key, *action, window)))
+            })
+            .collect()
+    }
+
+    /// The current fixed-window counter of every window of each action's policy for its key,
+    /// i.e. everything one request for each (action, key) pair is charged against at `now`
+    pub fn counters(&self, requests: &[(RateLimitAction, String)], now: i64) -> Vec<RateLimitCounter> {
+        requests
+            .iter()
+            .flat_map(|(action, key)| {
+                self.policy(*action)
+                    .windows
+                    .iter()
+                    .map(move |window| RateLimitCounter {
+                        key: rate_limit_key(key, *action, window),
+                        window: *window,
+                        window_start: window.window_start(now),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Whether anyone may sign up, or only the addresses and domains on the allow list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignupMode {
+    #[default]
+    Open,
+    Allowlist,
+}
+
+/// Sign-up allow and deny entries. Each is a full address (`ada@example.com`) or a domain,
+/// which covers its subdomains too (`example.com`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignupLists {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// The sign-up policy file bundled with the pre-signup trigger: a mode and lists shared by
+/// every environment, plus per-environment overrides
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SignupPolicyDocument {
+    #[serde(default)]
+    pub mode: SignupMode,
+    #[serde(flatten)]
+    pub lists: SignupLists,
+    #[serde(default)]
+    pub environments: HashMap<String, EnvironmentSignupPolicy>,
+}
+
+/// One environment's section of a SignupPolicyDocument
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EnvironmentSignupPolicy {
+    /// Replaces the document's mode
+    pub mode: Option<SignupMode>,
+    /// Added to the document's lists
+    #[serde(flatten)]
+    pub lists: SignupLists,
+}
+
+impl SignupPolicyDocument {
+    pub fn parse(json: &str) -> AuthResult<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| AuthError::InternalError(format!("Invalid sign-up policy: {}", e)))
+    }
+
+    /// The policy for one environment; fails on any entry that isn't an address or domain
+    pub fn for_environment(&self, environment: &str) -> AuthResult<SignupPolicy> {
+        let mut policy = SignupPolicy::new(self.mode);
+        policy.add_lists(&self.lists)?;
+        if let Some(overrides) = self.environments.get(environment) {
+            if let Some(mode) = overrides.mode {
+                policy.mode = mode;
+            }
+            policy.add_lists(&overrides.lists)?;
+        }
+        Ok(policy)
+    }
+}
+
+/// Who may sign up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignupPolicy {
+    pub mode: SignupMode,
+    allow: SignupEntries,
+    deny: SignupEntries,
+}
+
+impl SignupPolicy {
+    pub fn new(mode: SignupMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Add an address or domain to the allow list
+    pub fn allow(&mut self, entry: &str) -> AuthResult<()> {
+        self.allow.add(entry)
+    }
+
+    /// Add an address or domain to the deny list
+    pub fn deny(&mut self, entry: &str) -> AuthResult<()> {
+        self.deny.add(entry)
+    }
+
+    pub fn add_lists(&mut self, lists: &SignupLists) -> AuthResult<()> {
+        for entry in &lists.allow {
+            self.allow(entry)?;
+        }
+        for entry in &lists.deny {
+            self.deny(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Decide whether the address may sign up. An address allowed by name gets in even if its
+    /// domain is denied; otherwise the deny list wins over the allow list.
+    pub fn check(&self, email: &EmailAddress) -> Result<(), SignupRejection> {
+        if self.allow.emails.contains(email) {
+            return Ok(());
+        }
+        if self.deny.matches(email) {
+            return Err(SignupRejection::EmailNotAllowed);
+        }
+        if self.mode == SignupMode::Allowlist && !self.allow.matches_domain(email) {
+            return Err(SignupRejection::InviteOnly);
+        }
+        Ok(())
+    }
+}
+
+/// Canonical addresses and ASCII domains from a sign-up list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SignupEntries {
+    emails: HashSet<EmailAddress>,
+    domains: HashSet<String>,
+}
+
+impl SignupEntries {
+    fn add(&mut self, entry: &str) -> AuthResult<()> {
+        let entry = entry.trim();
+        let invalid = |_| AuthError::InternalError(format!("Invalid sign-up list entry: {}", entry));
+        if entry.contains('@') {
+            self.emails.insert(EmailAddress::parse(entry).map_err(invalid)?);
+        } else {
+            // Canonicalise the domain exactly as an address on it would be
+            let probe = EmailAddress::parse(&format!("probe@{}", entry)).map_err(invalid)?;
+            self.domains.insert(probe.domain().to_string());
+        }
+        Ok(())
+    }
+
+    fn matches(&self, email: &EmailAddress) -> bool {
+        self.emails.contains(email) || self.matches_domain(email)
+    }
+
+    /// Whether the address's domain, or any parent domain, is listed
+    fn matches_domain(&self, email: &EmailAddress) -> bool {
+        std::iter::successors(Some(email.domain()), |domain| {
+            domain.split_once('.').map(|(_, parent)| parent)
+        })
+        .any(|domain| self.domains.contains(domain))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate_limit_policy() {
+        let policy = RateLimitPolicy::parse("3/15m, 10/1d").unwrap();
+        assert_eq!(
+            policy.windows,
+            vec![RateLimitWindow::new(3, 15 * 60), RateLimitWindow::new(10, 24 * 60 * 60)]
+        );
+
+        assert_eq!(RateLimitWindow::parse("5/30s").unwrap(), RateLimitWindow::new(5, 30));
+        assert_eq!(RateLimitWindow::parse("2/1h").unwrap(), RateLimitWindow::new(2, 3600));
+        for invalid in ["", "3", "3/15", "0/1m", "3/0m", "x/1m", "3/1w", "3/15m,5/15m", "3/15m,1/900s"] {
+            assert!(RateLimitPolicy::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_every_action_has_a_policy() {
+        let policies = RateLimitPolicies::default();
+        for action in RateLimitAction::ALL {
+            assert!(!policies.policy(action).windows.is_empty(), "{:?}", action);
+        }
+        assert_eq!(RateLimitAction::OtpSendIp.env_var(), "RATE_LIMIT_OTP_SEND_IP");
+        assert_eq!(RateLimitAction::OtpSendDomain.env_var(), "RATE_LIMIT_OTP_SEND_DOMAIN");
+    }
+
+    #[test]
+    fn test_rate_limit_counters_use_fixed_windows() {
+        let policies = RateLimitPolicies::default()
+            .with_policy(RateLimitAction::OtpSend, RateLimitPolicy::parse("3/15m,10/1d").unwrap());
+        let now = 1_700_000_123;
+
+        let counters = policies.counters(
+            &[
+                (RateLimitAction::OtpSend, "a@example.com".to_string()),
+                (RateLimitAction::OtpResend, "a@example.com".to_string()),
+            ],
+            now,
+        );
+
+        let keys: Vec<&str> = counters.iter().map(|counter| counter.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "otp_send#900#a@example.com",
+                "otp_send#86400#a@example.com",
+                "otp_resend#60#a@example.com",
+            ]
+        );
+        for counter in &counters {
+            assert_eq!(counter.window_start % counter.window.window_seconds, 0);
+            assert!(counter.window_start <= now && now < counter.window_end());
+        }
+        assert_eq!(counters[0].window_end() - now, 900 - now % 900);
+    }
+
+    fn email(address: &str) -> EmailAddress {
+        EmailAddress::parse(address).unwrap()
+    }
+
+    #[test]
+    fn test_signup_policy_open_mode_honours_deny_list() {
+        let mut policy = SignupPolicy::new(SignupMode::Open);
+        policy.deny("Mailinator.com").unwrap();
+        policy.allow("qa@mailinator.com").unwrap();
+
+        assert_eq!(policy.check(&email("ada@example.com")), Ok(()));
+        assert_eq!(
+            policy.check(&email("spam@mailinator.com")),
+            Err(SignupRejection::EmailNotAllowed)
+        );
+        // Subdomains are covered, and a named address beats its denied domain
+        assert_eq!(
+            policy.check(&email("spam@eu.mailinator.com")),
+            Err(SignupRejection::EmailNotAllowed)
+        );
+        assert_eq!(policy.check(&email("QA@mailinator.com")), Ok(()));
+    }
+
+    #[test]
+    fn test_signup_policy_allowlist_mode() {
+        let mut policy = SignupPolicy::new(SignupMode::Allowlist);
+        policy.allow("appreciata.com").unwrap();
+        policy.allow("beta.tester@example.com").unwrap();
+        policy.deny("former.staff@appreciata.com").unwrap();
+
+        assert_eq!(policy.check(&email("team@appreciata.com")), Ok(()));
+        assert_eq!(policy.check(&email("beta.tester@example.com")), Ok(()));
+        assert_eq!(
+            policy.check(&email("someone@example.com")),
+            Err(SignupRejection::InviteOnly)
+        );
+        assert_eq!(
+            policy.check(&email("former.staff@appreciata.com")),
+            Err(SignupRejection::EmailNotAllowed)
+        );
+        // A listed domain doesn't cover lookalikes that merely end with it
+        assert_eq!(
+            policy.check(&email("x@notappreciata.com")),
+            Err(SignupRejection::InviteOnly)
+        );
+
+        assert!(policy.allow("not an entry").is_err());
+        assert!(policy.deny("@").is_err());
+    }
+
+    #[test]
+    fn test_signup_policy_document_environments() {
+        let document = SignupPolicyDocument::parse(
+            r#"{
+                "deny": ["mailinator.com"],
+                "environments": {
+                    "test": { "mode": "allowlist", "allow": ["appreciata.com"] },
+                    "prod": { "deny": ["yopmail.com"] }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let test = document.for_environment("test").unwrap();
+        assert_eq!(test.mode, SignupMode::Allowlist);
+        assert_eq!(test.check(&email("team@appreciata.com")), Ok(()));
+        assert_eq!(test.check(&email("ada@example.com")), Err(SignupRejection::InviteOnly));
+
+        let prod = document.for_environment("prod").unwrap();
+        assert_eq!(prod.mode, SignupMode::Open);
+        assert_eq!(prod.check(&email("ada@example.com")), Ok(()));
+        for denied in ["a@mailinator.com", "a@yopmail.com"] {
+            assert_eq!(prod.check(&email(denied)), Err(SignupRejection::EmailNotAllowed));
+        }
+
+        // Unlisted environments get the shared settings
+        assert_eq!(document.for_environment("staging").unwrap().mode, SignupMode::Open);
+        assert!(SignupPolicyDocument::parse(r#"{"mode": "closed"}"#).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+pub mod in_memory;
+
+pub use in_memory::*;
+
+use async_trait::async_trait;
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::{
+    AuthError, AuthResult, OTPRecord, ProfileDetails, RateLimitAction, RateLimitCounter,
+    RateLimitUsage, RateLimitWindow, ReviewDecision, Session, SignupLists, StatusChange, UserPage,
+    UserProfile, UserStatus,
+};
+use notifications_shared::{EmailAddress, EmailRequest, SubAddressing};
+
+/// Storage for pending OTP challenges, keyed by canonical email
+#[async_trait]
+pub trait OtpStore: Send + Sync {
+    /// Store (or replace) the OTP record for an email
+    async fn store_otp(&self, record: &OTPRecord) -> AuthResult<()>;
+
+    /// Retrieve the OTP record for an email
+    async fn get_otp(&self, email: &EmailAddress) -> AuthResult<Option<OTPRecord>>;
+
+    /// Delete the OTP record for an email, returning false if there was none
+    async fn delete_otp(&self, email: &EmailAddress) -> AuthResult<bool>;
+
+    /// Atomically delete the OTP record if it still belongs to the given challenge and
+    /// is not locked. Returns false if another verification consumed it first.
+    async fn consume_otp(&self, email: &EmailAddress, challenge_id: &str) -> AuthResult<bool>;
+
+    /// Atomically increment the failed attempt counter on the given challenge's OTP,
+    /// returning the new count
+    async fn increment_attempts(&self, email: &EmailAddress, challenge_id: &str) -> AuthResult<u8>;
+
+    /// Lock the email out until the given timestamp, invalidating the pending OTP
+    async fn lock_out(&self, email: &EmailAddress, locked_until: i64) -> AuthResult<()>;
+}
+
+/// Storage for user profiles
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Look up a user by email
+    async fn get_user_by_email(&self, email: &EmailAddress) -> AuthResult<Option<UserProfile>>;
+
+    /// Create a new user keyed by their Cognito user ID. If the ID already has a row that the
+    /// email lookup missed (stored under a legacy raw address, or an email since changed), that
+    /// row is returned instead, with its email re-keyed to `email`.
+    async fn create_user(&self, email: &EmailAddress, cognito_user_id: &str) -> AuthResult<UserProfile>;
+
+    /// Move a user from one status to another, recording the change in their status history.
+    /// Fails with InvalidStatusTransition if the move isn't in the status graph or the user is
+    /// no longer in `from` (e.g. a concurrent change got there first).
+    async fn transition_status(
+        &self,
+        user_id: &str,
+        from: UserStatus,
+        to: UserStatus,
+        actor: &str,
+    ) -> AuthResult<()>;
+
+    /// Every status change for the user, oldest first
+    async fn get_status_history(&self, user_id: &str) -> AuthResult<Vec<StatusChange>>;
+
+    /// Look up a user by their user ID
+    async fn get_user_by_id(&self, user_id: &str) -> AuthResult<Option<UserProfile>>;
+
+    /// Move a user from RegistrationEmailNotVerified to RegistrationNeedUserInfo and set
+    /// welcome_pending in the same guarded write. Returns the updated user.
+    async fn mark_email_verified(&self, user_id: &str, actor: &str) -> AuthResult<UserProfile>;
+
+    /// Clear welcome_pending once the welcome email is queued, returning false if it was
+    /// already clear (another sign-in queued it first)
+    async fn clear_welcome_pending(&self, user_id: &str) -> AuthResult<bool>;
+
+    /// Clear review_email_pending once the review email is queued, returning false if it was
+    /// already clear
+    async fn clear_review_email_pending(&self, user_id: &str) -> AuthResult<bool>;
+
+    /// Save the creator's profile details and move them from RegistrationNeedUserInfo to
+    /// RegistrationNeedStripe in one guarded write. Returns the updated user.
+    async fn complete_profile(
+        &self,
+        user_id: &str,
+        details: &ProfileDetails,
+        actor: &str,
+    ) -> AuthResult<UserProfile>;
+
+    /// Attach a Stripe Connect account to a user in RegistrationNeedStripe. Setting the same
+    /// account again is a no-op; a different account, or any other status, fails with
+    /// InvalidStatusTransition.
+    async fn set_stripe_account_id(&self, user_id: &str, stripe_account_id: &str) -> AuthResult<()>;
+
+    /// Move a user out of AwaitingReview according to the decision, fill in the review fields
+    /// and set review_email_pending, with the same guarantees as transition_status. Returns the
+    /// updated user.
+    async fn record_review(
+        &self,
+        user_id: &str,
+        decision: &ReviewDecision,
+        reviewer_id: &str,
+    ) -> AuthResult<UserProfile>;
+
+    /// Users in a status, oldest first, a page at a time
+    async fn list_users_by_status(
+        &self,
+        status: UserStatus,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> AuthResult<UserPage>;
+
+    /// Delete the user's item, status history included, returning false if it didn't exist
+    async fn delete_user(&self, user_id: &str) -> AuthResult<bool>;
+}
+
+/// Reject a status change that isn't an edge of the status graph
+pub fn validate_status_transition(from: UserStatus, to: UserStatus) -> AuthResult<()> {
+    if from.can_transition_to(to) {
+        Ok(())
+    } else {
+        Err(AuthError::InvalidStatusTransition(format!(
+            "{} cannot move to {}",
+            from.as_str(),
+            to.as_str()
+        )))
+    }
+}
+
+/// Cursor for a user listing ordered by creation time, resuming after the given user
+pub fn user_page_cursor(user: &UserProfile) -> String {
+    format!("{}#{}", user.created_at.to_rfc3339(), user.user_id)
+}
+
+/// Split a cursor from `user_page_cursor` back into its created_at and user_id
+pub fn parse_user_page_cursor(cursor: &str) -> AuthResult<(&str, &str)> {
+    cursor
+        .split_once('#')
+        .filter(|(created_at, user_id)| !created_at.is_empty() && !user_id.is_empty())
+        .ok_or_else(|| AuthError::ValidationError("Invalid page cursor".to_string()))
+}
+
+/// Outgoing notification emails, handed to the notifications domain for delivery
+#[async_trait]
+pub trait EmailQueue: Send + Sync {
+    async fn queue_email(&self, request: EmailRequest) -> AuthResult<()>;
+}
+
+/// Emails sent straight to the user, e.g. through SES. Failures that may clear up on their own
+/// are EmailDeliveryUnavailable; anything else is EmailDeliveryFailed.
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    async fn send_email(&self, request: EmailRequest) -> AuthResult<()>;
+}
+
+/// Fixed-window request counters used for rate limiting, checked against the policy for each
+/// action. The key is whatever the action is limited by: an email, a client IP, or an email domain.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Check and count one request for each (action, key) pair against every window of the
+    /// action's policy, atomically: either every counter has room and all are incremented, or
+    /// none are and RateLimitExceeded says when the binding window resets
+    async fn consume_rate_limits(&self, requests: &[(RateLimitAction, String)]) -> AuthResult<()>;
+
+    /// Delete every counter kept for the key under the given actions, in any window, returning
+    /// how many were removed
+    async fn delete_rate_limits(&self, actions: &[RateLimitAction], key: &str) -> AuthResult<usize>;
+
+    /// Every counter still kept for the key under the given actions, grouped by action and
+    /// window, oldest first within each
+    async fn get_rate_limits(&self, actions: &[RateLimitAction], key: &str) -> AuthResult<Vec<RateLimitUsage>>;
+}
+
+/// Partition key for one window's counters for a key (email, IP, or domain) and action
+pub fn rate_limit_key(key: &str, action: RateLimitAction, window: &RateLimitWindow) -> String {
+    format!("{}#{}#{}", action.as_str(), window.window_seconds, key)
+}
+
+/// RateLimitExceeded for the counters that were full, reporting the longest wait among them
+pub fn rate_limit_exceeded(full: &[&RateLimitCounter], now: i64) -> AuthError {
+    for counter in full {
+        tracing::warn!("Rate limit exceeded: {}", counter.key);
+    }
+    let reset_minutes = full
+        .iter()
+        .map(|counter| counter.window_end() - now)
+        .max()
+        .unwrap_or(0)
+        / 60;
+    AuthError::RateLimitExceeded(format!(
+        "Too many requests. Try again in {} minutes.",
+        reset_minutes.max(1)
+    ))
+}
+
+/// Attempts made at a rate limit transaction before a conflict is reported
+pub const RATE_LIMIT_TRANSACTION_ATTEMPTS: u32 = 3;
+
+/// Run a rate limit transaction, retrying after a short jittered pause while it is cancelled
+/// only by TransactionConflict, which is what concurrent requests charging the same counter get
+pub async fn retry_transaction_conflicts<F, Fut>(mut attempt: F) -> AuthResult<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = AuthResult<()>>,
+{
+    let mut attempts = 1;
+    loop {
+        match attempt().await {
+            Err(AuthError::TransactionConflict(reason)) if attempts < RATE_LIMIT_TRANSACTION_ATTEMPTS => {
+                let delay = rand::thread_rng().gen_range(10..=50 * u64::from(attempts));
+                tracing::warn!("Retrying rate limit transaction in {}ms: {}", delay, reason);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Identity provider operations that reach beyond our own tables
+#[async_trait]
+pub trait IdentityProvider: Send + Sync {
+    /// Invalidate all tokens issued to the user on every device
+    async fn global_sign_out(&self, username: &str) -> AuthResult<()>;
+
+    /// Delete the user from the identity provider, returning false if there was no such user
+    async fn delete_user(&self, username: &str) -> AuthResult<bool>;
+
+    /// Usernames of every user whose email canonicalises to `email` under the sub-addressing
+    /// policy. Several can share one users row, e.g. a sub-address and its base address under
+    /// the strip policy.
+    async fn find_users_by_email(
+        &self,
+        email: &EmailAddress,
+        sub_addressing: SubAddressing,
+    ) -> AuthResult<Vec<String>>;
+}
+
+/// Storage for user sessions, keyed by session ID
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Store a new session (fails if the session ID already exists)
+    async fn create_session(&self, session: &Session) -> AuthResult<()>;
+
+    /// Retrieve a session by ID
+    async fn get_session(&self, session_id: &str) -> AuthResult<Option<Session>>;
+
+    /// Slide an existing session's expiry. Returns false if the session no longer exists.
+    async fn extend_session(&self, session_id: &str, last_accessed: i64, expires_at: i64) -> AuthResult<bool>;
+
+    /// Delete a session, returning false if it didn't exist
+    async fn delete_session(&self, session_id: &str) -> AuthResult<bool>;
+
+    /// All sessions belonging to a user
+    async fn get_sessions_for_user(&self, user_id: &str) -> AuthResult<Vec<Session>>;
+}
+
+/// Sign-up allow and deny entries managed at runtime, on top of the bundled sign-up policy
+#[async_trait]
+pub trait SignupListStore: Send + Sync {
+    async fn get_signup_lists(&self) -> AuthResult<SignupLists>;
+}
@@ -0,0 +1,901 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{
+    current_timestamp, parse_user_page_cursor, rate_limit_exceeded, user_page_cursor, AuthError,
+    AuthResult, EmailQueue, EmailSender, IdentityProvider, OTPRecord, OtpStore, ProfileDetails,
+    RateLimitAction, RateLimitCounter, RateLimitPolicies, RateLimitStore, RateLimitUsage,
+    ReviewDecision, Session, SessionStore, SignupListStore, SignupLists, StatusChange, UserPage,
+    UserProfile, UserRepository, UserStatus, retry_transaction_conflicts, validate_status_transition,
+};
+use notifications_shared::{EmailAddress, EmailRequest, SubAddressing};
+
+/// In-memory OTP store for tests and local development
+#[derive(Debug, Default)]
+pub struct InMemoryOtpStore {
+    records: Mutex<HashMap<EmailAddress, OTPRecord>>,
+}
+
+impl InMemoryOtpStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl OtpStore for InMemoryOtpStore {
+    async fn store_otp(&self, record: &OTPRecord) -> AuthResult<()> {
+        self.records
+            .lock()
+            .unwrap()
+            .insert(record.email.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn get_otp(&self, email: &EmailAddress) -> AuthResult<Option<OTPRecord>> {
+        Ok(self.records.lock().unwrap().get(email).cloned())
+    }
+
+    async fn delete_otp(&self, email: &EmailAddress) -> AuthResult<bool> {
+        Ok(self.records.lock().unwrap().remove(email).is_some())
+    }
+
+    async fn consume_otp(&self, email: &EmailAddress, challenge_id: &str) -> AuthResult<bool> {
+        let mut records = self.records.lock().unwrap();
+        let consumable = records
+            .get(email)
+            .is_some_and(|record| record.challenge_id == challenge_id && record.locked_until.is_none());
+        if consumable {
+            records.remove(email);
+        }
+        Ok(consumable)
+    }
+
+    async fn increment_attempts(&self, email: &EmailAddress, challenge_id: &str) -> AuthResult<u8> {
+        let mut records = self.records.lock().unwrap();
+        let record = records
+            .get_mut(email)
+            .filter(|record| record.challenge_id == challenge_id)
+            .ok_or_else(|| AuthError::InvalidOTP("OTP record not found".to_string()))?;
+        record.attempts = record.attempts.saturating_add(1);
+        Ok(record.attempts)
+    }
+
+    async fn lock_out(&self, email: &EmailAddress, locked_until: i64) -> AuthResult<()> {
+        let mut records = self.records.lock().unwrap();
+        let record = records
+            .get_mut(email)
+            .ok_or_else(|| AuthError::InvalidOTP("OTP record not found".to_string()))?;
+        record.locked_until = Some(locked_until);
+        record.ttl = locked_until;
+        Ok(())
+    }
+}
+
+/// In-memory user repository for tests and local development, keyed by user_id
+#[derive(Debug, Default)]
+pub struct InMemoryUserRepository {
+    users: Mutex<HashMap<String, UserProfile>>,
+    status_history: Mutex<HashMap<String, Vec<StatusChange>>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert or replace a user profile directly (useful for seeding tests)
+    pub fn insert_user(&self, user: UserProfile) {
+        self.users.lock().unwrap().insert(user.user_id.clone(), user);
+    }
+
+    /// Guarded status change shared by transition_status and record_review; `update` applies
+    /// any other field changes that belong to the same write
+    fn change_status(
+        &self,
+        user_id: &str,
+        from: UserStatus,
+        to: UserStatus,
+        actor: &str,
+        update: impl FnOnce(&mut UserProfile),
+    ) -> AuthResult<UserProfile> {
+        validate_status_transition(from, to)?;
+
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .get_mut(user_id)
+            .filter(|user| user.status == from)
+            .ok_or_else(|| {
+                AuthError::InvalidStatusTransition(format!(
+                    "User {} is not in status {}",
+                    user_id,
+                    from.as_str()
+                ))
+            })?;
+
+        let now = Utc::now();
+        user.status = to;
+        user.updated_at = now;
+        update(user);
+        self.status_history
+            .lock()
+            .unwrap()
+            .entry(user_id.to_string())
+            .or_default()
+            .push(StatusChange {
+                from,
+                to,
+                actor: actor.to_string(),
+                changed_at: now,
+            });
+        Ok(user.clone())
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn get_user_by_email(&self, email: &EmailAddress) -> AuthResult<Option<UserProfile>> {
+        Ok(self
+            .users
+            .lock()
+            .unwrap()
+            .values()
+            .find(|user| &user.email == email)
+            .cloned())
+    }
+
+    async fn create_user(&self, email: &EmailAddress, cognito_user_id: &str) -> AuthResult<UserProfile> {
+        let mut users = self.users.lock().unwrap();
+        if let Some(existing) = users.get_mut(cognito_user_id) {
+            existing.email = email.clone();
+            return Ok(existing.clone());
+        }
+
+        let now = Utc::now();
+        let user = UserProfile {
+            user_id: cognito_user_id.to_string(),
+            email: email.clone(),
+            status: UserStatus::default(),
+            full_name: None,
+            content_description: None,
+            content_link: None,
+            stripe_account_id: None,
+            created_at: now,
+            updated_at: now,
+            reviewed_by: None,
+            reviewed_at: None,
+            rejection_reason: None,
+            welcome_pending: false,
+            review_email_pending: false,
+        };
+        users.insert(user.user_id.clone(), user.clone());
+        Ok(user)
+    }
+
+    async fn transition_status(
+        &self,
+        user_id: &str,
+        from: UserStatus,
+        to: UserStatus,
+        actor: &str,
+    ) -> AuthResult<()> {
+        self.change_status(user_id, from, to, actor, |_| {})?;
+        Ok(())
+    }
+
+    async fn get_status_history(&self, user_id: &str) -> AuthResult<Vec<StatusChange>> {
+        if !self.users.lock().unwrap().contains_key(user_id) {
+            return Err(AuthError::UserNotFound(user_id.to_string()));
+        }
+        Ok(self
+            .status_history
+            .lock()
+            .unwrap()
+            .get(user_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn get_user_by_id(&self, user_id: &str) -> AuthResult<Option<UserProfile>> {
+        Ok(self.users.lock().unwrap().get(user_id).cloned())
+    }
+
+    async fn mark_email_verified(&self, user_id: &str, actor: &str) -> AuthResult<UserProfile> {
+        self.change_status(
+            user_id,
+            UserStatus::RegistrationEmailNotVerified,
+            UserStatus::RegistrationNeedUserInfo,
+            actor,
+            |user| user.welcome_pending = true,
+        )
+    }
+
+    async fn clear_welcome_pending(&self, user_id: &str) -> AuthResult<bool> {
+        let mut users = self.users.lock().unwrap();
+        Ok(users
+            .get_mut(user_id)
+            .map(|user| std::mem::take(&mut user.welcome_pending))
+            .unwrap_or(false))
+    }
+
+    async fn clear_review_email_pending(&self, user_id: &str) -> AuthResult<bool> {
+        let mut users = self.users.lock().unwrap();
+        Ok(users
+            .get_mut(user_id)
+            .map(|user| std::mem::take(&mut user.review_email_pending))
+            .unwrap_or(false))
+    }
+
+    async fn complete_profile(
+        &self,
+        user_id: &str,
+        details: &ProfileDetails,
+        actor: &str,
+    ) -> AuthResult<UserProfile> {
+        self.change_status(
+            user_id,
+            UserStatus::RegistrationNeedUserInfo,
+            UserStatus::RegistrationNeedStripe,
+            actor,
+            |user| {
+                user.full_name = Some(details.full_name.clone());
+                user.content_description = Some(details.content_description.clone());
+                user.content_link = Some(details.content_link.clone());
+            },
+        )
+    }
+
+    async fn set_stripe_account_id(&self, user_id: &str, stripe_account_id: &str) -> AuthResult<()> {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .get_mut(user_id)
+            .filter(|user| {
+                user.status == UserStatus::RegistrationNeedStripe
+                    && user
+                        .stripe_account_id
+                        .as_deref()
+                        .is_none_or(|existing| existing == stripe_account_id)
+            })
+            .ok_or_else(|| {
+                AuthError::InvalidStatusTransition(format!(
+                    "User {} cannot be linked to Stripe account {}",
+                    user_id, stripe_account_id
+                ))
+            })?;
+
+        user.stripe_account_id = Some(stripe_account_id.to_string());
+        user.updated_at = Utc::now();
+        Ok(())
+    }
+
+    async fn record_review(
+        &self,
+        user_id: &str,
+        decision: &ReviewDecision,
+        reviewer_id: &str,
+    ) -> AuthResult<UserProfile> {
+        self.change_status(
+            user_id,
+            UserStatus::AwaitingReview,
+            decision.status(),
+            reviewer_id,
+            |user| {
+                user.reviewed_by = Some(reviewer_id.to_string());
+                user.reviewed_at = Some(user.updated_at);
+                user.rejection_reason = match decision {
+                    ReviewDecision::Approve => None,
+                    ReviewDecision::Reject { reason } => Some(reason.clone()),
+                };
+                user.review_email_pending = true;
+            },
+        )
+    }
+
+    async fn list_users_by_status(
+        &self,
+        status: UserStatus,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> AuthResult<UserPage> {
+        let after = cursor.map(parse_user_page_cursor).transpose()?;
+
+        // Same order as the status-index: created_at as a string, then user_id
+        let mut matching: Vec<UserProfile> = self
+            .users
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|user| user.status == status)
+            .cloned()
+            .collect();
+        matching.sort_by_key(|user| (user.created_at.to_rfc3339(), user.user_id.clone()));
+        let remaining: Vec<UserProfile> = matching
+            .into_iter()
+            .filter(|user| {
+                after.is_none_or(|(created_at, user_id)| {
+                    (user.created_at.to_rfc3339().as_str(), user.user_id.as_str())
+                        > (created_at, user_id)
+                })
+            })
+            .collect();
+
+        let users: Vec<UserProfile> = remaining.iter().take(limit).cloned().collect();
+        let next_cursor = if remaining.len() > users.len() {
+            users.last().map(user_page_cursor)
+        } else {
+            None
+        };
+        Ok(UserPage { users, next_cursor })
+    }
+
+    async fn delete_user(&self, user_id: &str) -> AuthResult<bool> {
+        self.status_history.lock().unwrap().remove(user_id);
+        Ok(self.users.lock().unwrap().remove(user_id).is_some())
+    }
+}
+
+/// In-memory email queue for tests and local development, which can be told to fail
+#[derive(Debug, Default)]
+pub struct InMemoryEmailQueue {
+    requests: Mutex<Vec<EmailRequest>>,
+    /// How many of the next queue attempts fail
+    failures: Mutex<usize>,
+}
+
+impl InMemoryEmailQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail the next `count` queue attempts with EmailDeliveryUnavailable
+    pub fn fail_next(&self, count: usize) {
+        *self.failures.lock().unwrap() = count;
+    }
+
+    /// Every email queued so far, in order
+    pub fn queued(&self) -> Vec<EmailRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl EmailQueue for InMemoryEmailQueue {
+    async fn queue_email(&self, request: EmailRequest) -> AuthResult<()> {
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(AuthError::EmailDeliveryUnavailable("Queue unavailable".to_string()));
+        }
+        self.requests.lock().unwrap().push(request);
+        Ok(())
+    }
+}
+
+/// In-memory email sender for tests and local development, which can be told to fail
+#[derive(Debug, Default)]
+pub struct InMemoryEmailSender {
+    sent: Mutex<Vec<EmailRequest>>,
+    /// Fail every send, transiently if true
+    failure: Mutex<Option<bool>>,
+}
+
+impl InMemoryEmailSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail every send from now on, with EmailDeliveryUnavailable if transient and
+    /// EmailDeliveryFailed otherwise
+    pub fn fail(&self, transient: bool) {
+        *self.failure.lock().unwrap() = Some(transient);
+    }
+
+    /// Every email sent so far, in order
+    pub fn sent(&self) -> Vec<EmailRequest> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl EmailSender for InMemoryEmailSender {
+    async fn send_email(&self, request: EmailRequest) -> AuthResult<()> {
+        match *self.failure.lock().unwrap() {
+            Some(true) => Err(AuthError::EmailDeliveryUnavailable("Throttling".to_string())),
+            Some(false) => Err(AuthError::EmailDeliveryFailed("MessageRejected".to_string())),
+            None => {
+                self.sent.lock().unwrap().push(request);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// In-memory rate limit counters for tests and local development
+#[derive(Debug, Default)]
+pub struct InMemoryRateLimitStore {
+    /// Request count per (counter key, window start)
+    counters: Mutex<HashMap<(String, i64), usize>>,
+    policies: RateLimitPolicies,
+    conflicts: Mutex<usize>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policies(policies: RateLimitPolicies) -> Self {
+        Self {
+            counters: Mutex::default(),
+            policies,
+            conflicts: Mutex::default(),
+        }
+    }
+
+    /// Cancel the next `count` consume attempts with TransactionConflict, as if a concurrent
+    /// request had charged the same counters
+    pub fn conflict_next(&self, count: usize) {
+        *self.conflicts.lock().unwrap() = count;
+    }
+
+    /// Consume rate limits as of an explicit timestamp (useful for testing window boundaries)
+    pub fn consume_rate_limits_at(
+        &self,
+        requests: &[(RateLimitAction, String)],
+        now: i64,
+    ) -> AuthResult<()> {
+        let planned = self.policies.counters(requests, now);
+        let mut counters = self.counters.lock().unwrap();
+
+        let full: Vec<&RateLimitCounter> = planned
+            .iter()
+            .filter(|counter| {
+                counters
+                    .get(&(counter.key.clone(), counter.window_start))
+                    .is_some_and(|count| *count >= counter.window.max_requests)
+            })
+            .collect();
+        if !full.is_empty() {
+            return Err(rate_limit_exceeded(&full, now));
+        }
+
+        for counter in &planned {
+            *counters
+                .entry((counter.key.clone(), counter.window_start))
+                .or_default() += 1;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn consume_rate_limits(&self, requests: &[(RateLimitAction, String)]) -> AuthResult<()> {
+        retry_transaction_conflicts(|| async move {
+            let mut conflicts = self.conflicts.lock().unwrap();
+            if *conflicts > 0 {
+                *conflicts -= 1;
+                return Err(AuthError::TransactionConflict("Rate limit transaction cancelled".to_string()));
+            }
+            drop(conflicts);
+            self.consume_rate_limits_at(requests, current_timestamp())
+        })
+        .await
+    }
+
+    async fn delete_rate_limits(&self, actions: &[RateLimitAction], key: &str) -> AuthResult<usize> {
+        let keys = self.policies.counter_keys(actions, key);
+        let mut counters = self.counters.lock().unwrap();
+        let before = counters.len();
+        counters.retain(|(counter_key, _), _| !keys.contains(counter_key));
+        Ok(before - counters.len())
+    }
+
+    async fn get_rate_limits(&self, actions: &[RateLimitAction], key: &str) -> AuthResult<Vec<RateLimitUsage>> {
+        let counters = self.counters.lock().unwrap();
+        let mut usage = Vec::new();
+        for (action, window, counter_key) in self.policies.counter_partitions(actions, key) {
+            let mut windows: Vec<RateLimitUsage> = counters
+                .iter()
+                .filter(|((existing, _), _)| *existing == counter_key)
+                .map(|((_, window_start), count)| RateLimitUsage {
+                    action: action.as_str().to_string(),
+                    window_seconds: window.window_seconds,
+                    window_start: *window_start,
+                    request_count: *count,
+                })
+                .collect();
+            windows.sort_by_key(|usage| usage.window_start);
+            usage.extend(windows);
+        }
+        Ok(usage)
+    }
+}
+
+/// In-memory session store for tests and local development
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert or replace a session directly (useful for seeding tests)
+    pub fn insert_session(&self, session: Session) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session.session_id.clone(), session);
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn create_session(&self, session: &Session) -> AuthResult<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.contains_key(&session.session_id) {
+            return Err(AuthError::ValidationError(format!(
+                "Session already exists: {}",
+                session.session_id
+            )));
+        }
+        sessions.insert(session.session_id.clone(), session.clone());
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        Ok(self.sessions.lock().unwrap().get(session_id).cloned())
+    }
+
+    async fn extend_session(&self, session_id: &str, last_accessed: i64, expires_at: i64) -> AuthResult<bool> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return Ok(false);
+        };
+        session.last_accessed = last_accessed;
+        session.expires_at = expires_at;
+        Ok(true)
+    }
+
+    async fn delete_session(&self, session_id: &str) -> AuthResult<bool> {
+        Ok(self.sessions.lock().unwrap().remove(session_id).is_some())
+    }
+
+    async fn get_sessions_for_user(&self, user_id: &str) -> AuthResult<Vec<Session>> {
+        Ok(self
+            .sessions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|session| session.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+}
+
+/// In-memory identity provider that records sign-outs and deletions, for tests and local
+/// development
+#[derive(Debug, Default)]
+pub struct InMemoryIdentityProvider {
+    signed_out: Mutex<Vec<String>>,
+    deleted: Mutex<Vec<String>>,
+    /// (username, email as signed up with) of the users findable by email
+    emails: Mutex<Vec<(String, String)>>,
+}
+
+impl InMemoryIdentityProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a user's email, so find_users_by_email can find them
+    pub fn add_user(&self, username: &str, email: &str) {
+        self.emails
+            .lock()
+            .unwrap()
+            .push((username.to_string(), email.to_string()));
+    }
+
+    /// Usernames globally signed out so far, in order
+    pub fn signed_out_users(&self) -> Vec<String> {
+        self.signed_out.lock().unwrap().clone()
+    }
+
+    /// Usernames deleted so far, in order
+    pub fn deleted_users(&self) -> Vec<String> {
+        self.deleted.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for InMemoryIdentityProvider {
+    async fn global_sign_out(&self, username: &str) -> AuthResult<()> {
+        self.signed_out.lock().unwrap().push(username.to_string());
+        Ok(())
+    }
+
+    /// Every username is treated as existing until it has been deleted once
+    async fn delete_user(&self, username: &str) -> AuthResult<bool> {
+        let mut deleted = self.deleted.lock().unwrap();
+        if deleted.iter().any(|existing| existing == username) {
+            return Ok(false);
+        }
+        deleted.push(username.to_string());
+        Ok(true)
+    }
+
+    async fn find_users_by_email(
+        &self,
+        email: &EmailAddress,
+        sub_addressing: SubAddressing,
+    ) -> AuthResult<Vec<String>> {
+        let deleted = self.deleted.lock().unwrap();
+        Ok(self
+            .emails
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(username, _)| !deleted.contains(username))
+            .filter(|(_, raw)| {
+                EmailAddress::parse_with(raw, sub_addressing).is_ok_and(|canonical| &canonical == email)
+            })
+            .map(|(username, _)| username.clone())
+            .collect())
+    }
+}
+
+/// In-memory sign-up lists for tests and local development
+#[derive(Debug, Default)]
+pub struct InMemorySignupListStore {
+    lists: Mutex<SignupLists>,
+    unavailable: Mutex<bool>,
+}
+
+impl InMemorySignupListStore {
+    pub fn new(lists: SignupLists) -> Self {
+        Self {
+            lists: Mutex::new(lists),
+            ..Self::default()
+        }
+    }
+
+    /// Make every read fail, as if the table couldn't be reached
+    pub fn set_unavailable(&self, unavailable: bool) {
+        *self.unavailable.lock().unwrap() = unavailable;
+    }
+}
+
+#[async_trait]
+impl SignupListStore for InMemorySignupListStore {
+    async fn get_signup_lists(&self) -> AuthResult<SignupLists> {
+        if *self.unavailable.lock().unwrap() {
+            return Err(AuthError::DynamoDBError("Sign-up lists unavailable".to_string()));
+        }
+        Ok(self.lists.lock().unwrap().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RateLimitPolicy, RATE_LIMIT_TRANSACTION_ATTEMPTS};
+
+    fn email(address: &str) -> EmailAddress {
+        EmailAddress::parse(address).unwrap()
+    }
+
+    fn otp_record(address: &str) -> OTPRecord {
+        let now = current_timestamp();
+        OTPRecord {
+            email: email(address),
+            otp_hash: "hash".to_string(),
+            created_at: now,
+            expires_at: now + 300,
+            ttl: now + 3900,
+            challenge_id: "challenge".to_string(),
+            attempts: 0,
+            locked_until: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_otp_store_round_trip() {
+        let store = InMemoryOtpStore::new();
+        store.store_otp(&otp_record("a@example.com")).await.unwrap();
+
+        let record = store.get_otp(&email("a@example.com")).await.unwrap().unwrap();
+        assert_eq!(record.challenge_id, "challenge");
+        assert!(store.get_otp(&email("b@example.com")).await.unwrap().is_none());
+
+        store.delete_otp(&email("a@example.com")).await.unwrap();
+        assert!(store.get_otp(&email("a@example.com")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_otp_store_attempts_and_lockout() {
+        let store = InMemoryOtpStore::new();
+        assert!(store.increment_attempts(&email("a@example.com"), "challenge").await.is_err());
+
+        store.store_otp(&otp_record("a@example.com")).await.unwrap();
+        assert_eq!(store.increment_attempts(&email("a@example.com"), "challenge").await.unwrap(), 1);
+        assert_eq!(store.increment_attempts(&email("a@example.com"), "challenge").await.unwrap(), 2);
+        assert!(store.increment_attempts(&email("a@example.com"), "other").await.is_err());
+
+        let now = current_timestamp();
+        store.lock_out(&email("a@example.com"), now + 900).await.unwrap();
+        let record = store.get_otp(&email("a@example.com")).await.unwrap().unwrap();
+        assert!(record.is_locked(now));
+        assert!(!record.is_locked(now + 900));
+        assert!(record.ttl >= now + 900);
+
+        // A locked OTP cannot be consumed
+        assert!(!store.consume_otp(&email("a@example.com"), "challenge").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_otp_store_consume_is_bound_to_challenge() {
+        let store = InMemoryOtpStore::new();
+        store.store_otp(&otp_record("a@example.com")).await.unwrap();
+
+        assert!(!store.consume_otp(&email("a@example.com"), "other").await.unwrap());
+        assert!(store.get_otp(&email("a@example.com")).await.unwrap().is_some());
+
+        assert!(store.consume_otp(&email("a@example.com"), "challenge").await.unwrap());
+        assert!(!store.consume_otp(&email("a@example.com"), "challenge").await.unwrap());
+        assert!(store.get_otp(&email("a@example.com")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_user_repository_create_and_update_status() {
+        let repo = InMemoryUserRepository::new();
+        let created = repo.create_user(&email("a@example.com"), "user-1").await.unwrap();
+        // Creating the same ID again returns the existing row rather than failing
+        let again = repo.create_user(&email("a@example.com"), "user-1").await.unwrap();
+        assert_eq!(again.created_at, created.created_at);
+
+        repo.transition_status(
+            "user-1",
+            UserStatus::RegistrationEmailNotVerified,
+            UserStatus::RegistrationNeedUserInfo,
+            "verify-auth-challenge",
+        )
+        .await
+        .unwrap();
+        let user = repo.get_user_by_email(&email("a@example.com")).await.unwrap().unwrap();
+        assert_eq!(user.user_id, "user-1");
+        assert_eq!(user.status, UserStatus::RegistrationNeedUserInfo);
+
+        let history = repo.get_status_history("user-1").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].from, UserStatus::RegistrationEmailNotVerified);
+        assert_eq!(history[0].to, UserStatus::RegistrationNeedUserInfo);
+        assert_eq!(history[0].actor, "verify-auth-challenge");
+
+        assert!(repo.get_status_history("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_user_repository_rejects_invalid_transitions() {
+        let repo = InMemoryUserRepository::new();
+        repo.create_user(&email("a@example.com"), "user-1").await.unwrap();
+
+        // Not an edge of the status graph
+        let skipped = repo
+            .transition_status(
+                "user-1",
+                UserStatus::RegistrationEmailNotVerified,
+                UserStatus::Active,
+                "admin-1",
+            )
+            .await;
+        assert!(matches!(skipped, Err(AuthError::InvalidStatusTransition(_))));
+
+        // A valid edge, but the user is no longer in the expected status
+        let stale = repo
+            .transition_status(
+                "user-1",
+                UserStatus::AwaitingReview,
+                UserStatus::Active,
+                "admin-1",
+            )
+            .await;
+        assert!(matches!(stale, Err(AuthError::InvalidStatusTransition(_))));
+
+        let user = repo.get_user_by_email(&email("a@example.com")).await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::RegistrationEmailNotVerified);
+        assert!(repo.get_status_history("user-1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_user_repository_sets_stripe_account_once() {
+        let repo = InMemoryUserRepository::new();
+        let mut user = repo.create_user(&email("a@example.com"), "user-1").await.unwrap();
+
+        // Only users setting up payments can be linked
+        let early = repo.set_stripe_account_id("user-1", "acct_1").await;
+        assert!(matches!(early, Err(AuthError::InvalidStatusTransition(_))));
+
+        user.status = UserStatus::RegistrationNeedStripe;
+        repo.insert_user(user);
+        repo.set_stripe_account_id("user-1", "acct_1").await.unwrap();
+        repo.set_stripe_account_id("user-1", "acct_1").await.unwrap();
+
+        let other = repo.set_stripe_account_id("user-1", "acct_2").await;
+        assert!(matches!(other, Err(AuthError::InvalidStatusTransition(_))));
+        let user = repo.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.stripe_account_id.as_deref(), Some("acct_1"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_store_window() {
+        let store = InMemoryRateLimitStore::new();
+        let send = |email: &str| vec![(RateLimitAction::OtpSend, email.to_string())];
+        // Start of a 15 minute window, which is also inside a single day
+        let now = 1_700_006_400;
+
+        for _ in 0..3 {
+            store.consume_rate_limits_at(&send("a@example.com"), now).unwrap();
+        }
+        assert!(matches!(
+            store.consume_rate_limits_at(&send("a@example.com"), now + 60),
+            Err(AuthError::RateLimitExceeded(message)) if message.contains("14 minutes")
+        ));
+        store.consume_rate_limits_at(&send("b@example.com"), now).unwrap();
+
+        // Actions are limited independently
+        store
+            .consume_rate_limits_at(&[(RateLimitAction::OtpVerify, "a@example.com".to_string())], now)
+            .unwrap();
+
+        // The next 15 minute window has room again
+        store.consume_rate_limits_at(&send("a@example.com"), now + 15 * 60).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_store_is_all_or_nothing() {
+        let store = InMemoryRateLimitStore::with_policies(
+            RateLimitPolicies::default()
+                .with_policy(RateLimitAction::OtpSendIp, RateLimitPolicy::parse("1/1h").unwrap()),
+        );
+        let now = 1_700_006_400;
+        let ip = (RateLimitAction::OtpSendIp, "203.0.113.7".to_string());
+        let email = (RateLimitAction::OtpSend, "a@example.com".to_string());
+        store.consume_rate_limits_at(std::slice::from_ref(&ip), now).unwrap();
+
+        // The IP is full, so the email's counters must not be charged either
+        for _ in 0..3 {
+            assert!(store.consume_rate_limits_at(&[email.clone(), ip.clone()], now).is_err());
+        }
+        for _ in 0..3 {
+            store.consume_rate_limits_at(std::slice::from_ref(&email), now).unwrap();
+        }
+        assert!(store.consume_rate_limits_at(&[email], now).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_store_retries_transaction_conflicts() {
+        let store = InMemoryRateLimitStore::new();
+        let send = vec![(RateLimitAction::OtpSend, "a@example.com".to_string())];
+
+        // A concurrent send cancels the first attempts; the retry charges each counter once
+        store.conflict_next(RATE_LIMIT_TRANSACTION_ATTEMPTS as usize - 1);
+        store.consume_rate_limits(&send).await.unwrap();
+        let usage = store.get_rate_limits(&[RateLimitAction::OtpSend], "a@example.com").await.unwrap();
+        assert!(!usage.is_empty());
+        assert!(usage.iter().all(|window| window.request_count == 1));
+
+        // A conflict that outlasts every attempt is reported, without charging anything
+        store.conflict_next(RATE_LIMIT_TRANSACTION_ATTEMPTS as usize);
+        assert!(matches!(
+            store.consume_rate_limits(&send).await,
+            Err(AuthError::TransactionConflict(_))
+        ));
+        let usage = store.get_rate_limits(&[RateLimitAction::OtpSend], "a@example.com").await.unwrap();
+        assert!(usage.iter().all(|window| window.request_count == 1));
+    }
+}
@@ -1,10 +1,29 @@
 pub mod dynamodb_service;
 pub mod ses_service;
 pub mod rate_limit_service;
+pub mod session_service;
+pub mod cognito_service;
+pub mod email_queue_service;
+pub mod review_service;
+pub mod profile_service;
+pub mod account_deletion_service;
+pub mod data_export_service;
+pub mod signup_policy_service;
+pub mod email_verification_service;
+pub mod otp_delivery_service;
 
 pub use dynamodb_service::*;
 pub use ses_service::*;
 pub use rate_limit_service::*;
+pub use session_service::*;
+pub use cognito_service::*;
+pub use review_service::*;
+pub use profile_service::*;
+pub use account_deletion_service::*;
+pub use data_export_service::*;
+pub use signup_policy_service::*;
+pub use email_verification_service::*;
+pub use otp_delivery_service::*;
 
 #[cfg(test)]
 mod tests {
@@ -39,6 +58,5 @@ mod tests {
         
         // Services should be created successfully (we can't test much more without actual AWS resources)
         // The fact that they compile and create without panicking is the main test
-        assert!(true, "Services initialized successfully with hardcoded names");
     }
 }
\ No newline at end of file
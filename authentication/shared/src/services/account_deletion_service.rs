@@ -0,0 +1,385 @@
+use aws_sdk_cognitoidentityprovider::Client as CognitoClient;
+use aws_sdk_dynamodb::Client as DynamoClient;
+use std::sync::Arc;
+
+use crate::{
+    sub_addressing_from_env, AuthError, AuthResult, CognitoService, DeletionReport, DeletionTarget,
+    DynamoDBService, IdentityProvider, OtpStore, RateLimitAction, RateLimitService, RateLimitStore,
+    SessionService, SubAddressing, UserRepository,
+};
+
+/// Erases everything the authentication domain keeps about an account
+pub struct AccountDeletionService {
+    users: Arc<dyn UserRepository>,
+    otps: Arc<dyn OtpStore>,
+    rate_limits: Arc<dyn RateLimitStore>,
+    sessions: SessionService,
+    identity: Arc<dyn IdentityProvider>,
+    sub_addressing: SubAddressing,
+}
+
+impl AccountDeletionService {
+    pub fn new(
+        users: Arc<dyn UserRepository>,
+        otps: Arc<dyn OtpStore>,
+        rate_limits: Arc<dyn RateLimitStore>,
+        sessions: SessionService,
+        identity: Arc<dyn IdentityProvider>,
+        sub_addressing: SubAddressing,
+    ) -> Self {
+        Self {
+            users,
+            otps,
+            rate_limits,
+            sessions,
+            identity,
+            sub_addressing,
+        }
+    }
+
+    /// Create AccountDeletionService using the CDK-provided tables, user pool and sub-addressing
+    /// policy
+    pub fn from_env(dynamo_client: DynamoClient, cognito_client: CognitoClient) -> Result<Self, AuthError> {
+        let dynamo = Arc::new(DynamoDBService::from_env(dynamo_client.clone())?);
+        let rate_limits = RateLimitService::from_env(dynamo_client.clone())?;
+        let sessions = SessionService::from_env(dynamo_client)?;
+        let identity = CognitoService::from_env(cognito_client)?;
+
+        Ok(Self::new(
+            dynamo.clone(),
+            dynamo,
+            Arc::new(rate_limits),
+            sessions,
+            Arc::new(identity),
+            sub_addressing_from_env()?,
+        ))
+    }
+
+    /// Delete the account's sessions, OTP, rate limit counters, Cognito user and users row.
+    ///
+    /// The users row links the user ID to the email, so it goes last: if any step fails the
+    /// deletion can simply be run again, and steps that already happened report nothing removed.
+    /// Given only a user ID whose users row is gone, the email-keyed data can't be found; delete
+    /// by email to clean that up.
+    pub async fn delete_account(&self, target: &DeletionTarget) -> AuthResult<DeletionReport> {
+        let mut report = DeletionReport::default();
+
+        match target {
+            DeletionTarget::Email(email) => {
+                let user = self.users.get_user_by_email(email).await?;
+                report.user_id = user.map(|user| user.user_id);
+                report.email = Some(email.clone());
+            }
+            DeletionTarget::UserId(user_id) => {
+                let user_id = user_id.trim();
+                if user_id.is_empty() {
+                    return Err(AuthError::ValidationError("User ID is required".to_string()));
+                }
+                let user = self.users.get_user_by_id(user_id).await?;
+                report.user_id = Some(user_id.to_string());
+                report.email = user.map(|user| user.email);
+            }
+        }
+
+        tracing::info!(
+            "Deleting account: user_id={:?}, email={:?}",
+            report.user_id, report.email
+        );
+
+        if let Some(user_id) = &report.user_id {
+            report.sessions_deleted = self.sessions.revoke_user_sessions(user_id).await?;
+        }
+
+        if let Some(email) = &report.email {
+            report.otp_deleted = self.otps.delete_otp(email).await?;
+            report.rate_limits_deleted = self
+                .rate_limits
+                .delete_rate_limits(&RateLimitAction::EMAIL_KEYED, email.as_str())
+                .await?;
+        }
+
+        // The Cognito username is the sub, which is the user_id of the row it created. Other
+        // Cognito users whose email canonicalises to the row's share it, so everyone with the
+        // email goes too; that also finds the Cognito users once the users row is gone.
+        let mut usernames: Vec<String> = report.user_id.iter().cloned().collect();
+        if let Some(email) = &report.email {
+            for username in self.identity.find_users_by_email(email, self.sub_addressing).await? {
+                if !usernames.contains(&username) {
+                    usernames.push(username);
+                }
+            }
+        }
+        for username in &usernames {
+            if self.identity.delete_user(username).await? {
+                report.identities_deleted += 1;
+            }
+        }
+
+        if let Some(user_id) = &report.user_id {
+            report.user_deleted = self.users.delete_user(user_id).await?;
+        }
+
+        tracing::info!("Account deletion finished: {:?}", report);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        EmailAddress, InMemoryIdentityProvider, InMemoryOtpStore, InMemoryRateLimitStore,
+        InMemorySessionStore, InMemoryUserRepository, OTPRecord, SessionPolicy, SessionStore,
+        current_timestamp,
+    };
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn email(address: &str) -> EmailAddress {
+        EmailAddress::parse(address).unwrap()
+    }
+
+    /// Identity provider whose first deletion fails, to interrupt an erasure part-way
+    #[derive(Default)]
+    struct FlakyIdentityProvider {
+        inner: InMemoryIdentityProvider,
+        failed: AtomicBool,
+    }
+
+    #[async_trait]
+    impl IdentityProvider for FlakyIdentityProvider {
+        async fn global_sign_out(&self, username: &str) -> AuthResult<()> {
+            self.inner.global_sign_out(username).await
+        }
+
+        async fn delete_user(&self, username: &str) -> AuthResult<bool> {
+            if !self.failed.swap(true, Ordering::SeqCst) {
+                return Err(AuthError::InternalError("Cognito unavailable".to_string()));
+            }
+            self.inner.delete_user(username).await
+        }
+
+        async fn find_users_by_email(
+            &self,
+            email: &EmailAddress,
+            sub_addressing: SubAddressing,
+        ) -> AuthResult<Vec<String>> {
+            self.inner.find_users_by_email(email, sub_addressing).await
+        }
+    }
+
+    struct Fixture {
+        users: Arc<InMemoryUserRepository>,
+        otps: Arc<InMemoryOtpStore>,
+        rate_limits: Arc<InMemoryRateLimitStore>,
+        sessions: Arc<InMemorySessionStore>,
+    }
+
+    impl Fixture {
+        fn service(&self, identity: Arc<dyn IdentityProvider>) -> AccountDeletionService {
+            self.service_with(identity, SubAddressing::default())
+        }
+
+        fn service_with(
+            &self,
+            identity: Arc<dyn IdentityProvider>,
+            sub_addressing: SubAddressing,
+        ) -> AccountDeletionService {
+            AccountDeletionService::new(
+                self.users.clone(),
+                self.otps.clone(),
+                self.rate_limits.clone(),
+                SessionService::new(self.sessions.clone(), SessionPolicy::default()),
+                identity,
+                sub_addressing,
+            )
+        }
+    }
+
+    /// A signed-up user with a pending OTP, a rate limit counter per email-keyed action and
+    /// two sessions
+    async fn seeded() -> Fixture {
+        let fixture = Fixture {
+            users: Arc::new(InMemoryUserRepository::new()),
+            otps: Arc::new(InMemoryOtpStore::new()),
+            rate_limits: Arc::new(InMemoryRateLimitStore::new()),
+            sessions: Arc::new(InMemorySessionStore::new()),
+        };
+        let email = email("creator@example.com");
+        let user = fixture.users.create_user(&email, "user-1").await.unwrap();
+
+        let now = current_timestamp();
+        fixture
+            .otps
+            .store_otp(&OTPRecord {
+                email: email.clone(),
+                otp_hash: "hash".to_string(),
+                created_at: now,
+                expires_at: now + 300,
+                ttl: now + 300,
+                challenge_id: "challenge".to_string(),
+                attempts: 0,
+                locked_until: None,
+            })
+            .await
+            .unwrap();
+
+        let requests: Vec<(RateLimitAction, String)> = RateLimitAction::EMAIL_KEYED
+            .into_iter()
+            .map(|action| (action, email.to_string()))
+            .collect();
+        fixture.rate_limits.consume_rate_limits(&requests).await.unwrap();
+        // Another user's counters must survive
+        fixture
+            .rate_limits
+            .consume_rate_limits(&[(RateLimitAction::OtpSend, "other@example.com".to_string())])
+            .await
+            .unwrap();
+
+        let sessions = SessionService::new(fixture.sessions.clone(), SessionPolicy::default());
+        for _ in 0..2 {
+            sessions.create_session(&user, None, None).await.unwrap();
+        }
+        fixture
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_by_email_removes_everything() {
+        let fixture = seeded().await;
+        let identity = Arc::new(InMemoryIdentityProvider::new());
+        identity.add_user("user-1", "creator@example.com");
+        let service = fixture.service(identity.clone());
+
+        let report = service
+            .delete_account(&DeletionTarget::Email(email("creator@example.com")))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report,
+            DeletionReport {
+                user_id: Some("user-1".to_string()),
+                email: Some(email("creator@example.com")),
+                user_deleted: true,
+                otp_deleted: true,
+                // OtpSend has two windows by default, OtpVerify and OtpResend one each
+                rate_limits_deleted: 4,
+                sessions_deleted: 2,
+                identities_deleted: 1,
+            }
+        );
+        assert!(fixture.users.get_user_by_id("user-1").await.unwrap().is_none());
+        assert!(fixture.otps.get_otp(&email("creator@example.com")).await.unwrap().is_none());
+        assert!(fixture.sessions.get_sessions_for_user("user-1").await.unwrap().is_empty());
+        assert_eq!(identity.deleted_users(), vec!["user-1".to_string()]);
+
+        // Only the other user's counters are left
+        let other = fixture
+            .rate_limits
+            .delete_rate_limits(&RateLimitAction::EMAIL_KEYED, "other@example.com")
+            .await
+            .unwrap();
+        assert_eq!(other, 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_by_user_id() {
+        let fixture = seeded().await;
+        let service = fixture.service(Arc::new(InMemoryIdentityProvider::new()));
+
+        let report = service
+            .delete_account(&DeletionTarget::UserId("user-1".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(report.email, Some(email("creator@example.com")));
+        assert!(report.user_deleted && report.otp_deleted);
+        assert_eq!((report.sessions_deleted, report.identities_deleted), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_can_be_rerun_after_partial_failure() {
+        let fixture = seeded().await;
+        let service = fixture.service(Arc::new(FlakyIdentityProvider::default()));
+        let target = DeletionTarget::UserId("user-1".to_string());
+
+        let failed = service.delete_account(&target).await;
+        assert!(matches!(failed, Err(AuthError::InternalError(_))));
+        // The users row is kept so the rerun can still find the email
+        assert!(fixture.users.get_user_by_id("user-1").await.unwrap().is_some());
+
+        let report = service.delete_account(&target).await.unwrap();
+        assert!(report.user_deleted && !report.otp_deleted);
+        assert_eq!(report.identities_deleted, 1);
+        assert_eq!((report.sessions_deleted, report.rate_limits_deleted), (0, 0));
+
+        // Once complete, running it again finds nothing left to remove
+        let report = service.delete_account(&target).await.unwrap();
+        assert!(!report.user_deleted && !report.otp_deleted);
+        assert_eq!(report.identities_deleted, 0);
+        assert_eq!((report.sessions_deleted, report.rate_limits_deleted), (0, 0));
+
+        // Whatever the case of the email
+        let report = service
+            .delete_account(&DeletionTarget::Email(email("Creator@Example.com")))
+            .await
+            .unwrap();
+        assert_eq!(report.user_id, None);
+        assert!(!report.user_deleted && !report.otp_deleted);
+        assert_eq!((report.sessions_deleted, report.rate_limits_deleted), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_identities_found_by_email_without_user_id() {
+        let fixture = seeded().await;
+        let identity = Arc::new(InMemoryIdentityProvider::new());
+        identity.add_user("cognito-sub-9", "orphan@example.com");
+        let service = fixture.service(identity.clone());
+
+        // No users row for this email, so the Cognito users are found by their email
+        let report = service
+            .delete_account(&DeletionTarget::Email(email("orphan@example.com")))
+            .await
+            .unwrap();
+        assert_eq!(report.user_id, None);
+        assert_eq!(report.identities_deleted, 1);
+        assert_eq!(identity.deleted_users(), vec!["cognito-sub-9".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_every_cognito_user_sharing_the_row_is_deleted() {
+        let fixture = seeded().await;
+        let identity = Arc::new(InMemoryIdentityProvider::new());
+        // user-1 created the row; cognito-sub-2 signed up with a sub-address that strips to it
+        identity.add_user("user-1", "creator@example.com");
+        identity.add_user("cognito-sub-2", "Creator+tips@example.com");
+        identity.add_user("cognito-sub-3", "someone@example.com");
+        let service = fixture.service_with(identity.clone(), SubAddressing::Strip);
+
+        let report = service
+            .delete_account(&DeletionTarget::UserId("user-1".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(report.identities_deleted, 2);
+        assert_eq!(
+            identity.deleted_users(),
+            vec!["user-1".to_string(), "cognito-sub-2".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_requires_a_target() {
+        let fixture = seeded().await;
+        let service = fixture.service(Arc::new(InMemoryIdentityProvider::new()));
+
+        for user_id in ["", " "] {
+            let result = service.delete_account(&DeletionTarget::UserId(user_id.to_string())).await;
+            assert!(matches!(result, Err(AuthError::ValidationError(_))));
+        }
+
+        // Emails are validated when the target is parsed
+        let blank = serde_json::from_value::<DeletionTarget>(serde_json::json!({ "email": " " }));
+        assert!(blank.is_err());
+    }
+}
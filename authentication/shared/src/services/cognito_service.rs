@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use aws_sdk_cognitoidentityprovider::Client as CognitoClient;
+
+use crate::{AuthError, AuthResult, EmailAddress, IdentityProvider, SubAddressing};
+
+/// Cognito user pool admin operations
+pub struct CognitoService {
+    client: CognitoClient,
+    user_pool_id: String,
+}
+
+impl CognitoService {
+    pub fn new(client: CognitoClient, user_pool_id: String) -> Self {
+        Self {
+            client,
+            user_pool_id,
+        }
+    }
+
+    /// Create CognitoService using CDK-provided user pool ID from environment variable
+    pub fn from_env(client: CognitoClient) -> Result<Self, AuthError> {
+        let user_pool_id = std::env::var("USER_POOL_ID")
+            .map_err(|e| {
+                tracing::error!("USER_POOL_ID environment variable not set: {:?}", e);
+                AuthError::InternalError("USER_POOL_ID not set".to_string())
+            })?;
+
+        tracing::info!("CognitoService initialized with user pool: {}", user_pool_id);
+        Ok(Self::new(client, user_pool_id))
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for CognitoService {
+    /// Revoke all of the user's Cognito refresh tokens via AdminUserGlobalSignOut
+    async fn global_sign_out(&self, username: &str) -> AuthResult<()> {
+        self.client
+            .admin_user_global_sign_out()
+            .user_pool_id(&self.user_pool_id)
+            .username(username)
+            .send()
+            .await
+            .map_err(|e| {
+                AuthError::InternalError(format!("Cognito global sign-out failed: {:?}", e))
+            })?;
+
+        tracing::info!("Globally signed out user: {}", username);
+        Ok(())
+    }
+
+    /// Delete the user via AdminDeleteUser, treating an already deleted user as done
+    async fn delete_user(&self, username: &str) -> AuthResult<bool> {
+        let result = self
+            .client
+            .admin_delete_user()
+            .user_pool_id(&self.user_pool_id)
+            .username(username)
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => {
+                tracing::info!("Deleted Cognito user: {}", username);
+                Ok(true)
+            }
+            Err(e) if e
+                .as_service_error()
+                .is_some_and(|se| se.is_user_not_found_exception()) =>
+            {
+                tracing::info!("Cognito user already deleted: {}", username);
+                Ok(false)
+            }
+            Err(e) => Err(AuthError::InternalError(format!(
+                "Cognito user deletion failed: {:?}",
+                e
+            ))),
+        }
+    }
+
+    /// Every spelling that canonicalises to the email starts with its local part (a sub-address
+    /// only adds a +tag, and the domain may be in Unicode or punycode), so list users by that
+    /// prefix via ListUsers and keep those whose canonical email matches
+    async fn find_users_by_email(
+        &self,
+        email: &EmailAddress,
+        sub_addressing: SubAddressing,
+    ) -> AuthResult<Vec<String>> {
+        let filter = format!("email ^= \"{}\"", email.local_part().replace('"', "\\\""));
+        let mut usernames = Vec::new();
+        let mut pagination_token = None;
+
+        loop {
+            let page = self
+                .client
+                .list_users()
+                .user_pool_id(&self.user_pool_id)
+                .filter(&filter)
+                .attributes_to_get("email")
+                .set_pagination_token(pagination_token)
+                .send()
+                .await
+                .map_err(|e| AuthError::InternalError(format!("Cognito user listing failed: {:?}", e)))?;
+
+            for user in page.users() {
+                let matches = user
+                    .attributes()
+                    .iter()
+                    .find(|attribute| attribute.name() == "email")
+                    .and_then(|attribute| attribute.value())
+                    .and_then(|raw| EmailAddress::parse_with(raw, sub_addressing).ok())
+                    .is_some_and(|canonical| &canonical == email);
+                if let (true, Some(username)) = (matches, user.username()) {
+                    usernames.push(username.to_string());
+                }
+            }
+
+            pagination_token = page.pagination_token().map(str::to_string);
+            if pagination_token.is_none() {
+                break;
+            }
+        }
+
+        tracing::info!("Found {} Cognito users for {}", usernames.len(), email);
+        Ok(usernames)
+    }
+}
@@ -0,0 +1,192 @@
+use aws_sdk_dynamodb::Client as DynamoClient;
+use chrono::Utc;
+use std::sync::Arc;
+
+use crate::{
+    AuthError, AuthResult, DynamoDBService, DynamoDBSessionStore, EmailAddress, NotificationsExport,
+    OtpExport, OtpStore, RateLimitAction, RateLimitService, RateLimitStore, SessionExport, SessionStore,
+    UserDataExport, UserRepository, USER_DATA_EXPORT_VERSION,
+};
+
+/// Gathers everything held about a user for subject access requests
+pub struct DataExportService {
+    users: Arc<dyn UserRepository>,
+    otps: Arc<dyn OtpStore>,
+    rate_limits: Arc<dyn RateLimitStore>,
+    sessions: Arc<dyn SessionStore>,
+}
+
+impl DataExportService {
+    pub fn new(
+        users: Arc<dyn UserRepository>,
+        otps: Arc<dyn OtpStore>,
+        rate_limits: Arc<dyn RateLimitStore>,
+        sessions: Arc<dyn SessionStore>,
+    ) -> Self {
+        Self {
+            users,
+            otps,
+            rate_limits,
+            sessions,
+        }
+    }
+
+    /// Create DataExportService using the CDK-provided tables
+    pub fn from_env(client: DynamoClient) -> Result<Self, AuthError> {
+        let dynamo = Arc::new(DynamoDBService::from_env(client.clone())?);
+        let rate_limits = RateLimitService::from_env(client.clone())?;
+        let sessions = DynamoDBSessionStore::from_env(client)?;
+
+        Ok(Self::new(
+            dynamo.clone(),
+            dynamo,
+            Arc::new(rate_limits),
+            Arc::new(sessions),
+        ))
+    }
+
+    /// Export everything held for the email. The user is found exactly as
+    /// `UserRepository::get_user_by_email` finds them; data keyed by the email itself (the
+    /// pending OTP and rate limit counters) is exported even if no user exists.
+    pub async fn export_user_data(&self, email: &EmailAddress) -> AuthResult<UserDataExport> {
+        let user = self.users.get_user_by_email(email).await?;
+        let (status_history, sessions) = match &user {
+            Some(user) => (
+                self.users.get_status_history(&user.user_id).await?,
+                self.sessions.get_sessions_for_user(&user.user_id).await?,
+            ),
+            None => (Vec::new(), Vec::new()),
+        };
+        let pending_otp = self.otps.get_otp(email).await?;
+        let rate_limits = self
+            .rate_limits
+            .get_rate_limits(&RateLimitAction::EMAIL_KEYED, email.as_str())
+            .await?;
+
+        tracing::info!(
+            "Exported data for {}: user={}, sessions={}, rate limit windows={}",
+            email,
+            user.is_some(),
+            sessions.len(),
+            rate_limits.len()
+        );
+
+        let mut sessions: Vec<SessionExport> = sessions.iter().map(SessionExport::from).collect();
+        sessions.sort_by_key(|session| session.created_at);
+
+        Ok(UserDataExport {
+            export_version: USER_DATA_EXPORT_VERSION,
+            exported_at: Utc::now(),
+            email: email.clone(),
+            user,
+            status_history,
+            sessions,
+            pending_otp: pending_otp.as_ref().map(OtpExport::from),
+            rate_limits,
+            notifications: NotificationsExport::none_stored(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        InMemoryOtpStore, InMemoryRateLimitStore, InMemorySessionStore, InMemoryUserRepository,
+        SessionPolicy, SessionService, UserStatus,
+    };
+
+    fn email(address: &str) -> EmailAddress {
+        EmailAddress::parse(address).unwrap()
+    }
+
+    struct Fixture {
+        users: Arc<InMemoryUserRepository>,
+        rate_limits: Arc<InMemoryRateLimitStore>,
+        sessions: Arc<InMemorySessionStore>,
+        service: DataExportService,
+    }
+
+    fn fixture() -> Fixture {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let rate_limits = Arc::new(InMemoryRateLimitStore::new());
+        let sessions = Arc::new(InMemorySessionStore::new());
+        let service = DataExportService::new(
+            users.clone(),
+            Arc::new(InMemoryOtpStore::new()),
+            rate_limits.clone(),
+            sessions.clone(),
+        );
+        Fixture {
+            users,
+            rate_limits,
+            sessions,
+            service,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_gathers_everything_for_the_user() {
+        let fixture = fixture();
+        fixture.users.create_user(&email("creator@example.com"), "user-1").await.unwrap();
+        fixture
+            .users
+            .transition_status(
+                "user-1",
+                UserStatus::RegistrationEmailNotVerified,
+                UserStatus::RegistrationNeedUserInfo,
+                "verify-auth-challenge",
+            )
+            .await
+            .unwrap();
+        let user = fixture.users.get_user_by_id("user-1").await.unwrap().unwrap();
+        let session = SessionService::new(fixture.sessions.clone(), SessionPolicy::default())
+            .create_session(&user, Some("203.0.113.7".to_string()), None)
+            .await
+            .unwrap();
+        fixture
+            .rate_limits
+            .consume_rate_limits(&[(RateLimitAction::OtpSend, "creator@example.com".to_string())])
+            .await
+            .unwrap();
+
+        // The lookup is by canonical address, so the case it's asked for in doesn't matter
+        let export = fixture
+            .service
+            .export_user_data(&email("Creator@EXAMPLE.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(export.export_version, USER_DATA_EXPORT_VERSION);
+        assert_eq!(export.user.unwrap().user_id, "user-1");
+        assert_eq!(export.status_history.len(), 1);
+        assert_eq!(export.sessions.len(), 1);
+        assert_eq!(export.sessions[0].ip_address.as_deref(), Some("203.0.113.7"));
+        // Both OtpSend windows
+        assert_eq!(export.rate_limits.len(), 2);
+        assert!(export.rate_limits.iter().all(|usage| usage.request_count == 1));
+        assert!(export.pending_otp.is_none());
+        assert!(!export.notifications.delivery_records_stored);
+        assert!(export.notifications.delivery_records.is_empty());
+
+        // Session IDs are credentials and never leave in an export
+        let json = serde_json::to_string(&export.sessions).unwrap();
+        assert!(!json.contains(&session.session_id));
+    }
+
+    #[tokio::test]
+    async fn test_export_for_unknown_email_only_has_email_keyed_data() {
+        let fixture = fixture();
+        fixture
+            .rate_limits
+            .consume_rate_limits(&[(RateLimitAction::OtpVerify, "nobody@example.com".to_string())])
+            .await
+            .unwrap();
+
+        let export = fixture.service.export_user_data(&email("nobody@example.com")).await.unwrap();
+        assert!(export.user.is_none());
+        assert!(export.sessions.is_empty() && export.status_history.is_empty());
+        assert_eq!(export.rate_limits.len(), 1);
+        assert_eq!(export.rate_limits[0].action, "otp_verify");
+    }
+}
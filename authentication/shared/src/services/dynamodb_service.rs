@@ -1,8 +1,17 @@
-use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoClient};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, ReturnValue},
+    Client as DynamoClient,
+};
 use chrono::Utc;
 use std::collections::HashMap;
 
-use crate::{AuthError, AuthResult, OTPRecord, UserProfile, UserStatus};
+use crate::{
+    from_attribute, parse_user_page_cursor, to_attribute, user_page_cursor, AuthError, AuthResult,
+    DynamoItem, OTPRecord, OtpStore, ProfileDetails, ReviewDecision, StatusChange, UserPage, UserProfile, UserRepository, UserStatus,
+    validate_status_transition,
+};
+use notifications_shared::EmailAddress;
 
 pub struct DynamoDBService {
     client: DynamoClient,
@@ -37,32 +46,90 @@ impl DynamoDBService {
         Ok(Self::new(client, otp_table, users_table))
     }
 
-    /// Store OTP record in DynamoDB
-    pub async fn store_otp(&self, record: &OTPRecord) -> AuthResult<()> {
-        let mut item = HashMap::new();
-        item.insert("email".to_string(), AttributeValue::S(record.email.clone()));
-        item.insert(
-            "otp_hash".to_string(),
-            AttributeValue::S(record.otp_hash.clone()),
-        );
-        item.insert(
-            "created_at".to_string(),
-            AttributeValue::N(record.created_at.to_string()),
-        );
-        item.insert(
-            "expires_at".to_string(),
-            AttributeValue::N(record.expires_at.to_string()),
-        );
-        item.insert("ttl".to_string(), AttributeValue::N(record.ttl.to_string()));
-        item.insert(
-            "challenge_id".to_string(),
-            AttributeValue::S(record.challenge_id.clone()),
+    /// Guarded status change shared by transition_status and record_review: `sets` and
+    /// `removes` are extra attributes written in the same conditional update
+    async fn change_status(
+        &self,
+        user_id: &str,
+        from: UserStatus,
+        to: UserStatus,
+        actor: &str,
+        sets: Vec<(&str, AttributeValue)>,
+        removes: &[&str],
+    ) -> AuthResult<UserProfile> {
+        validate_status_transition(from, to)?;
+
+        let change = StatusChange {
+            from,
+            to,
+            actor: actor.to_string(),
+            changed_at: Utc::now(),
+        };
+
+        let mut update_expression = String::from(
+            "SET #status = :to, updated_at = :updated_at, \
+             status_history = list_append(if_not_exists(status_history, :empty), :change)",
         );
-        item.insert(
-            "attempts".to_string(),
-            AttributeValue::N(record.attempts.to_string()),
+        let mut request = self
+            .client
+            .update_item()
+            .table_name(&self.users_table)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .condition_expression("#status = :from")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":from", AttributeValue::S(from.as_str().to_string()))
+            .expression_attribute_values(":to", AttributeValue::S(to.as_str().to_string()))
+            .expression_attribute_values(":updated_at", AttributeValue::S(change.changed_at.to_rfc3339()))
+            .expression_attribute_values(":empty", AttributeValue::L(Vec::new()))
+            .expression_attribute_values(
+                ":change",
+                AttributeValue::L(vec![to_attribute(&change)?]),
+            )
+            .return_values(ReturnValue::AllNew);
+        for (name, value) in sets {
+            update_expression.push_str(&format!(", {} = :{}", name, name));
+            request = request.expression_attribute_values(format!(":{}", name), value);
+        }
+        if !removes.is_empty() {
+            update_expression.push_str(&format!(" REMOVE {}", removes.join(", ")));
+        }
+
+        let result = request
+            .update_expression(update_expression)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception())
+                {
+                    AuthError::InvalidStatusTransition(format!(
+                        "User {} is not in status {}",
+                        user_id,
+                        from.as_str()
+                    ))
+                } else {
+                    AuthError::DynamoDBError(e.to_string())
+                }
+            })?;
+
+        tracing::info!(
+            "User {} moved from {} to {} by {}",
+            user_id, from.as_str(), to.as_str(), actor
         );
 
+        let item = result
+            .attributes
+            .ok_or_else(|| AuthError::InternalError("Missing updated user".to_string()))?;
+        UserProfile::from_item(item)
+    }
+}
+
+#[async_trait]
+impl OtpStore for DynamoDBService {
+    /// Store OTP record in DynamoDB
+    async fn store_otp(&self, record: &OTPRecord) -> AuthResult<()> {
+        let item = record.to_item()?;
+
         self.client
             .put_item()
             .table_name(&self.otp_table)
@@ -75,7 +142,7 @@ impl DynamoDBService {
     }
 
     /// Retrieve OTP record by email
-    pub async fn get_otp(&self, email: &str) -> AuthResult<Option<OTPRecord>> {
+    async fn get_otp(&self, email: &EmailAddress) -> AuthResult<Option<OTPRecord>> {
         let result = self
             .client
             .get_item()
@@ -85,65 +152,163 @@ impl DynamoDBService {
             .await
             .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
 
-        if let Some(item) = result.item {
-            let record = OTPRecord {
-                email: item
-                    .get("email")
-                    .and_then(|v| v.as_s().ok())
-                    .ok_or_else(|| AuthError::InternalError("Missing email".to_string()))?
-                    .clone(),
-                otp_hash: item
-                    .get("otp_hash")
-                    .and_then(|v| v.as_s().ok())
-                    .ok_or_else(|| AuthError::InternalError("Missing otp_hash".to_string()))?
-                    .clone(),
-                created_at: item
-                    .get("created_at")
-                    .and_then(|v| v.as_n().ok())
-                    .and_then(|s| s.parse().ok())
-                    .ok_or_else(|| AuthError::InternalError("Missing created_at".to_string()))?,
-                expires_at: item
-                    .get("expires_at")
-                    .and_then(|v| v.as_n().ok())
-                    .and_then(|s| s.parse().ok())
-                    .ok_or_else(|| AuthError::InternalError("Missing expires_at".to_string()))?,
-                ttl: item
-                    .get("ttl")
-                    .and_then(|v| v.as_n().ok())
-                    .and_then(|s| s.parse().ok())
-                    .ok_or_else(|| AuthError::InternalError("Missing ttl".to_string()))?,
-                challenge_id: item
-                    .get("challenge_id")
-                    .and_then(|v| v.as_s().ok())
-                    .ok_or_else(|| AuthError::InternalError("Missing challenge_id".to_string()))?
-                    .clone(),
-                attempts: item
-                    .get("attempts")
-                    .and_then(|v| v.as_n().ok())
-                    .and_then(|s| s.parse().ok())
-                    .ok_or_else(|| AuthError::InternalError("Missing attempts".to_string()))?,
-            };
-            Ok(Some(record))
-        } else {
-            Ok(None)
-        }
+        result.item.map(OTPRecord::from_item).transpose()
     }
 
     /// Delete OTP record after successful verification
-    pub async fn delete_otp(&self, email: &str) -> AuthResult<()> {
-        self.client
+    async fn delete_otp(&self, email: &EmailAddress) -> AuthResult<bool> {
+        let result = self
+            .client
+            .delete_item()
+            .table_name(&self.otp_table)
+            .key("email", AttributeValue::S(email.to_string()))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+
+        Ok(result.attributes.is_some())
+    }
+
+    /// Conditionally delete the OTP record so only one verification can succeed
+    async fn consume_otp(&self, email: &EmailAddress, challenge_id: &str) -> AuthResult<bool> {
+        let result = self
+            .client
             .delete_item()
             .table_name(&self.otp_table)
             .key("email", AttributeValue::S(email.to_string()))
+            .condition_expression("challenge_id = :challenge_id AND attribute_not_exists(locked_until)")
+            .expression_attribute_values(":challenge_id", AttributeValue::S(challenge_id.to_string()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception()) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(AuthError::DynamoDBError(e.to_string())),
+        }
+    }
+
+    /// Atomically increment the failed attempt counter on the OTP record
+    async fn increment_attempts(&self, email: &EmailAddress, challenge_id: &str) -> AuthResult<u8> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.otp_table)
+            .key("email", AttributeValue::S(email.to_string()))
+            .update_expression("ADD attempts :one")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":challenge_id", AttributeValue::S(challenge_id.to_string()))
+            .condition_expression("challenge_id = :challenge_id")
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception())
+                {
+                    AuthError::InvalidOTP("OTP record not found".to_string())
+                } else {
+                    AuthError::DynamoDBError(e.to_string())
+                }
+            })?;
+
+        result
+            .attributes
+            .as_ref()
+            .and_then(|attributes| attributes.get("attempts"))
+            .and_then(|v| v.as_n().ok())
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| AuthError::InternalError("Missing attempts".to_string()))
+    }
+
+    /// Lock the email out, keeping the record until the lockout ends
+    async fn lock_out(&self, email: &EmailAddress, locked_until: i64) -> AuthResult<()> {
+        self.client
+            .update_item()
+            .table_name(&self.otp_table)
+            .key("email", AttributeValue::S(email.to_string()))
+            .update_expression("SET locked_until = :locked_until, #ttl = :locked_until")
+            .expression_attribute_names("#ttl", "ttl")
+            .expression_attribute_values(":locked_until", AttributeValue::N(locked_until.to_string()))
+            .condition_expression("attribute_exists(email)")
             .send()
             .await
             .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
 
         Ok(())
     }
+}
+
+impl DynamoDBService {
+    /// Remove a pending-email flag only if it is still set, returning false if it was already
+    /// clear
+    async fn clear_pending_flag(&self, user_id: &str, flag: &str) -> AuthResult<bool> {
+        let result = self.client
+            .update_item()
+            .table_name(&self.users_table)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .update_expression(format!("REMOVE {}", flag))
+            .condition_expression(format!("{} = :pending", flag))
+            .expression_attribute_values(":pending", AttributeValue::Bool(true))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|se| se.is_conditional_check_failed_exception()) => {
+                Ok(false)
+            }
+            Err(e) => Err(AuthError::DynamoDBError(format!("Failed to clear {}: {}", flag, e))),
+        }
+    }
+
+    /// The sub already has a users row the email lookup missed: one stored with the raw address
+    /// Cognito supplied before emails were canonical, or under an email the user has since
+    /// changed. Keep the row and re-key its email so the email-index finds it from now on.
+    async fn adopt_existing_user(&self, email: &EmailAddress, user_id: &str) -> AuthResult<UserProfile> {
+        let item = self.client
+            .get_item()
+            .table_name(&self.users_table)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .send()
+            .await
+            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?
+            .item
+            .ok_or_else(|| AuthError::InternalError(format!("User {} vanished during creation", user_id)))?;
+
+        let stored_email = item.get("email").and_then(|v| v.as_s().ok()).cloned();
+        let mut user = UserProfile::from_item(item)?;
+        if stored_email.as_deref() != Some(email.as_str()) {
+            tracing::warn!(
+                "Re-keying user {} from stored email {:?} to {}",
+                user_id, stored_email, email
+            );
+            self.client
+                .update_item()
+                .table_name(&self.users_table)
+                .key("user_id", AttributeValue::S(user_id.to_string()))
+                .update_expression("SET email = :email")
+                .condition_expression("attribute_exists(user_id)")
+                .expression_attribute_values(":email", AttributeValue::S(email.to_string()))
+                .send()
+                .await
+                .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+            user.email = email.clone();
+        }
+        Ok(user)
+    }
+}
 
+#[async_trait]
+impl UserRepository for DynamoDBService {
     /// Get user by email using GSI
-    pub async fn get_user_by_email(&self, email: &str) -> AuthResult<Option<UserProfile>> {
+    async fn get_user_by_email(&self, email: &EmailAddress) -> AuthResult<Option<UserProfile>> {
         tracing::info!("Querying user by email: {} using table: {} and index: email-index", email, self.users_table);
         
         let result = self
@@ -161,23 +326,20 @@ impl DynamoDBService {
                 AuthError::DynamoDBError(format!("User query by email failed: {}", e))
             })?;
 
-        if let Some(items) = result.items {
-            if let Some(item) = items.first() {
-                let user = self.parse_user_from_item(item)?;
-                return Ok(Some(user));
-            }
-        }
-
-        Ok(None)
+        result
+            .items
+            .and_then(|items| items.into_iter().next())
+            .map(UserProfile::from_item)
+            .transpose()
     }
 
     /// Create new user with Cognito user ID
-    pub async fn create_user(&self, email: &str, cognito_user_id: &str) -> AuthResult<UserProfile> {
+    async fn create_user(&self, email: &EmailAddress, cognito_user_id: &str) -> AuthResult<UserProfile> {
         let now = Utc::now();
 
         let user = UserProfile {
             user_id: cognito_user_id.to_string(), // Use Cognito user ID, not a new UUID
-            email: email.to_string(),
+            email: email.clone(),
             status: UserStatus::default(), // Uses RegistrationEmailNotVerified
             full_name: None,
             content_description: None,
@@ -188,138 +350,265 @@ impl DynamoDBService {
             reviewed_by: None,
             reviewed_at: None,
             rejection_reason: None,
+            welcome_pending: false,
+            review_email_pending: false,
         };
 
-        let mut item = HashMap::new();
-        item.insert(
-            "user_id".to_string(),
-            AttributeValue::S(user.user_id.clone()),
-        );
-        item.insert("email".to_string(), AttributeValue::S(user.email.clone()));
-        item.insert(
-            "status".to_string(),
-            AttributeValue::S("REGISTRATION_EMAIL_NOT_VERIFIED".to_string()),
-        );
-        item.insert(
-            "created_at".to_string(),
-            AttributeValue::S(user.created_at.to_rfc3339()),
-        );
-        item.insert(
-            "updated_at".to_string(),
-            AttributeValue::S(user.updated_at.to_rfc3339()),
-        );
+        let item = user.to_item()?;
 
-        self.client
+        let result = self.client
             .put_item()
             .table_name(&self.users_table)
             .set_item(Some(item))
             .condition_expression("attribute_not_exists(user_id)")
             .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(user),
+            Err(e) if e.as_service_error().is_some_and(|se| se.is_conditional_check_failed_exception()) => {
+                self.adopt_existing_user(email, cognito_user_id).await
+            }
+            Err(e) => Err(AuthError::DynamoDBError(e.to_string())),
+        }
+    }
+
+    /// Conditionally move the user to a new status and append the change to `status_history`
+    /// in the same write, so the history can't miss or invent a transition
+    async fn transition_status(
+        &self,
+        user_id: &str,
+        from: UserStatus,
+        to: UserStatus,
+        actor: &str,
+    ) -> AuthResult<()> {
+        self.change_status(user_id, from, to, actor, Vec::new(), &[]).await?;
+        Ok(())
+    }
+
+    /// Read the status history kept on the user's item
+    async fn get_status_history(&self, user_id: &str) -> AuthResult<Vec<StatusChange>> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.users_table)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .projection_expression("user_id, status_history")
+            .send()
             .await
             .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
 
-        Ok(user)
+        let item = result
+            .item
+            .ok_or_else(|| AuthError::UserNotFound(user_id.to_string()))?;
+
+        match item.get("status_history") {
+            Some(history) => history
+                .as_l()
+                .map_err(|_| AuthError::InternalError("Invalid status_history".to_string()))?
+                .iter()
+                .cloned()
+                .map(from_attribute)
+                .collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get user by primary key
+    async fn get_user_by_id(&self, user_id: &str) -> AuthResult<Option<UserProfile>> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.users_table)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .send()
+            .await
+            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+
+        result
+            .item
+            .map(UserProfile::from_item)
+            .transpose()
     }
 
-    /// Update user status after email verification to need user info
-    pub async fn update_user_status_to_need_user_info(&self, email: &str) -> AuthResult<()> {
-        // First, get the user to find their user_id
-        let user = self
-            .get_user_by_email(email)
-            .await?
-            .ok_or_else(|| AuthError::ValidationError("User not found".to_string()))?;
+    /// Set welcome_pending in the same guarded write as the status change
+    async fn mark_email_verified(&self, user_id: &str, actor: &str) -> AuthResult<UserProfile> {
+        self.change_status(
+            user_id,
+            UserStatus::RegistrationEmailNotVerified,
+            UserStatus::RegistrationNeedUserInfo,
+            actor,
+            vec![("welcome_pending", AttributeValue::Bool(true))],
+            &[],
+        )
+        .await
+    }
 
-        let now = Utc::now();
+    /// Conditionally remove the flag, so only one of several racing sign-ins sees it cleared
+    async fn clear_welcome_pending(&self, user_id: &str) -> AuthResult<bool> {
+        self.clear_pending_flag(user_id, "welcome_pending").await
+    }
 
+    async fn clear_review_email_pending(&self, user_id: &str) -> AuthResult<bool> {
+        self.clear_pending_flag(user_id, "review_email_pending").await
+    }
+
+    /// Write the profile details in the same guarded write as the status change
+    async fn complete_profile(
+        &self,
+        user_id: &str,
+        details: &ProfileDetails,
+        actor: &str,
+    ) -> AuthResult<UserProfile> {
+        let sets = vec![
+            ("full_name", AttributeValue::S(details.full_name.clone())),
+            ("content_description", AttributeValue::S(details.content_description.clone())),
+            ("content_link", AttributeValue::S(details.content_link.clone())),
+        ];
+
+        self.change_status(
+            user_id,
+            UserStatus::RegistrationNeedUserInfo,
+            UserStatus::RegistrationNeedStripe,
+            actor,
+            sets,
+            &[],
+        )
+        .await
+    }
+
+    /// Conditionally attach the Stripe account, so a retried onboarding can't swap it for another
+    async fn set_stripe_account_id(&self, user_id: &str, stripe_account_id: &str) -> AuthResult<()> {
         self.client
             .update_item()
             .table_name(&self.users_table)
-            .key("user_id", AttributeValue::S(user.user_id))
-            .update_expression("SET #status = :status, updated_at = :updated_at")
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .update_expression("SET stripe_account_id = :account, updated_at = :updated_at")
+            .condition_expression(
+                "#status = :status AND \
+                 (attribute_not_exists(stripe_account_id) OR stripe_account_id = :account)",
+            )
             .expression_attribute_names("#status", "status")
             .expression_attribute_values(
                 ":status",
-                AttributeValue::S("REGISTRATION_NEED_USER_INFO".to_string()),
+                AttributeValue::S(UserStatus::RegistrationNeedStripe.as_str().to_string()),
             )
-            .expression_attribute_values(":updated_at", AttributeValue::S(now.to_rfc3339()))
-            .condition_expression("attribute_exists(user_id)")
+            .expression_attribute_values(":account", AttributeValue::S(stripe_account_id.to_string()))
+            .expression_attribute_values(":updated_at", AttributeValue::S(Utc::now().to_rfc3339()))
             .send()
             .await
-            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+            .map_err(|e| {
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception())
+                {
+                    AuthError::InvalidStatusTransition(format!(
+                        "User {} cannot be linked to Stripe account {}",
+                        user_id, stripe_account_id
+                    ))
+                } else {
+                    AuthError::DynamoDBError(e.to_string())
+                }
+            })?;
 
+        tracing::info!("User {} linked to Stripe account {}", user_id, stripe_account_id);
         Ok(())
     }
 
-    fn parse_user_from_item(
+    /// Record the decision, the review fields and the pending email flag in the same guarded
+    /// write as the status change
+    async fn record_review(
         &self,
-        item: &HashMap<String, AttributeValue>,
+        user_id: &str,
+        decision: &ReviewDecision,
+        reviewer_id: &str,
     ) -> AuthResult<UserProfile> {
-        let status_str = item
-            .get("status")
-            .and_then(|v| v.as_s().ok())
-            .ok_or_else(|| AuthError::InternalError("Missing status".to_string()))?;
-
-        let status = match status_str.as_str() {
-            "REGISTRATION_EMAIL_NOT_VERIFIED" => UserStatus::RegistrationEmailNotVerified,
-            "REGISTRATION_NEED_USER_INFO" => UserStatus::RegistrationNeedUserInfo,
-            "REGISTRATION_NEED_STRIPE" => UserStatus::RegistrationNeedStripe,
-            "AWAITING_REVIEW" => UserStatus::AwaitingReview,
-            "ACTIVE" => UserStatus::Active,
-            "REJECTED" => UserStatus::Rejected,
-            _ => return Err(AuthError::InternalError("Invalid status".to_string())),
-        };
+        let mut sets = vec![
+            ("reviewed_by", AttributeValue::S(reviewer_id.to_string())),
+            ("reviewed_at", AttributeValue::S(Utc::now().to_rfc3339())),
+            ("review_email_pending", AttributeValue::Bool(true)),
+        ];
+        let mut removes = Vec::new();
+        match decision {
+            ReviewDecision::Approve => removes.push("rejection_reason"),
+            ReviewDecision::Reject { reason } => {
+                sets.push(("rejection_reason", AttributeValue::S(reason.clone())))
+            }
+        }
 
-        let created_at = item
-            .get("created_at")
-            .and_then(|v| v.as_s().ok())
-            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .ok_or_else(|| AuthError::InternalError("Missing created_at".to_string()))?;
-
-        let updated_at = item
-            .get("updated_at")
-            .and_then(|v| v.as_s().ok())
-            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .ok_or_else(|| AuthError::InternalError("Missing updated_at".to_string()))?;
-
-        Ok(UserProfile {
-            user_id: item
-                .get("user_id")
-                .and_then(|v| v.as_s().ok())
-                .ok_or_else(|| AuthError::InternalError("Missing user_id".to_string()))?
-                .clone(),
-            email: item
-                .get("email")
-                .and_then(|v| v.as_s().ok())
-                .ok_or_else(|| AuthError::InternalError("Missing email".to_string()))?
-                .clone(),
-            status,
-            full_name: item.get("full_name").and_then(|v| v.as_s().ok()).cloned(),
-            content_description: item
-                .get("content_description")
-                .and_then(|v| v.as_s().ok())
-                .cloned(),
-            content_link: item
-                .get("content_link")
-                .and_then(|v| v.as_s().ok())
-                .cloned(),
-            stripe_account_id: item
-                .get("stripe_account_id")
-                .and_then(|v| v.as_s().ok())
-                .cloned(),
-            created_at,
-            updated_at,
-            reviewed_by: item.get("reviewed_by").and_then(|v| v.as_s().ok()).cloned(),
-            reviewed_at: item
-                .get("reviewed_at")
-                .and_then(|v| v.as_s().ok())
-                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-                .map(|dt| dt.with_timezone(&Utc)),
-            rejection_reason: item
-                .get("rejection_reason")
-                .and_then(|v| v.as_s().ok())
-                .cloned(),
-        })
+        self.change_status(
+            user_id,
+            UserStatus::AwaitingReview,
+            decision.status(),
+            reviewer_id,
+            sets,
+            &removes,
+        )
+        .await
     }
-}
\ No newline at end of file
+
+    /// Page through a status using the status-index GSI (sorted by created_at)
+    async fn list_users_by_status(
+        &self,
+        status: UserStatus,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> AuthResult<UserPage> {
+        let exclusive_start_key = cursor
+            .map(parse_user_page_cursor)
+            .transpose()?
+            .map(|(created_at, user_id)| {
+                HashMap::from([
+                    ("status".to_string(), AttributeValue::S(status.as_str().to_string())),
+                    ("created_at".to_string(), AttributeValue::S(created_at.to_string())),
+                    ("user_id".to_string(), AttributeValue::S(user_id.to_string())),
+                ])
+            });
+
+        let result = self
+            .client
+            .query()
+            .table_name(&self.users_table)
+            .index_name("status-index")
+            .key_condition_expression("#status = :status")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":status", AttributeValue::S(status.as_str().to_string()))
+            .limit(limit.min(i32::MAX as usize) as i32)
+            .set_exclusive_start_key(exclusive_start_key)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("User query by status failed: {}", e);
+                AuthError::DynamoDBError(format!("User query by status failed: {}", e))
+            })?;
+
+        let users = result
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(UserProfile::from_item)
+            .collect::<AuthResult<Vec<_>>>()?;
+
+        // DynamoDB hands back a LastEvaluatedKey whenever the page filled up; the last user
+        // on the page identifies the same position
+        let next_cursor = result
+            .last_evaluated_key
+            .and_then(|_| users.last().map(user_page_cursor));
+
+        Ok(UserPage { users, next_cursor })
+    }
+
+    /// Delete the user's item; the status history lives on it and goes with it
+    async fn delete_user(&self, user_id: &str) -> AuthResult<bool> {
+        let result = self
+            .client
+            .delete_item()
+            .table_name(&self.users_table)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+
+        Ok(result.attributes.is_some())
+    }
+}
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+use notifications_shared::{EmailQueueService, EmailRequest};
+
+use crate::{AuthError, AuthResult, EmailQueue};
+
+/// Hand emails to the notifications domain's SQS queue
+#[async_trait]
+impl EmailQueue for EmailQueueService {
+    async fn queue_email(&self, request: EmailRequest) -> AuthResult<()> {
+        EmailQueueService::queue_email(self, request)
+            .await
+            .map(|_| ())
+            .map_err(|e| AuthError::EmailDeliveryFailed(e.to_string()))
+    }
+}
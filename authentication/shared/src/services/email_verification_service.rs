@@ -0,0 +1,172 @@
+use aws_sdk_sqs::Client as SqsClient;
+use notifications_shared::{EmailQueueService, EmailRequest};
+use std::sync::Arc;
+
+use crate::{AuthError, AuthResult, EmailAddress, EmailQueue, UserRepository, UserStatus};
+
+/// What happens once a user has proved they own their email: a new registration moves on to
+/// the user info step and is sent the welcome email
+pub struct EmailVerificationService {
+    users: Arc<dyn UserRepository>,
+    emails: Arc<dyn EmailQueue>,
+    dashboard_url: String,
+}
+
+impl EmailVerificationService {
+    pub fn new(
+        users: Arc<dyn UserRepository>,
+        emails: Arc<dyn EmailQueue>,
+        dashboard_url: String,
+    ) -> Self {
+        Self {
+            users,
+            emails,
+            dashboard_url,
+        }
+    }
+
+    /// Create EmailVerificationService over the caller's user repository, using the
+    /// CDK-provided email queue and dashboard URL
+    pub fn from_env(users: Arc<dyn UserRepository>, sqs_client: SqsClient) -> Result<Self, AuthError> {
+        let emails = EmailQueueService::from_env(sqs_client)
+            .map_err(|e| AuthError::InternalError(e.to_string()))?;
+        let dashboard_url = std::env::var("DASHBOARD_URL").map_err(|e| {
+            tracing::error!("DASHBOARD_URL environment variable not set: {:?}", e);
+            AuthError::InternalError("DASHBOARD_URL not set".to_string())
+        })?;
+
+        Ok(Self::new(users, Arc::new(emails), dashboard_url))
+    }
+
+    /// Record a successful verification. A first verification advances the user and marks
+    /// their welcome email pending; while it is pending, this and every later verification
+    /// queues it. Returns true if this call queued the welcome.
+    ///
+    /// The status change is a conditional write, so when first verifications race only one
+    /// advances the user. The flag is cleared only after the welcome is queued, so a queueing
+    /// failure is returned and retried on the next sign-in: the welcome is sent at least once,
+    /// and twice only if a retry races another sign-in.
+    pub async fn email_verified(&self, email: &EmailAddress, actor: &str) -> AuthResult<bool> {
+        let mut user = self
+            .users
+            .get_user_by_email(email)
+            .await?
+            .ok_or_else(|| AuthError::UserNotFound(email.to_string()))?;
+
+        if user.status == UserStatus::RegistrationEmailNotVerified {
+            user = self.users.mark_email_verified(&user.user_id, actor).await?;
+        }
+        if !user.welcome_pending {
+            return Ok(false);
+        }
+
+        let welcome = EmailRequest::welcome(
+            user.email.to_string(),
+            user.first_name().to_string(),
+            self.dashboard_url.clone(),
+        );
+        self.emails.queue_email(welcome).await?;
+        self.users.clear_welcome_pending(&user.user_id).await?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryEmailQueue, InMemoryUserRepository};
+    use notifications_shared::EmailTemplates;
+
+    fn email() -> EmailAddress {
+        EmailAddress::parse("creator@example.com").unwrap()
+    }
+
+    async fn service() -> (
+        Arc<InMemoryUserRepository>,
+        Arc<InMemoryEmailQueue>,
+        EmailVerificationService,
+    ) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        users.create_user(&email(), "user-1").await.unwrap();
+        let emails = Arc::new(InMemoryEmailQueue::new());
+        let service = EmailVerificationService::new(
+            users.clone(),
+            emails.clone(),
+            "https://app.example.com/dashboard".to_string(),
+        );
+        (users, emails, service)
+    }
+
+    #[tokio::test]
+    async fn test_first_verification_welcomes_once() {
+        let (users, emails, service) = service().await;
+
+        assert!(service.email_verified(&email(), "verify-auth-challenge").await.unwrap());
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::RegistrationNeedUserInfo);
+
+        let queued = emails.queued();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].template_name, EmailTemplates::WELCOME);
+        assert_eq!(queued[0].recipient, "creator@example.com");
+        assert_eq!(queued[0].template_data["firstName"], "there");
+        assert_eq!(
+            queued[0].template_data["dashboardUrl"],
+            "https://app.example.com/dashboard"
+        );
+
+        // Signing in again doesn't welcome them again
+        assert!(!users.get_user_by_id("user-1").await.unwrap().unwrap().welcome_pending);
+        assert!(!service.email_verified(&email(), "verify-auth-challenge").await.unwrap());
+        assert_eq!(emails.queued().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_welcome_that_failed_to_queue_is_sent_on_next_sign_in() {
+        let (users, emails, service) = service().await;
+
+        emails.fail_next(1);
+        let result = service.email_verified(&email(), "verify-auth-challenge").await;
+        assert!(matches!(result, Err(AuthError::EmailDeliveryUnavailable(_))));
+
+        // The user is advanced regardless, with the welcome still owed
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::RegistrationNeedUserInfo);
+        assert!(user.welcome_pending);
+        assert!(emails.queued().is_empty());
+
+        assert!(service.email_verified(&email(), "verify-auth-challenge").await.unwrap());
+        assert!(!service.email_verified(&email(), "verify-auth-challenge").await.unwrap());
+        let queued = emails.queued();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].template_name, EmailTemplates::WELCOME);
+        assert!(!users.get_user_by_id("user-1").await.unwrap().unwrap().welcome_pending);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_verifications_welcome_once() {
+        let (_, emails, service) = service().await;
+
+        let address = email();
+        let (first, second) = tokio::join!(
+            service.email_verified(&address, "verify-auth-challenge"),
+            service.email_verified(&address, "verify-auth-challenge")
+        );
+
+        // The loser either saw the new status or lost the conditional write
+        let advanced = [first, second].into_iter().filter(|r| matches!(r, Ok(true))).count();
+        assert_eq!(advanced, 1);
+        assert_eq!(emails.queued().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_email_is_an_error() {
+        let (_, emails, service) = service().await;
+
+        let other = EmailAddress::parse("other@example.com").unwrap();
+        let result = service.email_verified(&other, "verify-auth-challenge").await;
+        assert!(matches!(result, Err(AuthError::UserNotFound(_))));
+        assert!(emails.queued().is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use aws_sdk_ses::Client as SesClient;
+use aws_sdk_sqs::Client as SqsClient;
+use notifications_shared::{EmailPriority, EmailQueueService, EmailRequest};
+use std::sync::Arc;
+
+use crate::{
+    AuthError, AuthResult, EmailDelivery, EmailQueue, EmailSender, OtpDeliveryStrategy,
+    SESService,
+};
+
+/// Gets OTP and magic link emails to the user following an OtpDeliveryStrategy
+pub struct OtpDeliveryService {
+    strategy: OtpDeliveryStrategy,
+    sender: Arc<dyn EmailSender>,
+    queue: Option<Arc<dyn EmailQueue>>,
+}
+
+impl OtpDeliveryService {
+    /// `queue` is required by every strategy but Direct
+    pub fn new(
+        strategy: OtpDeliveryStrategy,
+        sender: Arc<dyn EmailSender>,
+        queue: Option<Arc<dyn EmailQueue>>,
+    ) -> Result<Self, AuthError> {
+        if queue.is_none() && strategy != OtpDeliveryStrategy::Direct {
+            return Err(AuthError::InternalError(format!(
+                "{:?} OTP delivery needs the email queue",
+                strategy
+            )));
+        }
+        Ok(Self {
+            strategy,
+            sender,
+            queue,
+        })
+    }
+
+    /// Create OtpDeliveryService from FROM_EMAIL and OTP_DELIVERY_STRATEGY, using the
+    /// CDK-provided email queue unless delivery is direct only
+    pub fn from_env(ses_client: SesClient, sqs_client: SqsClient) -> Result<Self, AuthError> {
+        let from_email = std::env::var("FROM_EMAIL").map_err(|e| {
+            tracing::error!("FROM_EMAIL environment variable not set: {:?}", e);
+            AuthError::InternalError("FROM_EMAIL not set".to_string())
+        })?;
+        let sender = SESService::new(ses_client, from_email)?;
+
+        let strategy = OtpDeliveryStrategy::from_env()?;
+        let queue = match strategy {
+            OtpDeliveryStrategy::Direct => None,
+            _ => {
+                let queue = EmailQueueService::from_env(sqs_client)
+                    .map_err(|e| AuthError::InternalError(e.to_string()))?;
+                Some(Arc::new(queue) as Arc<dyn EmailQueue>)
+            }
+        };
+
+        tracing::info!("OTP delivery strategy: {:?}", strategy);
+        Self::new(strategy, Arc::new(sender), queue)
+    }
+
+    /// Deliver a challenge email at high priority, returning the path it took. Under
+    /// DirectWithFallback only transient SES failures are queued; an email SES rejected outright
+    /// would fail the same way from the queue, so that error is returned instead.
+    pub async fn deliver(&self, mut request: EmailRequest) -> AuthResult<EmailDelivery> {
+        request.priority = EmailPriority::High;
+
+        match self.strategy {
+            OtpDeliveryStrategy::Direct => {
+                self.sender.send_email(request).await?;
+                Ok(EmailDelivery::Direct)
+            }
+            OtpDeliveryStrategy::Queued => self.enqueue(request).await,
+            OtpDeliveryStrategy::DirectWithFallback => {
+                match self.sender.send_email(request.clone()).await {
+                    Ok(()) => Ok(EmailDelivery::Direct),
+                    Err(AuthError::EmailDeliveryUnavailable(e)) => {
+                        tracing::warn!("Direct delivery unavailable, queueing instead: {}", e);
+                        self.enqueue(request).await
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+
+    async fn enqueue(&self, request: EmailRequest) -> AuthResult<EmailDelivery> {
+        // new() guarantees the queue for every strategy that gets here
+        let queue = self.queue.as_ref().ok_or_else(|| {
+            AuthError::InternalError("OTP delivery has no email queue".to_string())
+        })?;
+        queue.queue_email(request).await?;
+        Ok(EmailDelivery::Queued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryEmailQueue, InMemoryEmailSender};
+
+    fn delivery(
+        strategy: OtpDeliveryStrategy,
+    ) -> (Arc<InMemoryEmailSender>, Arc<InMemoryEmailQueue>, OtpDeliveryService) {
+        let sender = Arc::new(InMemoryEmailSender::new());
+        let queue = Arc::new(InMemoryEmailQueue::new());
+        let service = OtpDeliveryService::new(strategy, sender.clone(), Some(queue.clone())).unwrap();
+        (sender, queue, service)
+    }
+
+    fn otp_email() -> EmailRequest {
+        EmailRequest::otp("user@example.com".to_string(), "123456".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_fallback_sends_directly_when_ses_is_up() {
+        let (sender, queue, service) = delivery(OtpDeliveryStrategy::DirectWithFallback);
+
+        assert_eq!(service.deliver(otp_email()).await.unwrap(), EmailDelivery::Direct);
+        assert_eq!(sender.sent().len(), 1);
+        assert!(queue.queued().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_queues_on_transient_failure() {
+        let (sender, queue, service) = delivery(OtpDeliveryStrategy::DirectWithFallback);
+        sender.fail(true);
+
+        let mut request = otp_email();
+        request.priority = EmailPriority::Normal;
+        assert_eq!(service.deliver(request).await.unwrap(), EmailDelivery::Queued);
+
+        let queued = queue.queued();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].template_data["otp"], "123456");
+        assert!(matches!(queued[0].priority, EmailPriority::High));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_returns_permanent_failures() {
+        let (sender, queue, service) = delivery(OtpDeliveryStrategy::DirectWithFallback);
+        sender.fail(false);
+
+        let result = service.deliver(otp_email()).await;
+        assert!(matches!(result, Err(AuthError::EmailDeliveryFailed(_))));
+        assert!(queue.queued().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_direct_and_queued_strategies() {
+        let (sender, queue, service) = delivery(OtpDeliveryStrategy::Direct);
+        sender.fail(true);
+        let result = service.deliver(otp_email()).await;
+        assert!(matches!(result, Err(AuthError::EmailDeliveryUnavailable(_))));
+        assert!(queue.queued().is_empty());
+
+        let (sender, queue, service) = delivery(OtpDeliveryStrategy::Queued);
+        assert_eq!(service.deliver(otp_email()).await.unwrap(), EmailDelivery::Queued);
+        assert!(sender.sent().is_empty());
+        assert_eq!(queue.queued().len(), 1);
+
+        let sender = Arc::new(InMemoryEmailSender::new());
+        assert!(OtpDeliveryService::new(OtpDeliveryStrategy::Queued, sender.clone(), None).is_err());
+        assert!(OtpDeliveryService::new(OtpDeliveryStrategy::Direct, sender, None).is_ok());
+    }
+}
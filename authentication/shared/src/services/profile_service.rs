@@ -0,0 +1,112 @@
+use aws_sdk_dynamodb::Client as DynamoClient;
+use std::sync::Arc;
+
+use crate::{
+    normalize_profile_details, AuthError, AuthResult, DynamoDBService, ProfileDetails,
+    UserProfile, UserRepository,
+};
+
+/// Actor recorded in the status history when a creator completes their profile
+const PROFILE_ACTOR: &str = "complete-profile";
+
+/// Creator profile completion for users in RegistrationNeedUserInfo
+pub struct ProfileService {
+    users: Arc<dyn UserRepository>,
+}
+
+impl ProfileService {
+    pub fn new(users: Arc<dyn UserRepository>) -> Self {
+        Self { users }
+    }
+
+    /// Create ProfileService backed by the CDK-provided users table
+    pub fn from_env(client: DynamoClient) -> Result<Self, AuthError> {
+        let users = DynamoDBService::from_env(client)?;
+        Ok(Self::new(Arc::new(users)))
+    }
+
+    /// Validate and save the creator's details, moving them on to RegistrationNeedStripe
+    pub async fn complete_profile(
+        &self,
+        user_id: &str,
+        details: &ProfileDetails,
+    ) -> AuthResult<UserProfile> {
+        let details = normalize_profile_details(details)?;
+        let user = self
+            .users
+            .complete_profile(user_id, &details, PROFILE_ACTOR)
+            .await?;
+
+        tracing::info!("User {} completed their profile", user_id);
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EmailAddress, InMemoryUserRepository, UserStatus};
+
+    fn email(address: &str) -> EmailAddress {
+        EmailAddress::parse(address).unwrap()
+    }
+
+    fn details(content_link: &str) -> ProfileDetails {
+        ProfileDetails {
+            full_name: " Ada Lovelace ".to_string(),
+            content_description: "Maths videos".to_string(),
+            content_link: content_link.to_string(),
+        }
+    }
+
+    async fn setup(status: UserStatus) -> (Arc<InMemoryUserRepository>, ProfileService) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let mut user = users.create_user(&email("ada@example.com"), "user-1").await.unwrap();
+        user.status = status;
+        users.insert_user(user);
+        (users.clone(), ProfileService::new(users))
+    }
+
+    #[tokio::test]
+    async fn test_complete_profile() {
+        let (users, service) = setup(UserStatus::RegistrationNeedUserInfo).await;
+
+        let user = service
+            .complete_profile("user-1", &details("YouTube.com/@ada"))
+            .await
+            .unwrap();
+        assert_eq!(user.status, UserStatus::RegistrationNeedStripe);
+        assert_eq!(user.full_name.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(user.content_description.as_deref(), Some("Maths videos"));
+        assert_eq!(user.content_link.as_deref(), Some("https://youtube.com/@ada"));
+
+        let history = users.get_status_history("user-1").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].actor, PROFILE_ACTOR);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_details_are_not_saved() {
+        let (users, service) = setup(UserStatus::RegistrationNeedUserInfo).await;
+
+        let result = service.complete_profile("user-1", &details("not a link")).await;
+        assert!(matches!(result, Err(AuthError::ValidationError(_))));
+
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::RegistrationNeedUserInfo);
+        assert_eq!(user.content_link, None);
+    }
+
+    #[tokio::test]
+    async fn test_complete_profile_requires_need_user_info() {
+        let (users, service) = setup(UserStatus::RegistrationEmailNotVerified).await;
+
+        let result = service
+            .complete_profile("user-1", &details("example.com"))
+            .await;
+        assert!(matches!(result, Err(AuthError::InvalidStatusTransition(_))));
+
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.full_name, None);
+    }
+}
@@ -1,15 +1,30 @@
-use aws_sdk_dynamodb::{Client as DynamoClient, types::AttributeValue};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    Client as DynamoClient,
+    operation::transact_write_items::TransactWriteItemsError,
+    types::{AttributeValue, ReturnValue, TransactWriteItem, Update},
+};
 use std::collections::HashMap;
-use crate::{AuthError, AuthResult, RateLimitRecord, current_timestamp};
+
+use crate::{
+    AuthError, AuthResult, DynamoItem, RateLimitAction, RateLimitCounter, RateLimitPolicies,
+    RateLimitRecord, RateLimitStore, RateLimitUsage, current_timestamp, rate_limit_exceeded,
+    retry_transaction_conflicts,
+};
 
 pub struct RateLimitService {
     client: DynamoClient,
     table_name: String,
+    policies: RateLimitPolicies,
 }
 
 impl RateLimitService {
     pub fn new(client: DynamoClient, table_name: String) -> Self {
-        Self { client, table_name }
+        Self::with_policies(client, table_name, RateLimitPolicies::default())
+    }
+
+    pub fn with_policies(client: DynamoClient, table_name: String, policies: RateLimitPolicies) -> Self {
+        Self { client, table_name, policies }
     }
 
     /// Create RateLimitService using CDK-provided table name from environment variable
@@ -21,99 +36,166 @@ impl RateLimitService {
                 AuthError::InternalError("RATE_LIMIT_TABLE_NAME not set".to_string())
             })?;
         
+        let policies = RateLimitPolicies::from_env()?;
+
         tracing::info!("RateLimitService initialized with table: {}", table_name);
-        Ok(Self::new(client, table_name))
+        Ok(Self::with_policies(client, table_name, policies))
     }
 
-    /// Check if email is rate limited (max 3 requests per 15 minutes)
-    pub async fn check_rate_limit(&self, email: &str) -> AuthResult<bool> {
-        let now = current_timestamp();
-        let fifteen_minutes_ago = now - (15 * 60); // 15 minutes in seconds
-
-        tracing::info!("Checking rate limit for email: {} using table: {}", email, self.table_name);
-
-        // Query recent requests for this email
-        let result = self.client
-            .query()
-            .table_name(&self.table_name)
-            .key_condition_expression("email = :email AND request_timestamp > :timestamp")
-            .expression_attribute_values(":email", AttributeValue::S(email.to_string()))
-            .expression_attribute_values(":timestamp", AttributeValue::N(fifteen_minutes_ago.to_string()))
-            .send()
-            .await
-            .map_err(|e| {
-                tracing::error!("Rate limit query failed: {}", e);
-                AuthError::DynamoDBError(format!("Rate limit query failed: {}", e))
-            })?;
-
-        let request_count = result.items.as_ref().map(|items| items.len()).unwrap_or(0);
-        
-        if request_count >= 3 {
-            tracing::warn!("Rate limit exceeded for email: {}", email);
-            return Ok(false); // Rate limited
+    /// Every window's counter in one counter partition, oldest first
+    async fn query_counters(&self, counter_key: &str) -> AuthResult<Vec<HashMap<String, AttributeValue>>> {
+        let mut items = Vec::new();
+        let mut exclusive_start_key = None;
+        loop {
+            let result = self.client
+                .query()
+                .table_name(&self.table_name)
+                .key_condition_expression("email = :key")
+                .expression_attribute_values(":key", AttributeValue::S(counter_key.to_string()))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Rate limit query failed: {}", e);
+                    AuthError::DynamoDBError(format!("Rate limit query failed: {}", e))
+                })?;
+
+            items.extend(result.items.unwrap_or_default());
+            exclusive_start_key = result.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                return Ok(items);
+            }
         }
+    }
 
-        Ok(true) // Not rate limited
+    /// Increment a counter, creating it on first use, only while it is below the window's limit
+    fn increment(&self, counter: &RateLimitCounter) -> AuthResult<TransactWriteItem> {
+        let update = Update::builder()
+            .table_name(&self.table_name)
+            .key("email", AttributeValue::S(counter.key.clone()))
+            .key("request_timestamp", AttributeValue::N(counter.window_start.to_string()))
+            .update_expression("ADD request_count :one SET #ttl = :ttl, schema_version = :version")
+            .condition_expression("attribute_not_exists(request_count) OR request_count < :max")
+            .expression_attribute_names("#ttl", "ttl")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":max", AttributeValue::N(counter.window.max_requests.to_string()))
+            // Each window has its own item, so it can expire as soon as the window closes
+            .expression_attribute_values(":ttl", AttributeValue::N(counter.window_end().to_string()))
+            .expression_attribute_values(":version", AttributeValue::N(RateLimitRecord::SCHEMA_VERSION.to_string()))
+            .build()
+            .map_err(|e| AuthError::DynamoDBError(format!("Invalid rate limit update: {}", e)))?;
+
+        Ok(TransactWriteItem::builder().update(update).build())
     }
 
-    /// Record a new OTP request for rate limiting
-    pub async fn record_request(&self, email: &str) -> AuthResult<()> {
+    /// Charge every counter in a single transaction, so concurrent invocations can never
+    /// both take the last slot of a window
+    async fn try_consume_rate_limits(&self, requests: &[(RateLimitAction, String)]) -> AuthResult<()> {
         let now = current_timestamp();
-        let ttl = now + (15 * 60); // TTL 15 minutes from now
+        let counters = self.policies.counters(requests, now);
+        if counters.is_empty() {
+            return Ok(());
+        }
 
-        let record = RateLimitRecord {
-            email: email.to_string(),
-            request_timestamp: now,
-            ttl,
-        };
+        tracing::info!(
+            "Consuming {} rate limit counters using table: {}",
+            counters.len(), self.table_name
+        );
 
-        let mut item = HashMap::new();
-        item.insert("email".to_string(), AttributeValue::S(record.email));
-        item.insert("request_timestamp".to_string(), AttributeValue::N(record.request_timestamp.to_string()));
-        item.insert("ttl".to_string(), AttributeValue::N(record.ttl.to_string()));
+        let items = counters
+            .iter()
+            .map(|counter| self.increment(counter))
+            .collect::<AuthResult<Vec<_>>>()?;
 
-        self.client
-            .put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
+        let result = self.client
+            .transact_write_items()
+            .set_transact_items(Some(items))
             .send()
-            .await
-            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
-
-        tracing::info!("Recorded OTP request for email: {}", email);
-        Ok(())
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                // Cancellation reasons line up with the transaction items; a failed condition
+                // means that counter's window is full
+                let reasons = match e.as_service_error() {
+                    Some(TransactWriteItemsError::TransactionCanceledException(cancelled)) => {
+                        cancelled.cancellation_reasons()
+                    }
+                    _ => &[],
+                };
+                let full: Vec<&RateLimitCounter> = reasons
+                    .iter()
+                    .zip(&counters)
+                    .filter(|(reason, _)| reason.code() == Some("ConditionalCheckFailed"))
+                    .map(|(_, counter)| counter)
+                    .collect();
+                let conflicted = reasons
+                    .iter()
+                    .any(|reason| reason.code() == Some("TransactionConflict"));
+
+                if !full.is_empty() {
+                    Err(rate_limit_exceeded(&full, now))
+                } else if conflicted {
+                    Err(AuthError::TransactionConflict(format!("Rate limit transaction cancelled: {}", e)))
+                } else {
+                    tracing::error!("Rate limit transaction failed: {}", e);
+                    Err(AuthError::DynamoDBError(format!("Rate limit transaction failed: {}", e)))
+                }
+            }
+        }
     }
+}
 
-    /// Get remaining time until rate limit resets (in seconds)
-    pub async fn get_rate_limit_reset_time(&self, email: &str) -> AuthResult<Option<i64>> {
-        let now = current_timestamp();
-        let fifteen_minutes_ago = now - (15 * 60);
+#[async_trait]
+impl RateLimitStore for RateLimitService {
+    /// Retries transactions that lost a race with a concurrent request for the same counters
+    async fn consume_rate_limits(&self, requests: &[(RateLimitAction, String)]) -> AuthResult<()> {
+        retry_transaction_conflicts(|| self.try_consume_rate_limits(requests)).await
+    }
 
-        let result = self.client
-            .query()
-            .table_name(&self.table_name)
-            .key_condition_expression("email = :email AND request_timestamp > :timestamp")
-            .expression_attribute_values(":email", AttributeValue::S(email.to_string()))
-            .expression_attribute_values(":timestamp", AttributeValue::N(fifteen_minutes_ago.to_string()))
-            .scan_index_forward(false) // Get most recent first
-            .limit(1)
-            .send()
-            .await
-            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
-
-        if let Some(items) = result.items {
-            if let Some(item) = items.first() {
-                if let Some(timestamp_attr) = item.get("request_timestamp") {
-                    if let Ok(timestamp_str) = timestamp_attr.as_n() {
-                        if let Ok(timestamp) = timestamp_str.parse::<i64>() {
-                            let reset_time = timestamp + (15 * 60) - now;
-                            return Ok(Some(reset_time.max(0)));
-                        }
-                    }
+    /// Delete every window in each counter partition under the current policies. Counters from
+    /// windows that have since been removed from the policy aren't found, but expire through
+    /// their TTL as soon as their window closes.
+    async fn delete_rate_limits(&self, actions: &[RateLimitAction], key: &str) -> AuthResult<usize> {
+        let mut deleted = 0;
+        for counter_key in self.policies.counter_keys(actions, key) {
+            for item in self.query_counters(&counter_key).await? {
+                let key = HashMap::from_iter(
+                    item.into_iter()
+                        .filter(|(name, _)| name == "email" || name == "request_timestamp"),
+                );
+                let result = self.client
+                    .delete_item()
+                    .table_name(&self.table_name)
+                    .set_key(Some(key))
+                    .return_values(ReturnValue::AllOld)
+                    .send()
+                    .await
+                    .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+                if result.attributes.is_some() {
+                    deleted += 1;
                 }
             }
         }
 
-        Ok(None)
+        tracing::info!("Deleted {} rate limit counters for: {}", deleted, key);
+        Ok(deleted)
     }
-}
\ No newline at end of file
+
+    async fn get_rate_limits(&self, actions: &[RateLimitAction], key: &str) -> AuthResult<Vec<RateLimitUsage>> {
+        let mut usage = Vec::new();
+        for (action, window, counter_key) in self.policies.counter_partitions(actions, key) {
+            for item in self.query_counters(&counter_key).await? {
+                let record = RateLimitRecord::from_item(item)?;
+                usage.push(RateLimitUsage {
+                    action: action.as_str().to_string(),
+                    window_seconds: window.window_seconds,
+                    window_start: record.window_start,
+                    request_count: record.request_count.max(0) as usize,
+                });
+            }
+        }
+        Ok(usage)
+    }
+}
@@ -0,0 +1,316 @@
+use aws_sdk_dynamodb::Client as DynamoClient;
+use aws_sdk_sqs::Client as SqsClient;
+use notifications_shared::{EmailQueueService, EmailRequest};
+use std::sync::Arc;
+
+use crate::{
+    AuthError, AuthResult, DynamoDBService, EmailQueue, ReviewDecision, UserPage, UserProfile,
+    UserRepository, UserStatus,
+};
+
+/// Page size used when the caller doesn't ask for one
+pub const DEFAULT_REVIEW_PAGE_SIZE: usize = 25;
+
+/// Largest page an admin can ask for in one call
+pub const MAX_REVIEW_PAGE_SIZE: usize = 100;
+
+/// Admin review of creators waiting in AwaitingReview
+pub struct ReviewService {
+    users: Arc<dyn UserRepository>,
+    emails: Arc<dyn EmailQueue>,
+    dashboard_url: String,
+    profile_url: String,
+}
+
+impl ReviewService {
+    pub fn new(
+        users: Arc<dyn UserRepository>,
+        emails: Arc<dyn EmailQueue>,
+        dashboard_url: String,
+        profile_url: String,
+    ) -> Self {
+        Self {
+            users,
+            emails,
+            dashboard_url,
+            profile_url,
+        }
+    }
+
+    /// Create ReviewService using the CDK-provided users table, email queue and app URLs
+    pub fn from_env(dynamo_client: DynamoClient, sqs_client: SqsClient) -> Result<Self, AuthError> {
+        let users = DynamoDBService::from_env(dynamo_client)?;
+        let emails = EmailQueueService::from_env(sqs_client)
+            .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+        let url = |name: &str| {
+            std::env::var(name).map_err(|e| {
+                tracing::error!("{} environment variable not set: {:?}", name, e);
+                AuthError::InternalError(format!("{} not set", name))
+            })
+        };
+        let dashboard_url = url("DASHBOARD_URL")?;
+        let profile_url = url("PROFILE_URL")?;
+
+        Ok(Self::new(Arc::new(users), Arc::new(emails), dashboard_url, profile_url))
+    }
+
+    /// Creators awaiting review, oldest first. `limit` defaults to DEFAULT_REVIEW_PAGE_SIZE and
+    /// is capped at MAX_REVIEW_PAGE_SIZE.
+    pub async fn list_pending(
+        &self,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> AuthResult<UserPage> {
+        let limit = limit
+            .unwrap_or(DEFAULT_REVIEW_PAGE_SIZE)
+            .clamp(1, MAX_REVIEW_PAGE_SIZE);
+        self.users
+            .list_users_by_status(UserStatus::AwaitingReview, limit, cursor)
+            .await
+    }
+
+    /// Approve a creator, activating their account
+    pub async fn approve(&self, user_id: &str, reviewer_id: &str) -> AuthResult<UserProfile> {
+        self.review(user_id, reviewer_id, ReviewDecision::Approve).await
+    }
+
+    /// Reject a creator with a reason they will be shown
+    pub async fn reject(
+        &self,
+        user_id: &str,
+        reviewer_id: &str,
+        reason: &str,
+    ) -> AuthResult<UserProfile> {
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(AuthError::ValidationError(
+                "A rejection reason is required".to_string(),
+            ));
+        }
+
+        let decision = ReviewDecision::Reject {
+            reason: reason.to_string(),
+        };
+        self.review(user_id, reviewer_id, decision).await
+    }
+
+    async fn review(
+        &self,
+        user_id: &str,
+        reviewer_id: &str,
+        decision: ReviewDecision,
+    ) -> AuthResult<UserProfile> {
+        if reviewer_id.trim().is_empty() {
+            return Err(AuthError::ValidationError("Reviewer ID is required".to_string()));
+        }
+
+        let user = match self.users.record_review(user_id, &decision, reviewer_id).await {
+            Ok(user) => {
+                tracing::info!(
+                    "User {} reviewed by {}: {}",
+                    user_id,
+                    reviewer_id,
+                    user.status.as_str()
+                );
+                user
+            }
+            // A retry of a review whose email didn't queue: the decision stands, so only the
+            // email is left to send
+            Err(AuthError::InvalidStatusTransition(message)) => {
+                match self.users.get_user_by_id(user_id).await? {
+                    Some(user) if user.review_email_pending && user.status == decision.status() => user,
+                    _ => return Err(AuthError::InvalidStatusTransition(message)),
+                }
+            }
+            Err(e) => return Err(e),
+        };
+
+        // The flag is cleared only after the email is queued, so a queueing failure is returned
+        // and the next call for the same decision sends it
+        if user.review_email_pending {
+            self.emails.queue_email(self.decision_email(&user)).await?;
+            self.users.clear_review_email_pending(&user.user_id).await?;
+        }
+
+        Ok(user)
+    }
+
+    /// The email for the decision recorded on the user, which on a retry may have been made by
+    /// an earlier call
+    fn decision_email(&self, user: &UserProfile) -> EmailRequest {
+        let first_name = user.first_name().to_string();
+
+        match &user.rejection_reason {
+            None => EmailRequest::account_approved(
+                user.email.to_string(),
+                first_name,
+                self.dashboard_url.clone(),
+            ),
+            Some(reason) => EmailRequest::account_rejected(
+                user.email.to_string(),
+                first_name,
+                reason.clone(),
+                self.profile_url.clone(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EmailAddress, InMemoryEmailQueue, InMemoryUserRepository};
+    use chrono::{Duration, Utc};
+    use notifications_shared::EmailTemplates;
+
+    fn creator(user_id: &str, status: UserStatus, minutes_ago: i64) -> UserProfile {
+        let created_at = Utc::now() - Duration::minutes(minutes_ago);
+        UserProfile {
+            user_id: user_id.to_string(),
+            email: EmailAddress::parse(&format!("{}@example.com", user_id)).unwrap(),
+            status,
+            full_name: Some("Ada Lovelace".to_string()),
+            content_description: None,
+            content_link: None,
+            stripe_account_id: None,
+            created_at,
+            updated_at: created_at,
+            reviewed_by: None,
+            reviewed_at: None,
+            rejection_reason: None,
+            welcome_pending: false,
+            review_email_pending: false,
+        }
+    }
+
+    fn service() -> (Arc<InMemoryUserRepository>, Arc<InMemoryEmailQueue>, ReviewService) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let emails = Arc::new(InMemoryEmailQueue::new());
+        let service = ReviewService::new(
+            users.clone(),
+            emails.clone(),
+            "https://app.example.com/dashboard".to_string(),
+            "https://app.example.com/profile".to_string(),
+        );
+        (users, emails, service)
+    }
+
+    #[tokio::test]
+    async fn test_approve() {
+        let (users, emails, service) = service();
+        users.insert_user(creator("user-1", UserStatus::AwaitingReview, 10));
+
+        let user = service.approve("user-1", "admin-1").await.unwrap();
+        assert_eq!(user.status, UserStatus::Active);
+        assert_eq!(user.reviewed_by.as_deref(), Some("admin-1"));
+        assert!(user.reviewed_at.is_some());
+        assert_eq!(user.rejection_reason, None);
+
+        let history = users.get_status_history("user-1").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].actor, "admin-1");
+
+        let queued = emails.queued();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].template_name, EmailTemplates::ACCOUNT_APPROVED);
+        assert_eq!(queued[0].recipient, "user-1@example.com");
+        assert_eq!(queued[0].template_data["firstName"], "Ada");
+        assert_eq!(
+            queued[0].template_data["dashboardUrl"],
+            "https://app.example.com/dashboard"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reject_requires_reason() {
+        let (users, emails, service) = service();
+        users.insert_user(creator("user-1", UserStatus::AwaitingReview, 10));
+
+        let result = service.reject("user-1", "admin-1", "  ").await;
+        assert!(matches!(result, Err(AuthError::ValidationError(_))));
+        assert!(emails.queued().is_empty());
+
+        let user = service
+            .reject("user-1", "admin-1", "Content link is broken")
+            .await
+            .unwrap();
+        assert_eq!(user.status, UserStatus::Rejected);
+        assert_eq!(user.rejection_reason.as_deref(), Some("Content link is broken"));
+
+        let queued = emails.queued();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].template_name, EmailTemplates::ACCOUNT_REJECTED);
+        assert_eq!(
+            queued[0].template_data["rejectionReason"],
+            "Content link is broken"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_review_requires_awaiting_review() {
+        let (users, emails, service) = service();
+        users.insert_user(creator("user-1", UserStatus::RegistrationNeedStripe, 10));
+
+        let result = service.approve("user-1", "admin-1").await;
+        assert!(matches!(result, Err(AuthError::InvalidStatusTransition(_))));
+
+        let result = service.approve("missing", "admin-1").await;
+        assert!(matches!(result, Err(AuthError::InvalidStatusTransition(_))));
+        assert!(emails.queued().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_review_email_is_sent_when_the_review_is_retried() {
+        let (users, emails, service) = service();
+        users.insert_user(creator("user-1", UserStatus::AwaitingReview, 10));
+
+        // The decision is recorded even though its email couldn't be queued
+        emails.fail_next(1);
+        let result = service.reject("user-1", "admin-1", "Content link is broken").await;
+        assert!(matches!(result, Err(AuthError::EmailDeliveryUnavailable(_))));
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::Rejected);
+        assert!(user.review_email_pending);
+        assert!(emails.queued().is_empty());
+
+        // A retry doesn't review again, and a different decision isn't taken for one
+        let result = service.approve("user-1", "admin-2").await;
+        assert!(matches!(result, Err(AuthError::InvalidStatusTransition(_))));
+        let user = service.reject("user-1", "admin-1", "Content link is broken").await.unwrap();
+        assert_eq!(user.reviewed_by.as_deref(), Some("admin-1"));
+        assert_eq!(users.get_status_history("user-1").await.unwrap().len(), 1);
+
+        let queued = emails.queued();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].template_name, EmailTemplates::ACCOUNT_REJECTED);
+        assert!(!users.get_user_by_id("user-1").await.unwrap().unwrap().review_email_pending);
+
+        // Once sent, the review can't be repeated
+        let result = service.reject("user-1", "admin-1", "Content link is broken").await;
+        assert!(matches!(result, Err(AuthError::InvalidStatusTransition(_))));
+        assert_eq!(emails.queued().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_pending_pages_oldest_first() {
+        let (users, _, service) = service();
+        users.insert_user(creator("user-1", UserStatus::AwaitingReview, 30));
+        users.insert_user(creator("user-2", UserStatus::AwaitingReview, 20));
+        users.insert_user(creator("user-3", UserStatus::AwaitingReview, 10));
+        users.insert_user(creator("user-4", UserStatus::Active, 40));
+
+        let page = service.list_pending(Some(2), None).await.unwrap();
+        let ids: Vec<_> = page.users.iter().map(|u| u.user_id.as_str()).collect();
+        assert_eq!(ids, ["user-1", "user-2"]);
+
+        let cursor = page.next_cursor.expect("more users to page through");
+        let page = service.list_pending(Some(2), Some(&cursor)).await.unwrap();
+        let ids: Vec<_> = page.users.iter().map(|u| u.user_id.as_str()).collect();
+        assert_eq!(ids, ["user-3"]);
+        assert_eq!(page.next_cursor, None);
+
+        let result = service.list_pending(None, Some("garbage")).await;
+        assert!(matches!(result, Err(AuthError::ValidationError(_))));
+    }
+}
@@ -0,0 +1,402 @@
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, ReturnValue},
+    Client as DynamoClient,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    current_timestamp, AuthError, AuthResult, Session, SessionPolicy, SessionStore, UserProfile,
+};
+
+/// Name of the GSI on the sessions table used to find all sessions for a user
+const USER_ID_INDEX: &str = "user-id-index";
+
+/// DynamoDB-backed session store for the user-sessions table
+pub struct DynamoDBSessionStore {
+    client: DynamoClient,
+    table_name: String,
+}
+
+impl DynamoDBSessionStore {
+    pub fn new(client: DynamoClient, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+
+    /// Create DynamoDBSessionStore using CDK-provided table name from environment variable
+    pub fn from_env(client: DynamoClient) -> Result<Self, AuthError> {
+        // Use exact table name provided by CDK
+        let table_name = std::env::var("SESSION_TABLE_NAME")
+            .map_err(|e| {
+                tracing::error!("SESSION_TABLE_NAME environment variable not set: {:?}", e);
+                AuthError::InternalError("SESSION_TABLE_NAME not set".to_string())
+            })?;
+
+        tracing::info!("DynamoDBSessionStore initialized with table: {}", table_name);
+        Ok(Self::new(client, table_name))
+    }
+
+    fn parse_session_from_item(item: &HashMap<String, AttributeValue>) -> AuthResult<Session> {
+        let string = |name: &str| item.get(name).and_then(|v| v.as_s().ok()).cloned();
+        let required_string = |name: &str| {
+            string(name).ok_or_else(|| AuthError::InternalError(format!("Missing {}", name)))
+        };
+        let required_number = |name: &str| {
+            item.get(name)
+                .and_then(|v| v.as_n().ok())
+                .and_then(|n| n.parse::<i64>().ok())
+                .ok_or_else(|| AuthError::InternalError(format!("Missing {}", name)))
+        };
+
+        Ok(Session {
+            session_id: required_string("session_id")?,
+            user_id: required_string("user_id")?,
+            email: required_string("email")?,
+            user_status: required_string("user_status")?,
+            given_name: string("given_name"),
+            family_name: string("family_name"),
+            created_at: required_number("created_at")?,
+            last_accessed: required_number("last_accessed")?,
+            expires_at: required_number("expires_at")?,
+            ip_address: string("ip_address"),
+            user_agent: string("user_agent"),
+        })
+    }
+}
+
+#[async_trait]
+impl SessionStore for DynamoDBSessionStore {
+    /// Store a new session, refusing to overwrite an existing session ID
+    async fn create_session(&self, session: &Session) -> AuthResult<()> {
+        let mut item = HashMap::new();
+        item.insert("session_id".to_string(), AttributeValue::S(session.session_id.clone()));
+        item.insert("user_id".to_string(), AttributeValue::S(session.user_id.clone()));
+        item.insert("email".to_string(), AttributeValue::S(session.email.clone()));
+        item.insert("user_status".to_string(), AttributeValue::S(session.user_status.clone()));
+        item.insert("created_at".to_string(), AttributeValue::N(session.created_at.to_string()));
+        item.insert("last_accessed".to_string(), AttributeValue::N(session.last_accessed.to_string()));
+        item.insert("expires_at".to_string(), AttributeValue::N(session.expires_at.to_string()));
+        let optional = [
+            ("given_name", &session.given_name),
+            ("family_name", &session.family_name),
+            ("ip_address", &session.ip_address),
+            ("user_agent", &session.user_agent),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                item.insert(name.to_string(), AttributeValue::S(value.clone()));
+            }
+        }
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(session_id)")
+            .send()
+            .await
+            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("session_id", AttributeValue::S(session_id.to_string()))
+            .send()
+            .await
+            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+
+        result
+            .item
+            .as_ref()
+            .map(Self::parse_session_from_item)
+            .transpose()
+    }
+
+    /// Slide the session expiry, unless the session was deleted in the meantime
+    async fn extend_session(&self, session_id: &str, last_accessed: i64, expires_at: i64) -> AuthResult<bool> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("session_id", AttributeValue::S(session_id.to_string()))
+            .update_expression("SET last_accessed = :last_accessed, expires_at = :expires_at")
+            .condition_expression("attribute_exists(session_id)")
+            .expression_attribute_values(":last_accessed", AttributeValue::N(last_accessed.to_string()))
+            .expression_attribute_values(":expires_at", AttributeValue::N(expires_at.to_string()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception()) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(AuthError::DynamoDBError(e.to_string())),
+        }
+    }
+
+    async fn delete_session(&self, session_id: &str) -> AuthResult<bool> {
+        let result = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("session_id", AttributeValue::S(session_id.to_string()))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+
+        Ok(result.attributes.is_some_and(|old| !old.is_empty()))
+    }
+
+    /// Find all sessions for a user via the user-id-index GSI
+    async fn get_sessions_for_user(&self, user_id: &str) -> AuthResult<Vec<Session>> {
+        let mut sessions = Vec::new();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let result = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(USER_ID_INDEX)
+                .key_condition_expression("user_id = :user_id")
+                .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .map_err(|e| AuthError::DynamoDBError(e.to_string()))?;
+
+            for item in result.items() {
+                sessions.push(Self::parse_session_from_item(item)?);
+            }
+
+            match result.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        Ok(sessions)
+    }
+}
+
+/// Creates, validates, slides and revokes user sessions with the same semantics as the webapp
+/// (see docs/session-management.md)
+#[derive(Clone)]
+pub struct SessionService {
+    store: Arc<dyn SessionStore>,
+    policy: SessionPolicy,
+}
+
+impl SessionService {
+    pub fn new(store: Arc<dyn SessionStore>, policy: SessionPolicy) -> Self {
+        Self { store, policy }
+    }
+
+    /// Create SessionService backed by the CDK-provided sessions table
+    pub fn from_env(client: DynamoClient) -> Result<Self, AuthError> {
+        let store = DynamoDBSessionStore::from_env(client)?;
+        let policy = SessionPolicy::from_env()?;
+        Ok(Self::new(Arc::new(store), policy))
+    }
+
+    pub fn policy(&self) -> &SessionPolicy {
+        &self.policy
+    }
+
+    /// Start a new session for a user, expiring after the configured duration of inactivity
+    pub async fn create_session(
+        &self,
+        user: &UserProfile,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> AuthResult<Session> {
+        let now = current_timestamp();
+        let session = Session {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            user_id: user.user_id.clone(),
+            email: user.email.to_string(),
+            user_status: user.status.as_str().to_string(),
+            given_name: None,
+            family_name: None,
+            created_at: now,
+            last_accessed: now,
+            expires_at: now + self.policy.duration_seconds,
+            ip_address,
+            user_agent,
+        };
+
+        self.store.create_session(&session).await?;
+        tracing::info!("Created session for user: {}", session.user_id);
+        Ok(session)
+    }
+
+    /// Look up a session, deleting it if expired and sliding its expiry if it hasn't been
+    /// extended within the update threshold. Returns None for unknown or expired sessions.
+    pub async fn validate_session(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        let Some(mut session) = self.store.get_session(session_id).await? else {
+            return Ok(None);
+        };
+
+        let now = current_timestamp();
+        if session.is_expired(now) {
+            tracing::info!("Session expired for user: {}", session.user_id);
+            self.store.delete_session(session_id).await?;
+            return Ok(None);
+        }
+
+        // Only write to DynamoDB once the update threshold has passed
+        if now - session.last_accessed > self.policy.update_threshold_seconds {
+            let expires_at = now + self.policy.duration_seconds;
+            if !self.store.extend_session(session_id, now, expires_at).await? {
+                tracing::info!("Session revoked during validation: {}", session_id);
+                return Ok(None);
+            }
+            session.last_accessed = now;
+            session.expires_at = expires_at;
+        }
+
+        Ok(Some(session))
+    }
+
+    /// End a single session immediately, returning false if it didn't exist
+    pub async fn revoke_session(&self, session_id: &str) -> AuthResult<bool> {
+        self.store.delete_session(session_id).await
+    }
+
+    /// End every session belonging to a user, returning how many were revoked
+    pub async fn revoke_user_sessions(&self, user_id: &str) -> AuthResult<usize> {
+        let mut revoked = 0;
+        for session in self.store.get_sessions_for_user(user_id).await? {
+            if self.store.delete_session(&session.session_id).await? {
+                revoked += 1;
+            }
+        }
+
+        tracing::info!("Revoked {} sessions for user: {}", revoked, user_id);
+        Ok(revoked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EmailAddress, InMemorySessionStore, UserStatus, SESSION_UPDATE_THRESHOLD_SECONDS};
+    use chrono::Utc;
+
+    fn user(user_id: &str) -> UserProfile {
+        UserProfile {
+            user_id: user_id.to_string(),
+            email: EmailAddress::parse(&format!("{}@example.com", user_id)).unwrap(),
+            status: UserStatus::Active,
+            full_name: None,
+            content_description: None,
+            content_link: None,
+            stripe_account_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            reviewed_by: None,
+            reviewed_at: None,
+            rejection_reason: None,
+            welcome_pending: false,
+            review_email_pending: false,
+        }
+    }
+
+    fn service() -> (Arc<InMemorySessionStore>, SessionService) {
+        let store = Arc::new(InMemorySessionStore::new());
+        let service = SessionService::new(store.clone(), SessionPolicy::default());
+        (store, service)
+    }
+
+    /// Seed a session last extended `idle_seconds` ago with the default duration
+    fn seed(store: &InMemorySessionStore, session_id: &str, user_id: &str, idle_seconds: i64) -> Session {
+        let last_accessed = current_timestamp() - idle_seconds;
+        let session = Session {
+            session_id: session_id.to_string(),
+            user_id: user_id.to_string(),
+            email: format!("{}@example.com", user_id),
+            user_status: "ACTIVE".to_string(),
+            given_name: None,
+            family_name: None,
+            created_at: last_accessed,
+            last_accessed,
+            expires_at: last_accessed + SessionPolicy::default().duration_seconds,
+            ip_address: None,
+            user_agent: None,
+        };
+        store.insert_session(session.clone());
+        session
+    }
+
+    #[tokio::test]
+    async fn test_create_session() {
+        let (store, service) = service();
+
+        let session = service
+            .create_session(&user("user-1"), Some("203.0.113.1".to_string()), None)
+            .await
+            .unwrap();
+
+        assert_eq!(session.user_status, "ACTIVE");
+        assert_eq!(session.expires_at - session.created_at, 20 * 60);
+        assert_eq!(session.last_accessed, session.created_at);
+        assert_eq!(store.get_session(&session.session_id).await.unwrap(), Some(session));
+    }
+
+    #[tokio::test]
+    async fn test_validate_within_threshold_does_not_write() {
+        let (store, service) = service();
+        let seeded = seed(&store, "s1", "user-1", 60);
+
+        let session = service.validate_session("s1").await.unwrap().unwrap();
+        assert_eq!(session, seeded);
+        assert!(service.validate_session("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_validate_slides_expiry_after_threshold() {
+        let (store, service) = service();
+        let seeded = seed(&store, "s1", "user-1", SESSION_UPDATE_THRESHOLD_SECONDS + 1);
+
+        let session = service.validate_session("s1").await.unwrap().unwrap();
+        assert!(session.last_accessed > seeded.last_accessed);
+        assert_eq!(session.expires_at, session.last_accessed + 20 * 60);
+        assert_eq!(store.get_session("s1").await.unwrap(), Some(session));
+    }
+
+    #[tokio::test]
+    async fn test_validate_deletes_expired_session() {
+        let (store, service) = service();
+        seed(&store, "s1", "user-1", 20 * 60 + 1);
+
+        assert!(service.validate_session("s1").await.unwrap().is_none());
+        assert!(store.get_session("s1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_sessions() {
+        let (store, service) = service();
+        seed(&store, "s1", "user-1", 0);
+        seed(&store, "s2", "user-1", 0);
+        seed(&store, "s3", "user-2", 0);
+
+        assert!(service.revoke_session("s1").await.unwrap());
+        assert!(!service.revoke_session("s1").await.unwrap());
+        assert!(service.validate_session("s1").await.unwrap().is_none());
+
+        assert_eq!(service.revoke_user_sessions("user-1").await.unwrap(), 1);
+        assert!(store.get_session("s2").await.unwrap().is_none());
+        assert!(store.get_session("s3").await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,206 @@
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoClient};
+use std::sync::Arc;
+
+use crate::{
+    sub_addressing_from_env, AuthError, AuthResult, EmailAddress, SignupLists, SignupListStore,
+    SignupPolicy, SignupPolicyDocument, SignupRejection, SubAddressing,
+};
+
+/// Sign-up lists kept in DynamoDB: partition key `list` (`allow` or `deny`), sort key `entry`
+pub struct DynamoDBSignupListStore {
+    client: DynamoClient,
+    table_name: String,
+}
+
+impl DynamoDBSignupListStore {
+    pub fn new(client: DynamoClient, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+
+    /// Every entry on one list
+    async fn query_list(&self, list: &str) -> AuthResult<Vec<String>> {
+        let mut entries = Vec::new();
+        let mut exclusive_start_key = None;
+        loop {
+            let result = self.client
+                .query()
+                .table_name(&self.table_name)
+                .key_condition_expression("#list = :list")
+                .expression_attribute_names("#list", "list")
+                .expression_attribute_values(":list", AttributeValue::S(list.to_string()))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Sign-up list query failed: {}", e);
+                    AuthError::DynamoDBError(format!("Sign-up list query failed: {}", e))
+                })?;
+
+            entries.extend(
+                result
+                    .items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|mut item| item.remove("entry"))
+                    .filter_map(|entry| entry.as_s().ok().cloned()),
+            );
+            exclusive_start_key = result.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                return Ok(entries);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SignupListStore for DynamoDBSignupListStore {
+    async fn get_signup_lists(&self) -> AuthResult<SignupLists> {
+        Ok(SignupLists {
+            allow: self.query_list("allow").await?,
+            deny: self.query_list("deny").await?,
+        })
+    }
+}
+
+/// Decides whether an email address may sign up, from the bundled policy plus any lists
+/// managed in the sign-up policy table
+pub struct SignupPolicyService {
+    policy: SignupPolicy,
+    lists: Option<Arc<dyn SignupListStore>>,
+    sub_addressing: SubAddressing,
+}
+
+impl SignupPolicyService {
+    pub fn new(
+        policy: SignupPolicy,
+        lists: Option<Arc<dyn SignupListStore>>,
+        sub_addressing: SubAddressing,
+    ) -> Self {
+        Self {
+            policy,
+            lists,
+            sub_addressing,
+        }
+    }
+
+    /// Create SignupPolicyService from a bundled policy document, resolved for the ENVIRONMENT
+    /// set by CDK. The table is consulted only when SIGNUP_POLICY_TABLE_NAME is set.
+    pub fn from_env(client: DynamoClient, document: &str) -> Result<Self, AuthError> {
+        let environment = std::env::var("ENVIRONMENT")
+            .map_err(|_| AuthError::InternalError("ENVIRONMENT not set".to_string()))?;
+        let policy = SignupPolicyDocument::parse(document)?.for_environment(&environment)?;
+
+        let lists = std::env::var("SIGNUP_POLICY_TABLE_NAME").ok().map(|table_name| {
+            tracing::info!("Sign-up lists loaded from table: {}", table_name);
+            Arc::new(DynamoDBSignupListStore::new(client, table_name)) as Arc<dyn SignupListStore>
+        });
+
+        Ok(Self::new(policy, lists, sub_addressing_from_env()?))
+    }
+
+    /// Check a sign-up against the policy, returning the canonical address if it may go ahead.
+    /// Refusals are SignupRejected with the reason to show the user.
+    pub async fn check_signup(&self, raw_email: &str) -> AuthResult<EmailAddress> {
+        let email = EmailAddress::parse_with(raw_email, self.sub_addressing)
+            .map_err(|_| AuthError::SignupRejected(SignupRejection::InvalidEmail))?;
+
+        self.current_policy()
+            .await?
+            .check(&email)
+            .map_err(AuthError::SignupRejected)?;
+        Ok(email)
+    }
+
+    /// The bundled policy with the table's entries added. An unreadable table is an error rather
+    /// than a fallback to the bundled policy, which would let through everything the table denies.
+    async fn current_policy(&self) -> AuthResult<SignupPolicy> {
+        let mut policy = self.policy.clone();
+        let Some(store) = &self.lists else {
+            return Ok(policy);
+        };
+
+        let lists = store.get_signup_lists().await.map_err(|e| {
+            tracing::error!("Failed to load sign-up lists: {}", e);
+            e
+        })?;
+        // One mistyped entry in the table shouldn't take the rest of the list with it
+        for entry in &lists.allow {
+            if let Err(e) = policy.allow(entry) {
+                tracing::warn!("Skipping sign-up allow entry: {}", e);
+            }
+        }
+        for entry in &lists.deny {
+            if let Err(e) = policy.deny(entry) {
+                tracing::warn!("Skipping sign-up deny entry: {}", e);
+            }
+        }
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySignupListStore, SignupMode};
+
+    fn rejection(result: AuthResult<EmailAddress>) -> Option<SignupRejection> {
+        match result {
+            Err(AuthError::SignupRejected(rejection)) => Some(rejection),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_signup_returns_canonical_address() {
+        let mut policy = SignupPolicy::new(SignupMode::Open);
+        policy.deny("mailinator.com").unwrap();
+        let service = SignupPolicyService::new(policy, None, SubAddressing::Strip);
+
+        let email = service.check_signup("Ada+News@Example.com").await.unwrap();
+        assert_eq!(email, "ada@example.com");
+
+        assert_eq!(
+            rejection(service.check_signup("spam@mailinator.com").await),
+            Some(SignupRejection::EmailNotAllowed)
+        );
+        assert_eq!(
+            rejection(service.check_signup("not-an-email").await),
+            Some(SignupRejection::InvalidEmail)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_signup_merges_table_lists() {
+        let store = Arc::new(InMemorySignupListStore::new(SignupLists {
+            allow: vec!["invited@example.com".to_string(), "not an entry".to_string()],
+            deny: vec!["yopmail.com".to_string()],
+        }));
+        let service = SignupPolicyService::new(
+            SignupPolicy::new(SignupMode::Allowlist),
+            Some(store.clone()),
+            SubAddressing::Preserve,
+        );
+
+        // A bad entry is skipped, not fatal
+        assert!(service.check_signup("invited@example.com").await.is_ok());
+        assert_eq!(
+            rejection(service.check_signup("uninvited@example.com").await),
+            Some(SignupRejection::InviteOnly)
+        );
+        assert_eq!(
+            rejection(service.check_signup("a@yopmail.com").await),
+            Some(SignupRejection::EmailNotAllowed)
+        );
+
+        // Without the table its deny entries can't be enforced, so nothing gets through
+        store.set_unavailable(true);
+        for address in ["invited@example.com", "a@yopmail.com"] {
+            assert!(matches!(
+                service.check_signup(address).await,
+                Err(AuthError::DynamoDBError(_))
+            ));
+        }
+    }
+}
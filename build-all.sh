@@ -31,21 +31,33 @@ cd notifications/lambda
 cd ../..
 
 echo ""
-echo "🏗️  Step 3: Deploying Authentication Infrastructure..."
+echo "🦀 Step 3: Building Payments Lambda functions..."
+./scripts/local/build-payments-lambdas.sh
+
+echo ""
+echo "🏗️  Step 4: Deploying Authentication Infrastructure..."
 cd authentication/cdk
 ./deploy.sh
 cd ../..
 
 echo ""
-echo "🏗️  Step 4: Deploying Notifications Infrastructure..."
+echo "🏗️  Step 5: Deploying Notifications Infrastructure..."
 cd notifications/cdk
 ./deploy.sh
 cd ../..
 
+echo ""
+echo "🏗️  Step 6: Deploying Payments Infrastructure..."
+cd payments/cdk
+pnpm install
+pnpm run deploy
+cd ../..
+
 echo ""
 echo "🎉 All services built and deployed successfully!"
 echo ""
 echo "📋 Next steps:"
 echo "1. Update webapp/.env with new Cognito configuration"
 echo "2. Verify SES domain for email sending"
-echo "3. Test the authentication and notification flows"
\ No newline at end of file
+echo "3. Register the StripeWebhookUrl output as a Connect webhook endpoint in Stripe"
+echo "4. Test the authentication, notification and payment flows"
\ No newline at end of file
@@ -18,16 +18,18 @@ interface NotificationStackProps extends cdk.StackProps {
  * using templated emails and asynchronous processing via SQS queues.
  * 
  * AWS Services Included:
- * - Amazon SES: Email delivery service with pre-defined templates (5 templates)
+ * - Amazon SES: Email delivery service with pre-defined templates (8 templates)
  * - Amazon SQS: Message queuing for reliable email processing (2 queues)
  * - AWS Lambda: Email processor for handling queued email requests (1 function)
  * - AWS IAM: Roles and policies for secure service interactions
  * 
  * Email Templates:
  * - OTP Email: One-time password verification codes
+ * - Magic Link Email: One-click sign-in links
  * - Welcome Email: New user onboarding messages
  * - Complete Registration (User Info): Profile completion reminders
  * - Complete Registration (Stripe): Payment setup reminders
+ * - Account Approved / Rejected: Creator review decisions
  * - Newsletter: General communication and updates
  * 
  * Key Features:
@@ -39,9 +41,12 @@ interface NotificationStackProps extends cdk.StackProps {
  */
 export class NotificationStack extends cdk.Stack {
   public otpTemplate: ses.CfnTemplate;
+  public magicLinkTemplate: ses.CfnTemplate;
   public welcomeTemplate: ses.CfnTemplate;
   public completeRegistrationUserInfoTemplate: ses.CfnTemplate;
   public completeRegistrationStripeTemplate: ses.CfnTemplate;
+  public accountApprovedTemplate: ses.CfnTemplate;
+  public accountRejectedTemplate: ses.CfnTemplate;
   public newsletterTemplate: ses.CfnTemplate;
   public emailQueue: sqs.Queue;
   public emailProcessor: lambda.Function;
@@ -106,6 +111,37 @@ export class NotificationStack extends cdk.Stack {
       cdk.Tags.of(this.otpTemplate).add(key, value);
     });
 
+    // Magic Link Email Template
+    this.magicLinkTemplate = new ses.CfnTemplate(this, 'MagicLinkTemplate', {
+      template: {
+        templateName: this.resourceNames.sesTemplate('magic-link'),
+        subjectPart: 'Your sign-in link',
+        htmlPart: `
+          <html>
+            <body>
+              <h2>Sign in to Appre</h2>
+              <p><a href="{{magicLinkUrl}}">Click here to sign in</a></p>
+              <p>This link will expire in {{expiresInMinutes}} minutes and can only be used once.</p>
+              <p>If you didn't request this link, please ignore this email.</p>
+            </body>
+          </html>
+        `,
+        textPart: `
+          Sign in to Appre: {{magicLinkUrl}}
+          
+          This link will expire in {{expiresInMinutes}} minutes and can only be used once.
+          
+          If you didn't request this link, please ignore this email.
+        `,
+      },
+    });
+
+    // Apply tags to magic link template
+    const magicLinkTags = this.tagBuilder.getSesTags('magic-link');
+    Object.entries(magicLinkTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.magicLinkTemplate).add(key, value);
+    });
+
     // Welcome Email Template
     this.welcomeTemplate = new ses.CfnTemplate(this, 'WelcomeTemplate', {
       template: {
@@ -214,6 +250,75 @@ export class NotificationStack extends cdk.Stack {
       cdk.Tags.of(this.completeRegistrationStripeTemplate).add(key, value);
     });
 
+    // Account Approved Template
+    this.accountApprovedTemplate = new ses.CfnTemplate(this, 'AccountApprovedTemplate', {
+      template: {
+        templateName: this.resourceNames.sesTemplate('account-approved'),
+        subjectPart: 'Your Appre account is approved',
+        htmlPart: `
+          <html>
+            <body>
+              <h2>You're approved, {{firstName}}!</h2>
+              <p>Our team has reviewed your profile and your account is now active.</p>
+              <p>You can start accepting payments from your audience right away.</p>
+              <p><a href="{{dashboardUrl}}">Go to Dashboard</a></p>
+            </body>
+          </html>
+        `,
+        textPart: `
+          You're approved, {{firstName}}!
+          
+          Our team has reviewed your profile and your account is now active.
+          
+          You can start accepting payments from your audience right away.
+          
+          Dashboard: {{dashboardUrl}}
+        `,
+      },
+    });
+
+    // Apply tags to account approved template
+    const accountApprovedTags = this.tagBuilder.getSesTags('account-approved');
+    Object.entries(accountApprovedTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.accountApprovedTemplate).add(key, value);
+    });
+
+    // Account Rejected Template
+    this.accountRejectedTemplate = new ses.CfnTemplate(this, 'AccountRejectedTemplate', {
+      template: {
+        templateName: this.resourceNames.sesTemplate('account-rejected'),
+        subjectPart: 'Update on your Appre application',
+        htmlPart: `
+          <html>
+            <body>
+              <h2>Hi {{firstName}},</h2>
+              <p>Our team has reviewed your profile and we can't approve your account yet.</p>
+              <p><strong>Reason:</strong> {{rejectionReason}}</p>
+              <p>You can update your profile and submit it for review again.</p>
+              <p><a href="{{profileUrl}}">Update Profile</a></p>
+            </body>
+          </html>
+        `,
+        textPart: `
+          Hi {{firstName}},
+          
+          Our team has reviewed your profile and we can't approve your account yet.
+          
+          Reason: {{rejectionReason}}
+          
+          You can update your profile and submit it for review again.
+          
+          Update profile: {{profileUrl}}
+        `,
+      },
+    });
+
+    // Apply tags to account rejected template
+    const accountRejectedTags = this.tagBuilder.getSesTags('account-rejected');
+    Object.entries(accountRejectedTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.accountRejectedTemplate).add(key, value);
+    });
+
     // Newsletter Template
     this.newsletterTemplate = new ses.CfnTemplate(this, 'NewsletterTemplate', {
       template: {
@@ -361,9 +466,12 @@ export class NotificationStack extends cdk.Stack {
         ENVIRONMENT: this.config.environment,
         FROM_EMAIL: 'noreply@appreciata.com',
         OTP_TEMPLATE_NAME: this.otpTemplate.ref,
+        MAGIC_LINK_TEMPLATE_NAME: this.magicLinkTemplate.ref,
         WELCOME_TEMPLATE_NAME: this.welcomeTemplate.ref,
         COMPLETE_REGISTRATION_USER_INFO_TEMPLATE_NAME: this.completeRegistrationUserInfoTemplate.ref,
         COMPLETE_REGISTRATION_STRIPE_TEMPLATE_NAME: this.completeRegistrationStripeTemplate.ref,
+        ACCOUNT_APPROVED_TEMPLATE_NAME: this.accountApprovedTemplate.ref,
+        ACCOUNT_REJECTED_TEMPLATE_NAME: this.accountRejectedTemplate.ref,
         NEWSLETTER_TEMPLATE_NAME: this.newsletterTemplate.ref,
         DEPLOYMENT_TIMESTAMP: Date.now().toString(),
       },
@@ -376,9 +484,11 @@ export class NotificationStack extends cdk.Stack {
       cdk.Tags.of(this.emailProcessor).add(key, value);
     });
 
-    // Connect SQS to Lambda
+    // Connect SQS to Lambda. The processor reports the messages it failed to send, so only those
+    // are redelivered (and dead-lettered after maxReceiveCount) while the rest are deleted
     this.emailProcessor.addEventSource(new lambdaEventSources.SqsEventSource(this.emailQueue, {
       batchSize: 10,
+      reportBatchItemFailures: true,
     }));
   }
 
@@ -390,6 +500,12 @@ export class NotificationStack extends cdk.Stack {
       exportName: `${this.config.appName}-OTPTemplateId-${this.config.environment}`,
     });
 
+    new cdk.CfnOutput(this, 'MagicLinkTemplateId', {
+      value: this.magicLinkTemplate.ref,
+      description: 'SES Template ID for magic link emails',
+      exportName: `${this.config.appName}-MagicLinkTemplateId-${this.config.environment}`,
+    });
+
     new cdk.CfnOutput(this, 'WelcomeTemplateId', {
       value: this.welcomeTemplate.ref,
       description: 'SES Template ID for welcome emails',
@@ -408,6 +524,18 @@ export class NotificationStack extends cdk.Stack {
       exportName: `${this.config.appName}-CompleteRegistrationStripeTemplateId-${this.config.environment}`,
     });
 
+    new cdk.CfnOutput(this, 'AccountApprovedTemplateId', {
+      value: this.accountApprovedTemplate.ref,
+      description: 'SES Template ID for account approved emails',
+      exportName: `${this.config.appName}-AccountApprovedTemplateId-${this.config.environment}`,
+    });
+
+    new cdk.CfnOutput(this, 'AccountRejectedTemplateId', {
+      value: this.accountRejectedTemplate.ref,
+      description: 'SES Template ID for account rejected emails',
+      exportName: `${this.config.appName}-AccountRejectedTemplateId-${this.config.environment}`,
+    });
+
     new cdk.CfnOutput(this, 'NewsletterTemplateId', {
       value: this.newsletterTemplate.ref,
       description: 'SES Template ID for newsletter emails',
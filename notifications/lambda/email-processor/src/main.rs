@@ -1,4 +1,4 @@
-use aws_lambda_events::event::sqs::SqsEvent;
+use aws_lambda_events::event::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent};
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
 use notifications_shared::{EmailRequest, EmailService, NotificationError, RuntimeConfig};
 use std::env;
@@ -15,74 +15,96 @@ async fn main() -> Result<(), Error> {
 
     info!("Starting email processor Lambda");
 
-    run(service_fn(function_handler)).await
+    // Built once here so warm invocations reuse the SES client and its connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
 }
 
-async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<(), Error> {
-    let (event, _context) = event.into_parts();
-    
-    info!("Processing {} SQS messages", event.records.len());
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    email_service: EmailService,
+}
 
-    // Initialize AWS clients
-    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
-    let ses_client = aws_sdk_ses::Client::new(&config);
+impl AppContext {
+    async fn from_env() -> Result<Self, Error> {
+        // Initialize AWS clients
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let ses_client = aws_sdk_ses::Client::new(&config);
 
-    // Get configuration from environment
-    let from_email = env::var("FROM_EMAIL")
-        .unwrap_or_else(|_| "noreply@appreciata.com".to_string());
+        // Get configuration from environment
+        let from_email = env::var("FROM_EMAIL")
+            .unwrap_or_else(|_| "noreply@appreciata.com".to_string());
 
-    info!("Lambda configuration - FROM_EMAIL: {}", from_email);
+        info!("Lambda configuration - FROM_EMAIL: {}", from_email);
 
-    // Initialize runtime configuration for resource name resolution
-    let runtime_config = match RuntimeConfig::from_env() {
-        Ok(config) => {
-            info!("Runtime configuration loaded - APP_NAME: {}, ENVIRONMENT: {}", 
-                  config.app_name, config.environment);
-            
-            // Log example resource names that could be constructed at runtime
-            info!("Example runtime resource names:");
-            info!("  SES template 'otp': {}", config.ses_template("otp"));
-            info!("  SES template 'welcome': {}", config.ses_template("welcome"));
-            info!("  SQS queue 'email-queue': {}", config.sqs_queue("email-queue"));
-            
-            Some(config)
-        }
-        Err(e) => {
-            warn!("Failed to load runtime configuration: {}. Using CDK-provided template names only.", e);
-            None
-        }
-    };
+        // Initialize runtime configuration for resource name resolution
+        let runtime_config = match RuntimeConfig::from_env() {
+            Ok(config) => {
+                info!("Runtime configuration loaded - APP_NAME: {}, ENVIRONMENT: {}", 
+                      config.app_name, config.environment);
+                
+                // Log example resource names that could be constructed at runtime
+                info!("Example runtime resource names:");
+                info!("  SES template 'otp': {}", config.ses_template("otp"));
+                info!("  SES template 'welcome': {}", config.ses_template("welcome"));
+                info!("  SQS queue 'email-queue': {}", config.sqs_queue("email-queue"));
+                
+                Some(config)
+            }
+            Err(e) => {
+                warn!("Failed to load runtime configuration: {}. Using CDK-provided template names only.", e);
+                None
+            }
+        };
 
-    // Initialize email service using CDK-provided environment variables (preferred method)
-    let email_service = match EmailService::from_env(ses_client.clone(), from_email.clone()) {
-        Ok(service) => {
-            info!("EmailService initialized using CDK-provided template names");
-            service
-        }
-        Err(e) => {
-            error!("Failed to initialize EmailService from CDK environment: {}", e);
-            
-            // Fallback: try runtime configuration approach
-            if let Some(config) = runtime_config {
-                warn!("Attempting fallback initialization using runtime configuration");
-                let service = EmailService::from_runtime_config(ses_client, from_email, config);
-                info!("EmailService initialized using runtime configuration fallback");
+        // Initialize email service using CDK-provided environment variables (preferred method)
+        let email_service = match EmailService::from_env(ses_client.clone(), from_email.clone()) {
+            Ok(service) => {
+                info!("EmailService initialized using CDK-provided template names");
                 service
-            } else {
-                error!("No fallback configuration available");
-                return Err(format!("Configuration error: {}", e).into());
             }
-        }
-    };
+            Err(e) => {
+                error!("Failed to initialize EmailService from CDK environment: {}", e);
+                
+                // Fallback: try runtime configuration approach
+                if let Some(config) = runtime_config {
+                    warn!("Attempting fallback initialization using runtime configuration");
+                    let service = EmailService::from_runtime_config(ses_client, from_email, config);
+                    info!("EmailService initialized using runtime configuration fallback");
+                    service
+                } else {
+                    error!("No fallback configuration available");
+                    return Err(format!("Configuration error: {}", e).into());
+                }
+            }
+        };
+
+        Ok(Self { email_service })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<SqsEvent>,
+) -> Result<SqsBatchResponse, Error> {
+    let (event, _context) = event.into_parts();
+    Ok(process_batch(&context.email_service, &event).await)
+}
+
+/// Send every email in the batch, reporting the messages that failed so SQS redelivers them
+/// (and moves them to the dead letter queue after maxReceiveCount) instead of deleting them
+async fn process_batch(email_service: &EmailService, event: &SqsEvent) -> SqsBatchResponse {
+    info!("Processing {} SQS messages", event.records.len());
 
     // Process each SQS message
     let mut successful_count = 0;
-    let mut failed_count = 0;
+    let mut batch_item_failures = Vec::new();
 
     for (index, record) in event.records.iter().enumerate() {
         info!("Processing SQS record {} of {}", index + 1, event.records.len());
         
-        match process_email_record(&email_service, record.clone()).await {
+        match process_email_record(email_service, record.clone()).await {
             Ok(_) => {
                 successful_count += 1;
                 info!("Successfully processed record {}", index + 1);
@@ -112,7 +134,12 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<(), Error> {
                     }
                 }
                 
-                failed_count += 1;
+                match &record.message_id {
+                    Some(message_id) => batch_item_failures.push(BatchItemFailure {
+                        item_identifier: message_id.clone(),
+                    }),
+                    None => error!("Record {} has no message ID and can't be retried", index + 1),
+                }
                 // Continue processing other messages even if one fails
             }
         }
@@ -121,15 +148,15 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<(), Error> {
     info!(
         "Email processing completed - Success: {}, Failed: {}", 
         successful_count, 
-        failed_count
+        batch_item_failures.len()
     );
 
-    // If any messages failed, Lambda will retry them based on SQS configuration
-    if failed_count > 0 {
-        warn!("{} messages failed processing and will be retried", failed_count);
+    // Reported failures stay on the queue and are retried based on the SQS configuration
+    if !batch_item_failures.is_empty() {
+        warn!("{} messages failed processing and will be retried", batch_item_failures.len());
     }
 
-    Ok(())
+    SqsBatchResponse { batch_item_failures }
 }
 
 async fn process_email_record(
@@ -242,9 +269,62 @@ async fn process_email_record(
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use aws_lambda_events::event::sqs::SqsMessage;
     use notifications_shared::{EmailRequest, EmailPriority};
     use std::collections::HashMap;
 
+    /// An EmailService whose SES endpoint refuses connections, so every send fails transiently
+    fn unreachable_email_service() -> EmailService {
+        let ses_config = aws_sdk_ses::Config::builder()
+            .behavior_version(aws_sdk_ses::config::BehaviorVersion::latest())
+            .region(aws_sdk_ses::config::Region::new("eu-west-2"))
+            .credentials_provider(aws_sdk_ses::config::SharedCredentialsProvider::new(
+                aws_sdk_ses::config::Credentials::new("test", "test", None, None, "test")
+            ))
+            .endpoint_url("http://127.0.0.1:9")
+            .retry_config(aws_sdk_ses::config::retry::RetryConfig::disabled())
+            .build();
+
+        let mut template_names = HashMap::new();
+        template_names.insert("otp".to_string(), "appre-otp-test".to_string());
+        EmailService::new(
+            aws_sdk_ses::Client::from_conf(ses_config),
+            "test@example.com".to_string(),
+            template_names,
+        )
+    }
+
+    fn message(message_id: &str, body: &str) -> SqsMessage {
+        SqsMessage {
+            message_id: Some(message_id.to_string()),
+            body: Some(body.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_messages_are_not_acknowledged() {
+        let otp = EmailRequest::otp("test@example.com".to_string(), "123456".to_string());
+        let event = SqsEvent {
+            records: vec![
+                message("queued-otp", &serde_json::to_string(&otp).unwrap()),
+                message("malformed", "not json"),
+            ],
+        };
+
+        let response = process_batch(&unreachable_email_service(), &event).await;
+
+        // SES being unreachable is transient, so the OTP goes back on the queue to be retried;
+        // the malformed message is retried too and ends up in the dead letter queue
+        let failed: Vec<_> = response
+            .batch_item_failures
+            .iter()
+            .map(|failure| failure.item_identifier.as_str())
+            .collect();
+        assert_eq!(failed, ["queued-otp", "malformed"]);
+    }
+
     #[test]
     fn test_email_request_parsing() {
         let mut template_data = HashMap::new();
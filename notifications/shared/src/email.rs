@@ -0,0 +1,268 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{InvalidEmailAddress, NotificationError, NotificationResult};
+
+/// What to do with a `+tag` in the local part
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubAddressing {
+    /// `bob+news@example.com` is its own address
+    #[default]
+    Preserve,
+    /// `bob+news@example.com` is `bob@example.com`
+    Strip,
+}
+
+impl SubAddressing {
+    /// Read the policy from EMAIL_SUBADDRESS_POLICY (`preserve` or `strip`), preserving if unset
+    pub fn from_env() -> NotificationResult<Self> {
+        match std::env::var("EMAIL_SUBADDRESS_POLICY") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+impl FromStr for SubAddressing {
+    type Err = NotificationError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "preserve" => Ok(Self::Preserve),
+            "strip" => Ok(Self::Strip),
+            other => Err(NotificationError::ConfigurationError(format!(
+                "EMAIL_SUBADDRESS_POLICY must be 'preserve' or 'strip', got '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// A validated email address in canonical form, used as the identity of a user everywhere an
+/// email is a key.
+///
+/// Parsing follows RFC 5322's addr-spec (no display names or domain literals, at least two
+/// domain labels), lowercases the whole address and converts internationalised domains to
+/// their ASCII (punycode) form, so `Bob@Bücher.Example` and `bob@xn--bcher-kva.example` are the
+/// same address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EmailAddress(String);
+
+impl EmailAddress {
+    /// Parse and canonicalise an address, keeping any `+tag`
+    pub fn parse(input: &str) -> Result<Self, InvalidEmailAddress> {
+        Self::parse_with(input, SubAddressing::Preserve)
+    }
+
+    /// Parse and canonicalise an address under the given sub-addressing policy
+    pub fn parse_with(input: &str, sub_addressing: SubAddressing) -> Result<Self, InvalidEmailAddress> {
+        let invalid = |reason: String| InvalidEmailAddress {
+            address: input.to_string(),
+            reason,
+        };
+
+        let options = ::email_address::Options {
+            minimum_sub_domains: 2,
+            allow_domain_literal: false,
+            allow_display_text: false,
+        };
+        let parsed = ::email_address::EmailAddress::parse_with_options(input.trim(), options)
+            .map_err(|e| invalid(e.to_string()))?;
+
+        let domain = idna::domain_to_ascii(parsed.domain())
+            .map_err(|_| invalid("domain is not a valid internationalised domain name".to_string()))?;
+        let local_part = parsed.local_part().to_lowercase();
+
+        Ok(Self(format!("{}@{}", local_part, domain)).with_sub_addressing(sub_addressing))
+    }
+
+    /// An address read back from storage. Rows written before canonicalisation may hold an
+    /// address `parse` now rejects; it is lowercased as stored rather than failing the record.
+    pub fn from_stored(stored: &str) -> Self {
+        Self::parse(stored).unwrap_or_else(|e| {
+            tracing::warn!("Keeping stored email that no longer parses: {}", e);
+            Self(stored.trim().to_lowercase())
+        })
+    }
+
+    /// Apply a sub-addressing policy to an already canonical address
+    pub fn with_sub_addressing(self, sub_addressing: SubAddressing) -> Self {
+        if sub_addressing == SubAddressing::Preserve || self.local_part().starts_with('"') {
+            return self;
+        }
+        match self.local_part().split_once('+') {
+            Some((base, _)) if !base.is_empty() => Self(format!("{}@{}", base, self.domain())),
+            _ => self,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Everything before the last `@`
+    pub fn local_part(&self) -> &str {
+        self.split().0
+    }
+
+    /// The ASCII domain, e.g. for keying limits by provider
+    pub fn domain(&self) -> &str {
+        self.split().1
+    }
+
+    fn split(&self) -> (&str, &str) {
+        // Quoted local parts may contain '@', the domain never does. Only a stored address that
+        // predates parsing can lack one.
+        self.0.rsplit_once('@').unwrap_or((&self.0, ""))
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for EmailAddress {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for EmailAddress {
+    type Err = InvalidEmailAddress;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+impl From<EmailAddress> for String {
+    fn from(email: EmailAddress) -> Self {
+        email.0
+    }
+}
+
+impl PartialEq<str> for EmailAddress {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for EmailAddress {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Serialize for EmailAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// Deserializing re-parses, so an address arriving as input is validated and canonical. Stored
+/// records decode their emails with `from_stored` instead, so a legacy row is never rejected.
+/// The sub-addressing policy is applied where input enters the system, not here.
+impl<'de> Deserialize<'de> for EmailAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        Self::parse(&input).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> String {
+        EmailAddress::parse(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_case_is_folded() {
+        assert_eq!(parse("Bob@X.com"), "bob@x.com");
+        assert_eq!(EmailAddress::parse("Bob@X.com"), EmailAddress::parse("bob@x.com"));
+        assert_eq!(parse("  user@Example.COM "), "user@example.com");
+    }
+
+    #[test]
+    fn test_internationalised_domains_become_punycode() {
+        assert_eq!(parse("anna@Bücher.example"), "anna@xn--bcher-kva.example");
+        assert_eq!(
+            EmailAddress::parse("anna@bücher.example"),
+            EmailAddress::parse("anna@xn--bcher-kva.example")
+        );
+    }
+
+    #[test]
+    fn test_parts() {
+        let email = EmailAddress::parse("User.Name+tag@Mail.Example.com").unwrap();
+        assert_eq!(email.local_part(), "user.name+tag");
+        assert_eq!(email.domain(), "mail.example.com");
+
+        let quoted = EmailAddress::parse("\"a@b\"@example.com").unwrap();
+        assert_eq!(quoted.local_part(), "\"a@b\"");
+        assert_eq!(quoted.domain(), "example.com");
+    }
+
+    #[test]
+    fn test_sub_addressing_policy() {
+        let tagged = "bob+news@example.com";
+        assert_eq!(parse(tagged), "bob+news@example.com");
+        assert_eq!(
+            EmailAddress::parse_with(tagged, SubAddressing::Strip).unwrap(),
+            "bob@example.com"
+        );
+        // Nothing to strip back to, and quoted local parts are left alone
+        assert_eq!(
+            EmailAddress::parse_with("+news@example.com", SubAddressing::Strip).unwrap(),
+            "+news@example.com"
+        );
+        assert_eq!(
+            EmailAddress::parse_with("\"bob+news\"@example.com", SubAddressing::Strip).unwrap(),
+            "\"bob+news\"@example.com"
+        );
+
+        assert_eq!("strip".parse::<SubAddressing>().unwrap(), SubAddressing::Strip);
+        assert_eq!(" Preserve ".parse::<SubAddressing>().unwrap(), SubAddressing::Preserve);
+        assert!("drop".parse::<SubAddressing>().is_err());
+    }
+
+    #[test]
+    fn test_invalid_addresses() {
+        for input in [
+            "",
+            "invalid-email",
+            "@example.com",
+            "user@",
+            "user@localhost",
+            "user@.com",
+            "user@example..com",
+            "two@@example.com",
+            "Bob <bob@example.com>",
+            "bob@[127.0.0.1]",
+            "spaces in@example.com",
+        ] {
+            assert!(EmailAddress::parse(input).is_err(), "{:?} should be rejected", input);
+        }
+    }
+
+    #[test]
+    fn test_stored_addresses_are_read_leniently() {
+        assert_eq!(EmailAddress::from_stored("Bob@X.com"), "bob@x.com");
+        // Accepted before parsing was strict: a single-label domain
+        let legacy = EmailAddress::from_stored("First.Last@Intranet");
+        assert_eq!(legacy, "first.last@intranet");
+        assert_eq!(legacy.domain(), "intranet");
+        assert_eq!(EmailAddress::from_stored("no-at-sign.example").domain(), "");
+    }
+
+    #[test]
+    fn test_serde_round_trip_is_canonical() {
+        let email: EmailAddress = serde_json::from_str("\"Bob@X.com\"").unwrap();
+        assert_eq!(serde_json::to_string(&email).unwrap(), "\"bob@x.com\"");
+        assert!(serde_json::from_str::<EmailAddress>("\"nope\"").is_err());
+    }
+}
@@ -30,6 +30,20 @@ pub enum NotificationError {
     InternalError(String),
 }
 
+/// An address that failed `EmailAddress` parsing
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid email address '{address}': {reason}")]
+pub struct InvalidEmailAddress {
+    pub address: String,
+    pub reason: String,
+}
+
+impl From<InvalidEmailAddress> for NotificationError {
+    fn from(err: InvalidEmailAddress) -> Self {
+        NotificationError::InvalidRecipient(err.to_string())
+    }
+}
+
 impl From<aws_sdk_sqs::Error> for NotificationError {
     fn from(err: aws_sdk_sqs::Error) -> Self {
         NotificationError::SQSError(err.to_string())
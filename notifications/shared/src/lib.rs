@@ -2,8 +2,10 @@ pub mod models;
 pub mod services;
 pub mod errors;
 pub mod naming;
+pub mod email;
 
 pub use models::*;
 pub use services::*;
 pub use errors::*;
-pub use naming::*;
\ No newline at end of file
+pub use naming::*;
+pub use email::*;
\ No newline at end of file
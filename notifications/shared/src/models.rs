@@ -17,22 +17,17 @@ pub struct EmailRequest {
     pub from_address: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum EmailPriority {
     /// High priority emails (OTP, password reset, etc.)
     High,
     /// Normal priority emails (welcome, notifications, etc.)
+    #[default]
     Normal,
     /// Low priority emails (newsletters, marketing, etc.)
     Low,
 }
 
-impl Default for EmailPriority {
-    fn default() -> Self {
-        EmailPriority::Normal
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailResponse {
     /// Unique message ID from SES
@@ -41,6 +36,10 @@ pub struct EmailResponse {
     pub success: bool,
     /// Error message if sending failed
     pub error: Option<String>,
+    /// Whether a failure was transient (throttling, timeouts, SES unavailable), so the same
+    /// email is worth sending again later
+    #[serde(default)]
+    pub retryable: bool,
 }
 
 /// Predefined base template names for type safety
@@ -49,9 +48,12 @@ pub struct EmailTemplates;
 
 impl EmailTemplates {
     pub const OTP: &'static str = "otp";
+    pub const MAGIC_LINK: &'static str = "magic-link";
     pub const WELCOME: &'static str = "welcome";
     pub const COMPLETE_REGISTRATION_USER_INFO: &'static str = "complete-registration-user-info";
     pub const COMPLETE_REGISTRATION_STRIPE: &'static str = "complete-registration-stripe";
+    pub const ACCOUNT_APPROVED: &'static str = "account-approved";
+    pub const ACCOUNT_REJECTED: &'static str = "account-rejected";
     pub const NEWSLETTER: &'static str = "newsletter";
 }
 
@@ -72,6 +74,22 @@ impl EmailRequest {
         }
     }
 
+    /// Create a magic link sign-in email request
+    pub fn magic_link(recipient: String, magic_link_url: String, expires_in_minutes: i64) -> Self {
+        let mut template_data = HashMap::new();
+        template_data.insert("magicLinkUrl".to_string(), magic_link_url);
+        template_data.insert("expiresInMinutes".to_string(), expires_in_minutes.to_string());
+
+        Self {
+            template_name: EmailTemplates::MAGIC_LINK.to_string(),
+            recipient,
+            template_data,
+            priority: EmailPriority::High,
+            reply_to: None,
+            from_address: None,
+        }
+    }
+
     /// Create a welcome email request
     pub fn welcome(recipient: String, first_name: String, dashboard_url: String) -> Self {
         let mut template_data = HashMap::new();
@@ -132,6 +150,44 @@ impl EmailRequest {
         }
     }
 
+    /// Create an account approved email, sent when an admin approves a creator's review
+    pub fn account_approved(recipient: String, first_name: String, dashboard_url: String) -> Self {
+        let mut template_data = HashMap::new();
+        template_data.insert("firstName".to_string(), first_name);
+        template_data.insert("dashboardUrl".to_string(), dashboard_url);
+
+        Self {
+            template_name: EmailTemplates::ACCOUNT_APPROVED.to_string(),
+            recipient,
+            template_data,
+            priority: EmailPriority::Normal,
+            reply_to: None,
+            from_address: None,
+        }
+    }
+
+    /// Create an account rejected email, sent when an admin rejects a creator's review
+    pub fn account_rejected(
+        recipient: String,
+        first_name: String,
+        rejection_reason: String,
+        profile_url: String,
+    ) -> Self {
+        let mut template_data = HashMap::new();
+        template_data.insert("firstName".to_string(), first_name);
+        template_data.insert("rejectionReason".to_string(), rejection_reason);
+        template_data.insert("profileUrl".to_string(), profile_url);
+
+        Self {
+            template_name: EmailTemplates::ACCOUNT_REJECTED.to_string(),
+            recipient,
+            template_data,
+            priority: EmailPriority::Normal,
+            reply_to: None,
+            from_address: None,
+        }
+    }
+
     /// Create a newsletter email request
     pub fn newsletter(
         recipient: String,
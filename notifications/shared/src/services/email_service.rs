@@ -1,6 +1,6 @@
 use aws_sdk_ses::Client as SesClient;
 use aws_sdk_ses::types::{Destination, MessageTag};
-use crate::{EmailRequest, EmailResponse, NotificationError, NotificationResult, RuntimeConfig};
+use crate::{EmailAddress, EmailRequest, EmailResponse, NotificationError, NotificationResult, RuntimeConfig};
 
 /// Service for sending emails via SES using templates
 pub struct EmailService {
@@ -28,6 +28,9 @@ impl EmailService {
         if let Ok(otp_template) = std::env::var("OTP_TEMPLATE_NAME") {
             template_names.insert("otp".to_string(), otp_template);
         }
+        if let Ok(magic_link_template) = std::env::var("MAGIC_LINK_TEMPLATE_NAME") {
+            template_names.insert("magic-link".to_string(), magic_link_template);
+        }
         if let Ok(welcome_template) = std::env::var("WELCOME_TEMPLATE_NAME") {
             template_names.insert("welcome".to_string(), welcome_template);
         }
@@ -37,6 +40,12 @@ impl EmailService {
         if let Ok(stripe_template) = std::env::var("COMPLETE_REGISTRATION_STRIPE_TEMPLATE_NAME") {
             template_names.insert("complete-registration-stripe".to_string(), stripe_template);
         }
+        if let Ok(approved_template) = std::env::var("ACCOUNT_APPROVED_TEMPLATE_NAME") {
+            template_names.insert("account-approved".to_string(), approved_template);
+        }
+        if let Ok(rejected_template) = std::env::var("ACCOUNT_REJECTED_TEMPLATE_NAME") {
+            template_names.insert("account-rejected".to_string(), rejected_template);
+        }
         if let Ok(newsletter_template) = std::env::var("NEWSLETTER_TEMPLATE_NAME") {
             template_names.insert("newsletter".to_string(), newsletter_template);
         }
@@ -53,9 +62,12 @@ impl EmailService {
         
         // Construct template names using runtime configuration
         template_names.insert("otp".to_string(), runtime_config.ses_template("otp"));
+        template_names.insert("magic-link".to_string(), runtime_config.ses_template("magic-link"));
         template_names.insert("welcome".to_string(), runtime_config.ses_template("welcome"));
         template_names.insert("complete-registration-user-info".to_string(), runtime_config.ses_template("complete-registration-user-info"));
         template_names.insert("complete-registration-stripe".to_string(), runtime_config.ses_template("complete-registration-stripe"));
+        template_names.insert("account-approved".to_string(), runtime_config.ses_template("account-approved"));
+        template_names.insert("account-rejected".to_string(), runtime_config.ses_template("account-rejected"));
         template_names.insert("newsletter".to_string(), runtime_config.ses_template("newsletter"));
         
         Self::new(client, from_email, template_names)
@@ -64,12 +76,8 @@ impl EmailService {
     /// Send an email using SES templates
     pub async fn send_templated_email(&self, request: EmailRequest) -> NotificationResult<EmailResponse> {
         tracing::debug!("Starting send_templated_email for recipient: {}", request.recipient);
-        // Validate recipient email
-        if !self.is_valid_email(&request.recipient) {
-            return Err(NotificationError::InvalidRecipient(
-                format!("Invalid email address: {}", request.recipient)
-            ));
-        }
+        // Validate recipient email; SES needs the ASCII form of internationalised domains
+        let recipient = EmailAddress::parse(&request.recipient)?;
 
         // Get template name from CDK-provided environment variables
         let template_name = self.template_names.get(&request.template_name)
@@ -86,7 +94,7 @@ impl EmailService {
 
         // Build destination
         let destination = Destination::builder()
-            .to_addresses(&request.recipient)
+            .to_addresses(recipient.as_str())
             .build();
 
         // Determine from address
@@ -127,7 +135,7 @@ impl EmailService {
             .tags(
                 MessageTag::builder()
                     .name("Priority")
-                    .value(&format!("{:?}", request.priority))
+                    .value(format!("{:?}", request.priority))
                     .build()
                     .map_err(|e| NotificationError::SESError(e.to_string()))?
             );
@@ -162,6 +170,7 @@ impl EmailService {
                     message_id,
                     success: true,
                     error: None,
+                    retryable: false,
                 })
             }
             Err(err) => {
@@ -247,6 +256,7 @@ impl EmailService {
                     message_id: String::new(),
                     success: false,
                     error: Some(detailed_error),
+                    retryable: is_transient_ses_error(error_code),
                 })
             }
         }
@@ -264,17 +274,6 @@ impl EmailService {
         Ok(responses)
     }
 
-    /// Validate email address format (basic validation)
-    fn is_valid_email(&self, email: &str) -> bool {
-        email.contains('@') 
-            && email.contains('.') 
-            && email.len() > 5 
-            && !email.starts_with('@') 
-            && !email.ends_with('@')
-            && !email.starts_with('.')
-            && !email.ends_with('.')
-    }
-
     /// Get available SES templates (for debugging/validation)
     pub async fn list_templates(&self) -> NotificationResult<Vec<String>> {
         let result = self.client
@@ -318,10 +317,25 @@ impl EmailService {
     }
 }
 
+/// SES failures that say nothing about the email itself and may well succeed on a later try
+fn is_transient_ses_error(error_code: &str) -> bool {
+    matches!(
+        error_code,
+        "Throttling"
+            | "ThrottlingException"
+            | "ServiceUnavailable"
+            | "InternalFailure"
+            | "RequestTimeout"
+            | "TimeoutError"
+            | "ResponseError"
+            | "DispatchFailure"
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{EmailRequest, EmailPriority};
+    use crate::{EmailAddress, EmailRequest, EmailPriority};
     use std::collections::HashMap;
 
     fn create_test_email_service() -> EmailService {
@@ -350,16 +364,27 @@ mod tests {
 
     #[test]
     fn test_email_validation() {
-        let service = create_test_email_service();
-
-        assert!(service.is_valid_email("user@example.com"));
-        assert!(service.is_valid_email("test.user+tag@domain.co.uk"));
+        // Recipients are validated by EmailAddress before anything reaches SES
+        assert!(EmailAddress::parse("user@example.com").is_ok());
+        assert!(EmailAddress::parse("test.user+tag@domain.co.uk").is_ok());
         
-        assert!(!service.is_valid_email("invalid"));
-        assert!(!service.is_valid_email("@example.com"));
-        assert!(!service.is_valid_email("user@"));
-        assert!(!service.is_valid_email(".user@example.com"));
-        assert!(!service.is_valid_email("user@example."));
+        assert!(EmailAddress::parse("invalid").is_err());
+        assert!(EmailAddress::parse("@example.com").is_err());
+        assert!(EmailAddress::parse("user@").is_err());
+        assert!(EmailAddress::parse(".user@example.com").is_err());
+        assert!(EmailAddress::parse("user@example.").is_err());
+    }
+
+    #[test]
+    fn test_transient_ses_errors() {
+        assert!(is_transient_ses_error("Throttling"));
+        assert!(is_transient_ses_error("TimeoutError"));
+        assert!(is_transient_ses_error("DispatchFailure"));
+
+        // Retrying these would fail the same way
+        assert!(!is_transient_ses_error("MessageRejected"));
+        assert!(!is_transient_ses_error("TemplateDoesNotExist"));
+        assert!(!is_transient_ses_error("AccountSendingPausedException"));
     }
 
     #[test]
@@ -399,9 +424,12 @@ mod tests {
         
         // Verify that template constants are base names (no environment suffix)
         assert_eq!(EmailTemplates::OTP, "otp");
+        assert_eq!(EmailTemplates::MAGIC_LINK, "magic-link");
         assert_eq!(EmailTemplates::WELCOME, "welcome");
         assert_eq!(EmailTemplates::COMPLETE_REGISTRATION_USER_INFO, "complete-registration-user-info");
         assert_eq!(EmailTemplates::COMPLETE_REGISTRATION_STRIPE, "complete-registration-stripe");
+        assert_eq!(EmailTemplates::ACCOUNT_APPROVED, "account-approved");
+        assert_eq!(EmailTemplates::ACCOUNT_REJECTED, "account-rejected");
         assert_eq!(EmailTemplates::NEWSLETTER, "newsletter");
         
         // Verify none of them contain hardcoded prefixes
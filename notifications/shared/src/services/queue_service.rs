@@ -12,6 +12,15 @@ impl EmailQueueService {
         Self { client, queue_url }
     }
 
+    /// Create EmailQueueService for other domains using the queue URL exported by the notification stack
+    pub fn from_env(client: SqsClient) -> Result<Self, NotificationError> {
+        let queue_url = std::env::var("EMAIL_QUEUE_URL")
+            .map_err(|_| NotificationError::ConfigurationError("EMAIL_QUEUE_URL not set".to_string()))?;
+
+        tracing::info!("EmailQueueService initialized with queue: {}", queue_url);
+        Ok(Self::new(client, queue_url))
+    }
+
     /// Queue an email request for processing
     pub async fn queue_email(&self, request: EmailRequest) -> NotificationResult<String> {
         let message_body = serde_json::to_string(&request)
@@ -0,0 +1,175 @@
+import * as cdk from 'aws-cdk-lib';
+import * as lambda from 'aws-cdk-lib/aws-lambda';
+import * as iam from 'aws-cdk-lib/aws-iam';
+import { Construct } from 'constructs';
+import { loadEnvironmentConfig, ResourceNames, TagBuilder, SERVICE_DOMAINS, createResourceName } from '../../../shared/cdk-utils/src';
+
+interface PaymentStackProps extends cdk.StackProps {
+  environment: string;
+}
+
+/**
+ * Payment Stack for Appre Platform
+ *
+ * This stack provides Stripe Connect onboarding for content creators. Creators in
+ * REGISTRATION_NEED_STRIPE get an Express account and a Stripe-hosted onboarding link,
+ * and Stripe's account.updated webhook moves them on to AWAITING_REVIEW.
+ *
+ * AWS Services Included:
+ * - AWS Lambda: Onboarding link and Stripe webhook handlers (2 functions)
+ * - Lambda Function URL: Public HTTPS endpoint for Stripe webhooks
+ * - AWS IAM: Roles and policies for secure service interactions
+ *
+ * Depends on the authentication stack's users and sessions tables, and on the `<app>-<env>-stripe`
+ * Secrets Manager secret created by scripts/aws/put-stripe-secrets.sh.
+ */
+export class PaymentStack extends cdk.Stack {
+  public stripeOnboarding: lambda.Function;
+  public stripeWebhook: lambda.Function;
+  public stripeWebhookUrl: lambda.FunctionUrl;
+
+  private config: ReturnType<typeof loadEnvironmentConfig>;
+  private resourceNames: ResourceNames;
+  private tagBuilder: TagBuilder;
+
+  constructor(scope: Construct, id: string, props: PaymentStackProps) {
+    super(scope, id, props);
+
+    // Load configuration and initialize utilities
+    this.config = loadEnvironmentConfig('../../../');
+    this.resourceNames = new ResourceNames(this.config);
+    this.tagBuilder = new TagBuilder(this.config, SERVICE_DOMAINS.PAYMENTS);
+
+    // Apply global tags to the stack
+    const globalTags = this.tagBuilder.getBaseTags();
+    Object.entries(globalTags).forEach(([key, value]) => {
+      cdk.Tags.of(this).add(key, value);
+    });
+
+    // Create Stripe onboarding and webhook Lambdas
+    this.createStripeLambdaFunctions();
+
+    // Outputs
+    this.createOutputs();
+  }
+
+  private createStripeLambdaFunctions() {
+    // IAM role for payment Lambda functions
+    const paymentsRole = new iam.Role(this, 'PaymentsLambdaRole', {
+      roleName: this.resourceNames.iamRole('payments-lambda-role'),
+      assumedBy: new iam.ServicePrincipal('lambda.amazonaws.com'),
+      managedPolicies: [
+        iam.ManagedPolicy.fromAwsManagedPolicyName('service-role/AWSLambdaBasicExecutionRole'),
+      ],
+    });
+
+    // Apply tags to IAM role
+    const roleTags = this.tagBuilder.getIamTags('payments-lambda-role');
+    Object.entries(roleTags).forEach(([key, value]) => {
+      cdk.Tags.of(paymentsRole).add(key, value);
+    });
+
+    // Grant access to the authentication domain's users and sessions tables
+    const tableArn = (tableName: string) =>
+      `arn:aws:dynamodb:${this.region}:${this.account}:table/${this.resourceNames.dynamoTable(tableName)}`;
+    paymentsRole.addToPolicy(new iam.PolicyStatement({
+      effect: iam.Effect.ALLOW,
+      actions: [
+        'dynamodb:GetItem',
+        'dynamodb:UpdateItem',
+        'dynamodb:DeleteItem',
+      ],
+      resources: [
+        tableArn('users'),
+        tableArn('user-sessions'),
+      ],
+      conditions: {
+        StringEquals: {
+          'aws:ResourceTag/Environment': this.config.environment,
+        },
+      },
+    }));
+
+    // Stripe credentials are CloudFormation dynamic references to the Stripe secret, so the
+    // template only names the secret. A missing secret or field fails the deploy instead of
+    // starting the Lambdas with an empty key.
+    const stripeSecretName = createResourceName('stripe', this.config);
+    const stripeSecret = (field: string) =>
+      cdk.SecretValue.secretsManager(stripeSecretName, { jsonField: field }).unsafeUnwrap();
+
+    const environment = {
+      APP_NAME: this.config.appName,
+      ENVIRONMENT: this.config.environment,
+      OTP_TABLE_NAME: this.resourceNames.dynamoTable('auth-otps'),
+      USERS_TABLE_NAME: cdk.Fn.importValue(`${this.config.appName}-UsersTable-${this.config.environment}`),
+      STRIPE_SECRET_KEY: stripeSecret('secretKey'),
+      STRIPE_ONBOARDING_REFRESH_URL: process.env.STRIPE_ONBOARDING_REFRESH_URL || 'https://appreciata.com/onboarding/stripe/refresh',
+      STRIPE_ONBOARDING_RETURN_URL: process.env.STRIPE_ONBOARDING_RETURN_URL || 'https://appreciata.com/onboarding/stripe/return',
+      DEPLOYMENT_TIMESTAMP: Date.now().toString(), // Force redeployment
+    };
+
+    // Stripe Onboarding Lambda (invoked directly by the webapp)
+    this.stripeOnboarding = new lambda.Function(this, 'StripeOnboarding', {
+      functionName: this.resourceNames.lambda('stripe-onboarding'),
+      runtime: lambda.Runtime.PROVIDED_AL2023,
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/stripe-onboarding/'),
+      role: paymentsRole,
+      timeout: cdk.Duration.seconds(30),
+      memorySize: 128,
+      environment: {
+        ...environment,
+        SESSION_TABLE_NAME: cdk.Fn.importValue(`${this.config.appName}-SessionTable-${this.config.environment}`),
+        SESSION_DURATION_MINUTES: process.env.SESSION_DURATION_MINUTES || '20',
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Stripe Onboarding Lambda
+    const onboardingTags = this.tagBuilder.getLambdaTags('stripe-onboarding');
+    Object.entries(onboardingTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.stripeOnboarding).add(key, value);
+    });
+
+    // Stripe Webhook Lambda (called by Stripe, authenticated by the webhook signature)
+    this.stripeWebhook = new lambda.Function(this, 'StripeWebhook', {
+      functionName: this.resourceNames.lambda('stripe-webhook'),
+      runtime: lambda.Runtime.PROVIDED_AL2023,
+      handler: 'bootstrap',
+      code: lambda.Code.fromAsset('../target/lambda/stripe-webhook/'),
+      role: paymentsRole,
+      timeout: cdk.Duration.seconds(30),
+      memorySize: 128,
+      environment: {
+        ...environment,
+        STRIPE_WEBHOOK_SECRET: stripeSecret('webhookSecret'),
+      },
+      tracing: lambda.Tracing.ACTIVE,
+    });
+
+    // Apply tags to Stripe Webhook Lambda
+    const webhookTags = this.tagBuilder.getLambdaTags('stripe-webhook');
+    Object.entries(webhookTags).forEach(([key, value]) => {
+      cdk.Tags.of(this.stripeWebhook).add(key, value);
+    });
+
+    // Public endpoint to register in the Stripe dashboard (Connect webhook, account.updated)
+    this.stripeWebhookUrl = this.stripeWebhook.addFunctionUrl({
+      authType: lambda.FunctionUrlAuthType.NONE,
+    });
+  }
+
+  private createOutputs() {
+    new cdk.CfnOutput(this, 'StripeOnboardingFunctionName', {
+      value: this.stripeOnboarding.functionName,
+      description: 'Stripe Onboarding Lambda Function Name (invoked by the webapp)',
+      exportName: `${this.config.appName}-StripeOnboardingFunction-${this.config.environment}`,
+    });
+
+    new cdk.CfnOutput(this, 'StripeWebhookUrl', {
+      value: this.stripeWebhookUrl.url,
+      description: 'Stripe Connect webhook endpoint URL',
+      exportName: `${this.config.appName}-StripeWebhookUrl-${this.config.environment}`,
+    });
+  }
+}
@@ -0,0 +1,153 @@
+use aws_config::BehaviorVersion;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use auth_shared::{AuthError, SessionService};
+use payments_shared::{OnboardingLink, OnboardingService, PaymentResult};
+
+/// Onboarding request, invoked directly by the webapp with the caller's session cookie value
+#[derive(Debug, Deserialize)]
+struct StripeOnboardingRequest {
+    session_id: String,
+}
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    sessions: SessionService,
+    onboarding: OnboardingService,
+}
+
+impl AppContext {
+    async fn from_env() -> PaymentResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
+        Ok(Self {
+            sessions: SessionService::from_env(dynamodb_client.clone())?,
+            onboarding: OnboardingService::from_env(dynamodb_client)?,
+        })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<StripeOnboardingRequest>,
+) -> Result<OnboardingLink, Error> {
+    let request = event.payload;
+
+    info!("Stripe onboarding link requested");
+
+    match start_onboarding(&request, &context.sessions, &context.onboarding).await {
+        Ok(link) => {
+            info!("Onboarding link created for account: {}", link.stripe_account_id);
+            Ok(link)
+        }
+        Err(e) => {
+            error!("Stripe onboarding failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Onboarding link for the user the session belongs to
+async fn start_onboarding(
+    request: &StripeOnboardingRequest,
+    sessions: &SessionService,
+    onboarding: &OnboardingService,
+) -> PaymentResult<OnboardingLink> {
+    let session = sessions
+        .validate_session(&request.session_id)
+        .await?
+        .ok_or_else(|| AuthError::InvalidSession("Session not found or expired".to_string()))?;
+
+    onboarding.start_onboarding(&session.user_id).await
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{
+        current_timestamp, EmailAddress, InMemorySessionStore, InMemoryUserRepository, Session,
+        SessionPolicy, UserRepository, UserStatus,
+    };
+    use payments_shared::{InMemoryConnectProvider, PaymentError};
+    use std::sync::Arc;
+
+    async fn setup() -> (Arc<InMemoryUserRepository>, SessionService, OnboardingService) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let email = EmailAddress::parse("ada@example.com").unwrap();
+        let mut user = users.create_user(&email, "user-1").await.unwrap();
+        user.status = UserStatus::RegistrationNeedStripe;
+        users.insert_user(user);
+
+        let store = Arc::new(InMemorySessionStore::new());
+        let now = current_timestamp();
+        store.insert_session(Session {
+            session_id: "session-1".to_string(),
+            user_id: "user-1".to_string(),
+            email: "ada@example.com".to_string(),
+            user_status: "REGISTRATION_NEED_STRIPE".to_string(),
+            given_name: None,
+            family_name: None,
+            created_at: now,
+            last_accessed: now,
+            expires_at: now + 20 * 60,
+            ip_address: None,
+            user_agent: None,
+        });
+
+        let sessions = SessionService::new(store, SessionPolicy::default());
+        let onboarding = OnboardingService::new(
+            users.clone(),
+            Arc::new(InMemoryConnectProvider::new()),
+            "https://app.example.com/onboarding/refresh".to_string(),
+            "https://app.example.com/onboarding/return".to_string(),
+        );
+        (users, sessions, onboarding)
+    }
+
+    fn request(session_id: &str) -> StripeOnboardingRequest {
+        StripeOnboardingRequest {
+            session_id: session_id.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_onboarding_link_for_session_user() {
+        let (users, sessions, onboarding) = setup().await;
+
+        let link = start_onboarding(&request("session-1"), &sessions, &onboarding)
+            .await
+            .unwrap();
+
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.stripe_account_id, Some(link.stripe_account_id));
+    }
+
+    #[tokio::test]
+    async fn test_onboarding_requires_valid_session() {
+        let (_, sessions, onboarding) = setup().await;
+
+        let result = start_onboarding(&request("missing"), &sessions, &onboarding).await;
+
+        assert!(matches!(
+            result,
+            Err(PaymentError::Auth(AuthError::InvalidSession(_)))
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+use aws_config::BehaviorVersion;
+use aws_lambda_events::http::HeaderMap;
+use aws_lambda_events::lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use auth_shared::current_timestamp;
+use payments_shared::{OnboardingService, PaymentError, PaymentResult, WebhookVerifier};
+
+/// Clients and services built once per container and shared by every invocation
+struct AppContext {
+    verifier: WebhookVerifier,
+    onboarding: OnboardingService,
+}
+
+impl AppContext {
+    async fn from_env() -> PaymentResult<Self> {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        Ok(Self {
+            verifier: WebhookVerifier::from_env()?,
+            onboarding: OnboardingService::from_env(aws_sdk_dynamodb::Client::new(&config))?,
+        })
+    }
+}
+
+async fn function_handler(
+    context: &AppContext,
+    event: LambdaEvent<LambdaFunctionUrlRequest>,
+) -> Result<LambdaFunctionUrlResponse, Error> {
+    Ok(handle_webhook(
+        &event.payload,
+        &context.verifier,
+        &context.onboarding,
+        current_timestamp(),
+    )
+    .await)
+}
+
+/// Verify and handle a Stripe webhook delivery. Bad signatures get a 400 so Stripe stops
+/// retrying; failures on our side get a 500 so it tries again later.
+async fn handle_webhook(
+    request: &LambdaFunctionUrlRequest,
+    verifier: &WebhookVerifier,
+    onboarding: &OnboardingService,
+    now: i64,
+) -> LambdaFunctionUrlResponse {
+    let body = request.body.clone().unwrap_or_default();
+    let payload = if request.is_base64_encoded {
+        match STANDARD.decode(&body).ok().and_then(|bytes| String::from_utf8(bytes).ok()) {
+            Some(payload) => payload,
+            None => return response(400, "Invalid request body"),
+        }
+    } else {
+        body
+    };
+
+    let Some(signature) = request
+        .headers
+        .get("stripe-signature")
+        .and_then(|value| value.to_str().ok())
+    else {
+        return response(400, "Missing Stripe-Signature header");
+    };
+
+    let event = match verifier.verify(&payload, signature, now) {
+        Ok(event) => event,
+        Err(e) => {
+            warn!("Rejected webhook: {}", e);
+            return response(400, &e.to_string());
+        }
+    };
+
+    info!("Received Stripe event {} ({})", event.id, event.event_type);
+
+    match onboarding.handle_event(&event).await {
+        Ok(outcome) => {
+            info!("Stripe event {} handled: {:?}", event.id, outcome);
+            json_response(200, json!({ "received": true, "outcome": outcome }))
+        }
+        Err(e @ PaymentError::InvalidEvent(_)) => {
+            warn!("Stripe event {} is malformed: {}", event.id, e);
+            response(400, &e.to_string())
+        }
+        Err(e) => {
+            error!("Failed to handle Stripe event {}: {}", event.id, e);
+            response(500, "Failed to handle event")
+        }
+    }
+}
+
+fn response(status_code: i64, message: &str) -> LambdaFunctionUrlResponse {
+    json_response(status_code, json!({ "message": message }))
+}
+
+fn json_response(status_code: i64, body: serde_json::Value) -> LambdaFunctionUrlResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", "application/json".parse().unwrap());
+    LambdaFunctionUrlResponse {
+        status_code,
+        headers,
+        body: Some(body.to_string()),
+        is_base64_encoded: false,
+        cookies: Vec::new(),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    // Built once here so warm invocations reuse the clients and their connections
+    let context = AppContext::from_env().await?;
+
+    run(service_fn(|event| function_handler(&context, event))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth_shared::{EmailAddress, InMemoryUserRepository, UserRepository, UserStatus};
+    use payments_shared::InMemoryConnectProvider;
+    use std::sync::Arc;
+
+    const NOW: i64 = 1_700_000_000;
+
+    async fn setup() -> (Arc<InMemoryUserRepository>, WebhookVerifier, OnboardingService) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let email = EmailAddress::parse("ada@example.com").unwrap();
+        let mut user = users.create_user(&email, "user-1").await.unwrap();
+        user.status = UserStatus::RegistrationNeedStripe;
+        users.insert_user(user);
+
+        let onboarding = OnboardingService::new(
+            users.clone(),
+            Arc::new(InMemoryConnectProvider::new()),
+            "https://app.example.com/onboarding/refresh".to_string(),
+            "https://app.example.com/onboarding/return".to_string(),
+        );
+        let verifier = WebhookVerifier::new("whsec_test_secret".to_string());
+        (users, verifier, onboarding)
+    }
+
+    fn delivery(payload: &str, signature: Option<String>, base64: bool) -> LambdaFunctionUrlRequest {
+        let mut request = serde_json::from_value::<LambdaFunctionUrlRequest>(json!({
+            "requestContext": { "timeEpoch": NOW * 1000, "http": {} },
+            "body": if base64 { STANDARD.encode(payload) } else { payload.to_string() },
+            "isBase64Encoded": base64,
+        }))
+        .unwrap();
+        if let Some(signature) = signature {
+            request
+                .headers
+                .insert("stripe-signature", signature.parse().unwrap());
+        }
+        request
+    }
+
+    fn account_updated(account_id: &str) -> String {
+        json!({
+            "id": "evt_1",
+            "type": "account.updated",
+            "data": {
+                "object": {
+                    "id": account_id,
+                    "details_submitted": true,
+                    "metadata": { "user_id": "user-1" }
+                }
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_completed_onboarding_event() {
+        let (users, verifier, onboarding) = setup().await;
+        let link = onboarding.start_onboarding("user-1").await.unwrap();
+        let payload = account_updated(&link.stripe_account_id);
+        let signature = verifier.signature_header(&payload, NOW);
+
+        let response =
+            handle_webhook(&delivery(&payload, Some(signature), true), &verifier, &onboarding, NOW).await;
+
+        assert_eq!(response.status_code, 200);
+        assert!(response.body.unwrap().contains("onboarding_completed"));
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::AwaitingReview);
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_or_forged_events_are_rejected() {
+        let (users, verifier, onboarding) = setup().await;
+        let link = onboarding.start_onboarding("user-1").await.unwrap();
+        let payload = account_updated(&link.stripe_account_id);
+        let forged = WebhookVerifier::new("whsec_attacker".to_string()).signature_header(&payload, NOW);
+
+        for signature in [None, Some(forged)] {
+            let response =
+                handle_webhook(&delivery(&payload, signature, false), &verifier, &onboarding, NOW).await;
+            assert_eq!(response.status_code, 400);
+        }
+
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::RegistrationNeedStripe);
+    }
+}
@@ -0,0 +1,37 @@
+use auth_shared::AuthError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PaymentError {
+    #[error("Stripe error: {0}")]
+    StripeError(String),
+
+    #[error("Invalid webhook signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Invalid webhook event: {0}")]
+    InvalidEvent(String),
+
+    #[error("Onboarding not available: {0}")]
+    OnboardingNotAvailable(String),
+
+    #[error("Configuration error: {0}")]
+    ConfigurationError(String),
+
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+}
+
+impl From<reqwest::Error> for PaymentError {
+    fn from(err: reqwest::Error) -> Self {
+        PaymentError::StripeError(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for PaymentError {
+    fn from(err: serde_json::Error) -> Self {
+        PaymentError::InvalidEvent(err.to_string())
+    }
+}
+
+pub type PaymentResult<T> = Result<T, PaymentError>;
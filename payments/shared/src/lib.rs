@@ -0,0 +1,11 @@
+pub mod models;
+pub mod services;
+pub mod repositories;
+pub mod webhook;
+pub mod errors;
+
+pub use models::*;
+pub use services::*;
+pub use repositories::*;
+pub use webhook::*;
+pub use errors::*;
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Stripe event type sent whenever a connected account changes, including onboarding progress
+pub const ACCOUNT_UPDATED_EVENT: &str = "account.updated";
+
+/// Metadata key on the Stripe account that points back to our user
+pub const USER_ID_METADATA_KEY: &str = "user_id";
+
+/// The fields we use from a Stripe Connect account object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectAccount {
+    pub id: String,
+    /// True once the creator has finished Stripe's onboarding form
+    #[serde(default)]
+    pub details_submitted: bool,
+    #[serde(default)]
+    pub charges_enabled: bool,
+    #[serde(default)]
+    pub payouts_enabled: bool,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl ConnectAccount {
+    /// Our user ID, as recorded in the account metadata when it was created
+    pub fn user_id(&self) -> Option<&str> {
+        self.metadata.get(USER_ID_METADATA_KEY).map(String::as_str)
+    }
+}
+
+/// A single-use Stripe-hosted onboarding URL for a connected account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountLink {
+    pub url: String,
+    pub expires_at: i64,
+}
+
+/// What the webapp needs to send the creator to Stripe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnboardingLink {
+    pub stripe_account_id: String,
+    pub url: String,
+    pub expires_at: i64,
+}
+
+/// A Stripe webhook event envelope
+#[derive(Debug, Clone, Deserialize)]
+pub struct StripeEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: StripeEventData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StripeEventData {
+    /// The object the event is about; its shape depends on the event type
+    pub object: serde_json::Value,
+}
+
+/// Outcome of handling a webhook event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookOutcome {
+    /// The creator finished onboarding and moved on to AwaitingReview
+    OnboardingCompleted,
+    /// Nothing to do: another event type, onboarding still in progress, or already handled
+    Ignored,
+}
@@ -0,0 +1,23 @@
+pub mod in_memory;
+
+pub use in_memory::*;
+
+use async_trait::async_trait;
+
+use crate::{AccountLink, ConnectAccount, PaymentResult};
+
+/// Stripe Connect operations used during creator onboarding
+#[async_trait]
+pub trait ConnectProvider: Send + Sync {
+    /// Create an Express account for the user, tagged with their user ID. Retries for the same
+    /// user return the same account rather than creating another.
+    async fn create_express_account(&self, user_id: &str, email: &str) -> PaymentResult<ConnectAccount>;
+
+    /// Create a Stripe-hosted onboarding link for the account
+    async fn create_account_link(
+        &self,
+        account_id: &str,
+        refresh_url: &str,
+        return_url: &str,
+    ) -> PaymentResult<AccountLink>;
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{
+    AccountLink, ConnectAccount, ConnectProvider, PaymentResult, USER_ID_METADATA_KEY,
+};
+
+/// In-memory Stripe Connect for tests and local development
+#[derive(Debug, Default)]
+pub struct InMemoryConnectProvider {
+    /// Accounts keyed by user ID, mirroring Stripe's idempotent account creation
+    accounts: Mutex<HashMap<String, ConnectAccount>>,
+}
+
+impl InMemoryConnectProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every account created so far
+    pub fn accounts(&self) -> Vec<ConnectAccount> {
+        self.accounts.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl ConnectProvider for InMemoryConnectProvider {
+    async fn create_express_account(&self, user_id: &str, _email: &str) -> PaymentResult<ConnectAccount> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let count = accounts.len();
+        let account = accounts
+            .entry(user_id.to_string())
+            .or_insert_with(|| ConnectAccount {
+                id: format!("acct_test{}", count + 1),
+                details_submitted: false,
+                charges_enabled: false,
+                payouts_enabled: false,
+                metadata: HashMap::from([(USER_ID_METADATA_KEY.to_string(), user_id.to_string())]),
+            });
+        Ok(account.clone())
+    }
+
+    async fn create_account_link(
+        &self,
+        account_id: &str,
+        _refresh_url: &str,
+        _return_url: &str,
+    ) -> PaymentResult<AccountLink> {
+        Ok(AccountLink {
+            url: format!("https://connect.stripe.com/setup/e/{}", account_id),
+            expires_at: chrono::Utc::now().timestamp() + 300,
+        })
+    }
+}
@@ -0,0 +1,5 @@
+pub mod stripe_service;
+pub mod onboarding_service;
+
+pub use stripe_service::*;
+pub use onboarding_service::*;
@@ -0,0 +1,258 @@
+use auth_shared::{AuthError, DynamoDBService, UserRepository, UserStatus};
+use aws_sdk_dynamodb::Client as DynamoClient;
+use std::sync::Arc;
+
+use crate::{
+    ConnectAccount, ConnectProvider, OnboardingLink, PaymentError, PaymentResult, StripeEvent,
+    StripeService, WebhookOutcome, ACCOUNT_UPDATED_EVENT,
+};
+
+/// Actor recorded in the status history when Stripe reports onboarding as complete
+const WEBHOOK_ACTOR: &str = "stripe-webhook";
+
+/// Stripe Connect onboarding for creators in RegistrationNeedStripe
+pub struct OnboardingService {
+    users: Arc<dyn UserRepository>,
+    stripe: Arc<dyn ConnectProvider>,
+    refresh_url: String,
+    return_url: String,
+}
+
+impl OnboardingService {
+    pub fn new(
+        users: Arc<dyn UserRepository>,
+        stripe: Arc<dyn ConnectProvider>,
+        refresh_url: String,
+        return_url: String,
+    ) -> Self {
+        Self {
+            users,
+            stripe,
+            refresh_url,
+            return_url,
+        }
+    }
+
+    /// Create OnboardingService using the CDK-provided users table, Stripe key and return URLs
+    pub fn from_env(client: DynamoClient) -> Result<Self, PaymentError> {
+        let users = DynamoDBService::from_env(client)?;
+        let stripe = StripeService::from_env()?;
+
+        let url = |name: &str| {
+            std::env::var(name).map_err(|e| {
+                tracing::error!("{} environment variable not set: {:?}", name, e);
+                PaymentError::ConfigurationError(format!("{} not set", name))
+            })
+        };
+        let refresh_url = url("STRIPE_ONBOARDING_REFRESH_URL")?;
+        let return_url = url("STRIPE_ONBOARDING_RETURN_URL")?;
+
+        Ok(Self::new(Arc::new(users), Arc::new(stripe), refresh_url, return_url))
+    }
+
+    /// Get an onboarding link for the user, creating and storing their Express account the
+    /// first time. Links are single use, so the webapp asks for a new one on every visit.
+    pub async fn start_onboarding(&self, user_id: &str) -> PaymentResult<OnboardingLink> {
+        let user = self
+            .users
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AuthError::UserNotFound(user_id.to_string()))?;
+
+        if user.status != UserStatus::RegistrationNeedStripe {
+            return Err(PaymentError::OnboardingNotAvailable(format!(
+                "User {} is in status {}",
+                user_id,
+                user.status.as_str()
+            )));
+        }
+
+        let stripe_account_id = match user.stripe_account_id {
+            Some(account_id) => account_id,
+            None => {
+                let account = self
+                    .stripe
+                    .create_express_account(user_id, user.email.as_str())
+                    .await?;
+                self.users
+                    .set_stripe_account_id(user_id, &account.id)
+                    .await?;
+                account.id
+            }
+        };
+
+        let link = self
+            .stripe
+            .create_account_link(&stripe_account_id, &self.refresh_url, &self.return_url)
+            .await?;
+
+        Ok(OnboardingLink {
+            stripe_account_id,
+            url: link.url,
+            expires_at: link.expires_at,
+        })
+    }
+
+    /// Advance the creator to AwaitingReview once an `account.updated` event shows their
+    /// details are submitted. Stripe retries and duplicates events, so anything already
+    /// handled is ignored rather than treated as an error.
+    pub async fn handle_event(&self, event: &StripeEvent) -> PaymentResult<WebhookOutcome> {
+        if event.event_type != ACCOUNT_UPDATED_EVENT {
+            return Ok(WebhookOutcome::Ignored);
+        }
+
+        let account: ConnectAccount = serde_json::from_value(event.data.object.clone())?;
+        if !account.details_submitted {
+            return Ok(WebhookOutcome::Ignored);
+        }
+
+        let Some(user_id) = account.user_id() else {
+            tracing::warn!("Stripe account {} has no user_id metadata", account.id);
+            return Ok(WebhookOutcome::Ignored);
+        };
+        let Some(user) = self.users.get_user_by_id(user_id).await? else {
+            tracing::warn!("Stripe account {} belongs to unknown user {}", account.id, user_id);
+            return Ok(WebhookOutcome::Ignored);
+        };
+        if user.stripe_account_id.as_deref() != Some(account.id.as_str()) {
+            tracing::warn!(
+                "Stripe account {} is not the account stored for user {}",
+                account.id, user_id
+            );
+            return Ok(WebhookOutcome::Ignored);
+        }
+        if user.status != UserStatus::RegistrationNeedStripe {
+            return Ok(WebhookOutcome::Ignored);
+        }
+
+        match self
+            .users
+            .transition_status(
+                user_id,
+                UserStatus::RegistrationNeedStripe,
+                UserStatus::AwaitingReview,
+                WEBHOOK_ACTOR,
+            )
+            .await
+        {
+            Ok(()) => {
+                tracing::info!("User {} completed Stripe onboarding ({})", user_id, event.id);
+                Ok(WebhookOutcome::OnboardingCompleted)
+            }
+            // A concurrent delivery of the same event got there first
+            Err(AuthError::InvalidStatusTransition(_)) => Ok(WebhookOutcome::Ignored),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryConnectProvider;
+    use auth_shared::{EmailAddress, InMemoryUserRepository};
+    use serde_json::json;
+
+    async fn setup(
+        status: UserStatus,
+    ) -> (Arc<InMemoryUserRepository>, Arc<InMemoryConnectProvider>, OnboardingService) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let email = EmailAddress::parse("ada@example.com").unwrap();
+        let mut user = users.create_user(&email, "user-1").await.unwrap();
+        user.status = status;
+        users.insert_user(user);
+
+        let stripe = Arc::new(InMemoryConnectProvider::new());
+        let service = OnboardingService::new(
+            users.clone(),
+            stripe.clone(),
+            "https://app.example.com/onboarding/refresh".to_string(),
+            "https://app.example.com/onboarding/return".to_string(),
+        );
+        (users, stripe, service)
+    }
+
+    fn account_updated(account_id: &str, user_id: &str, details_submitted: bool) -> StripeEvent {
+        serde_json::from_value(json!({
+            "id": "evt_1",
+            "type": "account.updated",
+            "data": {
+                "object": {
+                    "id": account_id,
+                    "object": "account",
+                    "details_submitted": details_submitted,
+                    "metadata": { "user_id": user_id }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_start_onboarding_creates_account_once() {
+        let (users, stripe, service) = setup(UserStatus::RegistrationNeedStripe).await;
+
+        let first = service.start_onboarding("user-1").await.unwrap();
+        let second = service.start_onboarding("user-1").await.unwrap();
+
+        assert_eq!(first.stripe_account_id, second.stripe_account_id);
+        assert!(first.url.contains(&first.stripe_account_id));
+        assert_eq!(stripe.accounts().len(), 1);
+        assert_eq!(stripe.accounts()[0].user_id(), Some("user-1"));
+
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.stripe_account_id, Some(first.stripe_account_id));
+        assert_eq!(user.status, UserStatus::RegistrationNeedStripe);
+    }
+
+    #[tokio::test]
+    async fn test_start_onboarding_requires_need_stripe() {
+        let (_, stripe, service) = setup(UserStatus::RegistrationNeedUserInfo).await;
+
+        let result = service.start_onboarding("user-1").await;
+        assert!(matches!(result, Err(PaymentError::OnboardingNotAvailable(_))));
+
+        let result = service.start_onboarding("missing").await;
+        assert!(matches!(result, Err(PaymentError::Auth(AuthError::UserNotFound(_)))));
+        assert!(stripe.accounts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_completed_onboarding_moves_to_awaiting_review() {
+        let (users, _, service) = setup(UserStatus::RegistrationNeedStripe).await;
+        let link = service.start_onboarding("user-1").await.unwrap();
+
+        // Still filling in the form
+        let event = account_updated(&link.stripe_account_id, "user-1", false);
+        assert_eq!(service.handle_event(&event).await.unwrap(), WebhookOutcome::Ignored);
+
+        let event = account_updated(&link.stripe_account_id, "user-1", true);
+        assert_eq!(
+            service.handle_event(&event).await.unwrap(),
+            WebhookOutcome::OnboardingCompleted
+        );
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::AwaitingReview);
+        let history = users.get_status_history("user-1").await.unwrap();
+        assert_eq!(history.last().unwrap().actor, WEBHOOK_ACTOR);
+
+        // Redelivery is harmless
+        assert_eq!(service.handle_event(&event).await.unwrap(), WebhookOutcome::Ignored);
+    }
+
+    #[tokio::test]
+    async fn test_events_for_other_accounts_are_ignored() {
+        let (users, _, service) = setup(UserStatus::RegistrationNeedStripe).await;
+        service.start_onboarding("user-1").await.unwrap();
+
+        let event = account_updated("acct_someone_else", "user-1", true);
+        assert_eq!(service.handle_event(&event).await.unwrap(), WebhookOutcome::Ignored);
+
+        let mut event = account_updated("acct_test1", "user-1", true);
+        event.event_type = "payout.paid".to_string();
+        assert_eq!(service.handle_event(&event).await.unwrap(), WebhookOutcome::Ignored);
+
+        let user = users.get_user_by_id("user-1").await.unwrap().unwrap();
+        assert_eq!(user.status, UserStatus::RegistrationNeedStripe);
+    }
+}
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::{
+    AccountLink, ConnectAccount, ConnectProvider, PaymentError, PaymentResult,
+    USER_ID_METADATA_KEY,
+};
+
+/// Stripe API used when STRIPE_API_BASE isn't set
+pub const DEFAULT_STRIPE_API_BASE: &str = "https://api.stripe.com";
+
+/// Error body returned by the Stripe API
+#[derive(Debug, Deserialize)]
+struct StripeErrorResponse {
+    error: StripeErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeErrorBody {
+    message: Option<String>,
+    #[serde(rename = "type")]
+    error_type: Option<String>,
+}
+
+/// Stripe Connect over the Stripe REST API
+pub struct StripeService {
+    client: reqwest::Client,
+    api_base: String,
+    secret_key: String,
+}
+
+impl StripeService {
+    pub fn new(client: reqwest::Client, api_base: String, secret_key: String) -> Self {
+        Self {
+            client,
+            api_base: api_base.trim_end_matches('/').to_string(),
+            secret_key,
+        }
+    }
+
+    /// Create StripeService from STRIPE_SECRET_KEY, and STRIPE_API_BASE when pointing at stripe-mock
+    pub fn from_env() -> Result<Self, PaymentError> {
+        let secret_key = std::env::var("STRIPE_SECRET_KEY").map_err(|e| {
+            tracing::error!("STRIPE_SECRET_KEY environment variable not set: {:?}", e);
+            PaymentError::ConfigurationError("STRIPE_SECRET_KEY not set".to_string())
+        })?;
+        let api_base = std::env::var("STRIPE_API_BASE")
+            .unwrap_or_else(|_| DEFAULT_STRIPE_API_BASE.to_string());
+
+        tracing::info!("StripeService initialized with API base: {}", api_base);
+        Ok(Self::new(reqwest::Client::new(), api_base, secret_key))
+    }
+
+    /// POST a form to the Stripe API and decode the response
+    async fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        form: &[(&str, &str)],
+        idempotency_key: Option<&str>,
+    ) -> PaymentResult<T> {
+        let mut request = self
+            .client
+            .post(format!("{}{}", self.api_base, path))
+            .bearer_auth(&self.secret_key)
+            .form(form);
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            let message = serde_json::from_str::<StripeErrorResponse>(&body)
+                .ok()
+                .map(|e| {
+                    format!(
+                        "{}: {}",
+                        e.error.error_type.unwrap_or_else(|| "api_error".to_string()),
+                        e.error.message.unwrap_or_default()
+                    )
+                })
+                .unwrap_or(body);
+            tracing::error!("Stripe {} failed with {}: {}", path, status, message);
+            return Err(PaymentError::StripeError(format!("{} ({})", message, status)));
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|e| PaymentError::StripeError(format!("Unexpected response from {}: {}", path, e)))
+    }
+}
+
+#[async_trait]
+impl ConnectProvider for StripeService {
+    /// Create the account with an idempotency key derived from the user ID, so a retried or
+    /// concurrent onboarding request gets the same account back from Stripe
+    async fn create_express_account(&self, user_id: &str, email: &str) -> PaymentResult<ConnectAccount> {
+        let metadata_key = format!("metadata[{}]", USER_ID_METADATA_KEY);
+        let form = [
+            ("type", "express"),
+            ("email", email),
+            (metadata_key.as_str(), user_id),
+            ("capabilities[card_payments][requested]", "true"),
+            ("capabilities[transfers][requested]", "true"),
+        ];
+        let idempotency_key = format!("express-account-{}", user_id);
+
+        let account: ConnectAccount = self
+            .post("/v1/accounts", &form, Some(&idempotency_key))
+            .await?;
+        tracing::info!("Created Stripe Express account {} for user {}", account.id, user_id);
+        Ok(account)
+    }
+
+    async fn create_account_link(
+        &self,
+        account_id: &str,
+        refresh_url: &str,
+        return_url: &str,
+    ) -> PaymentResult<AccountLink> {
+        let form = [
+            ("account", account_id),
+            ("refresh_url", refresh_url),
+            ("return_url", return_url),
+            ("type", "account_onboarding"),
+        ];
+        self.post("/v1/account_links", &form, None).await
+    }
+}
@@ -0,0 +1,135 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{PaymentError, PaymentResult, StripeEvent};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How far a webhook's signed timestamp may be from now before it's treated as a replay
+pub const DEFAULT_WEBHOOK_TOLERANCE_SECONDS: i64 = 300;
+
+/// Verifies the `Stripe-Signature` header on webhook deliveries
+#[derive(Debug, Clone)]
+pub struct WebhookVerifier {
+    secret: String,
+    tolerance_seconds: i64,
+}
+
+impl WebhookVerifier {
+    pub fn new(secret: String) -> Self {
+        Self {
+            secret,
+            tolerance_seconds: DEFAULT_WEBHOOK_TOLERANCE_SECONDS,
+        }
+    }
+
+    /// Create WebhookVerifier from the endpoint's STRIPE_WEBHOOK_SECRET (`whsec_...`)
+    pub fn from_env() -> Result<Self, PaymentError> {
+        let secret = std::env::var("STRIPE_WEBHOOK_SECRET").map_err(|e| {
+            tracing::error!("STRIPE_WEBHOOK_SECRET environment variable not set: {:?}", e);
+            PaymentError::ConfigurationError("STRIPE_WEBHOOK_SECRET not set".to_string())
+        })?;
+        Ok(Self::new(secret))
+    }
+
+    fn mac(&self, timestamp: i64, payload: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(format!("{}.{}", timestamp, payload).as_bytes());
+        mac
+    }
+
+    /// Check the signature header against the raw request body and parse the event. Any `v1`
+    /// signature may match, since Stripe signs with both secrets while one is being rolled.
+    pub fn verify(&self, payload: &str, signature_header: &str, now: i64) -> PaymentResult<StripeEvent> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in signature_header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                Some(("v1", value)) => signatures.push(value),
+                _ => {}
+            }
+        }
+
+        let timestamp = timestamp
+            .ok_or_else(|| PaymentError::InvalidSignature("Missing timestamp".to_string()))?;
+        if (now - timestamp).abs() > self.tolerance_seconds {
+            return Err(PaymentError::InvalidSignature(
+                "Timestamp outside the tolerance window".to_string(),
+            ));
+        }
+
+        let matches = signatures.iter().any(|signature| {
+            hex::decode(signature)
+                .is_ok_and(|bytes| self.mac(timestamp, payload).verify_slice(&bytes).is_ok())
+        });
+        if !matches {
+            return Err(PaymentError::InvalidSignature(
+                "No matching v1 signature".to_string(),
+            ));
+        }
+
+        Ok(serde_json::from_str(payload)?)
+    }
+
+    /// A `Stripe-Signature` header for the payload, as Stripe would send it (for tests and
+    /// replaying captured events locally)
+    pub fn signature_header(&self, payload: &str, timestamp: i64) -> String {
+        let signature = hex::encode(self.mac(timestamp, payload).finalize().into_bytes());
+        format!("t={},v1={}", timestamp, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: &str = r#"{"id":"evt_1","type":"account.updated","data":{"object":{"id":"acct_1"}}}"#;
+    const NOW: i64 = 1_700_000_000;
+
+    fn verifier() -> WebhookVerifier {
+        WebhookVerifier::new("whsec_test_secret".to_string())
+    }
+
+    #[test]
+    fn test_verify_signed_event() {
+        let header = verifier().signature_header(PAYLOAD, NOW);
+
+        let event = verifier().verify(PAYLOAD, &header, NOW + 10).unwrap();
+        assert_eq!(event.id, "evt_1");
+        assert_eq!(event.event_type, "account.updated");
+    }
+
+    #[test]
+    fn test_any_v1_signature_may_match() {
+        let other = WebhookVerifier::new("whsec_old_secret".to_string());
+        let header = format!(
+            "{},v1={},v0=ignored",
+            other.signature_header(PAYLOAD, NOW),
+            verifier().signature_header(PAYLOAD, NOW).split("v1=").nth(1).unwrap()
+        );
+
+        assert!(verifier().verify(PAYLOAD, &header, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_bad_signatures() {
+        let header = verifier().signature_header(PAYLOAD, NOW);
+        let tampered = PAYLOAD.replace("acct_1", "acct_2");
+        let wrong_secret = WebhookVerifier::new("whsec_other".to_string());
+
+        for (payload, header, now) in [
+            (tampered.as_str(), header.clone(), NOW),
+            (PAYLOAD, wrong_secret.signature_header(PAYLOAD, NOW), NOW),
+            (PAYLOAD, header.clone(), NOW + DEFAULT_WEBHOOK_TOLERANCE_SECONDS + 1),
+            (PAYLOAD, "v1=abc".to_string(), NOW),
+            (PAYLOAD, format!("t={}", NOW), NOW),
+        ] {
+            assert!(matches!(
+                verifier().verify(payload, &header, now),
+                Err(PaymentError::InvalidSignature(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Integration tests for StripeService against stripe-mock
+// Start stripe-mock with: docker run --rm -p 12111:12111 stripe/stripe-mock
+// Run with: cargo test -p payments-shared --test stripe_mock -- --ignored
+// STRIPE_MOCK_URL overrides the default http://localhost:12111
+
+use payments_shared::{ConnectProvider, StripeService};
+
+fn stripe() -> StripeService {
+    let api_base =
+        std::env::var("STRIPE_MOCK_URL").unwrap_or_else(|_| "http://localhost:12111".to_string());
+    // stripe-mock accepts any well-formed test key
+    StripeService::new(reqwest::Client::new(), api_base, "sk_test_123".to_string())
+}
+
+#[tokio::test]
+#[ignore = "requires stripe-mock"]
+async fn test_create_express_account() {
+    let account = stripe()
+        .create_express_account("user-1", "ada@example.com")
+        .await
+        .expect("Failed to create account");
+
+    assert!(account.id.starts_with("acct_"));
+}
+
+#[tokio::test]
+#[ignore = "requires stripe-mock"]
+async fn test_create_account_link() {
+    let stripe = stripe();
+    let account = stripe
+        .create_express_account("user-1", "ada@example.com")
+        .await
+        .expect("Failed to create account");
+
+    let link = stripe
+        .create_account_link(
+            &account.id,
+            "https://app.example.com/onboarding/refresh",
+            "https://app.example.com/onboarding/return",
+        )
+        .await
+        .expect("Failed to create account link");
+
+    assert!(link.url.starts_with("https://"));
+    assert!(link.expires_at > 0);
+}
+
+#[tokio::test]
+#[ignore = "requires stripe-mock"]
+async fn test_invalid_request_is_a_stripe_error() {
+    // stripe-mock validates parameters against the API spec
+    let result = stripe()
+        .create_account_link("acct_123", "not a url", "https://app.example.com/onboarding/return")
+        .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,159 @@
+#!/bin/bash
+
+# Email Canonicalisation Script
+# Rewrites emails stored before emails were canonical (exactly as Cognito supplied them) into the
+# lowercase form EmailAddress keys everything by, in the users, OTP and rate limit tables
+# Usage: ./canonicalize-emails.sh [--apply]
+# Without --apply it only lists what would change. The environment and region come from
+# ENVIRONMENT (default test) and AWS_REGION (default eu-west-2)
+# Examples:
+#   ./canonicalize-emails.sh                        # dry run against test
+#   ENVIRONMENT=prod ./canonicalize-emails.sh --apply
+
+set -e
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+YELLOW='\033[1;33m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+print_info() {
+    echo -e "${BLUE}ℹ️  $1${NC}"
+}
+
+print_success() {
+    echo -e "${GREEN}✅ $1${NC}"
+}
+
+print_warning() {
+    echo -e "${YELLOW}⚠️  $1${NC}"
+}
+
+print_error() {
+    echo -e "${RED}❌ $1${NC}"
+}
+
+APPLY=false
+if [ "$1" = "--apply" ]; then
+    APPLY=true
+elif [ -n "$1" ]; then
+    print_error "Usage: $0 [--apply]"
+    exit 1
+fi
+
+ENVIRONMENT=${ENVIRONMENT:-test}
+REGION=${AWS_REGION:-eu-west-2}
+PREFIX="${APP_NAME:-appre}-$ENVIRONMENT"
+USERS_TABLE="$PREFIX-users"
+OTP_TABLE="$PREFIX-auth-otps"
+RATE_LIMIT_TABLE="$PREFIX-rate-limits"
+
+if ! command -v aws &> /dev/null; then
+    print_error "AWS CLI is not installed. Please install it first."
+    exit 1
+fi
+
+# Canonical form of an ASCII address: trimmed and lowercased. Internationalised domains also
+# need punycode, which is left to EmailAddress; those are reported for a manual fix instead.
+canonical() {
+    local email
+    email=$(echo "$1" | sed 's/^[[:space:]]*//;s/[[:space:]]*$//')
+    if LC_ALL=C grep -q '[^ -~]' <<< "$email"; then
+        return 1
+    fi
+    echo "$email" | tr '[:upper:]' '[:lower:]'
+}
+
+CHANGED=0
+
+print_info "Users in $USERS_TABLE"
+while IFS=$'\t' read -r USER_ID EMAIL; do
+    [ -z "$USER_ID" ] && continue
+    if ! CANONICAL=$(canonical "$EMAIL"); then
+        print_warning "$USER_ID: $EMAIL has a non-ASCII domain; fix it by hand"
+        continue
+    fi
+    [ "$CANONICAL" = "$EMAIL" ] && continue
+
+    echo "  $USER_ID: $EMAIL -> $CANONICAL"
+    CHANGED=$((CHANGED + 1))
+    if [ "$APPLY" = true ]; then
+        aws dynamodb update-item \
+            --table-name "$USERS_TABLE" \
+            --region "$REGION" \
+            --key "{\"user_id\":{\"S\":\"$USER_ID\"}}" \
+            --update-expression "SET email = :email" \
+            --condition-expression "email = :old" \
+            --expression-attribute-values "{\":email\":{\"S\":\"$CANONICAL\"},\":old\":{\"S\":\"$EMAIL\"}}"
+    fi
+done < <(aws dynamodb scan \
+    --table-name "$USERS_TABLE" \
+    --region "$REGION" \
+    --projection-expression "user_id, email" \
+    --query 'Items[].[user_id.S, email.S]' \
+    --output text)
+
+# OTPs live for minutes, so a record under a raw address is deleted rather than moved; the user
+# just asks for a new code
+print_info "OTP records in $OTP_TABLE"
+while read -r EMAIL; do
+    [ -z "$EMAIL" ] && continue
+    CANONICAL=$(canonical "$EMAIL") || CANONICAL=""
+    [ "$CANONICAL" = "$EMAIL" ] && continue
+
+    echo "  delete OTP for $EMAIL"
+    CHANGED=$((CHANGED + 1))
+    if [ "$APPLY" = true ]; then
+        aws dynamodb delete-item \
+            --table-name "$OTP_TABLE" \
+            --region "$REGION" \
+            --key "{\"email\":{\"S\":\"$EMAIL\"}}"
+    fi
+done < <(aws dynamodb scan \
+    --table-name "$OTP_TABLE" \
+    --region "$REGION" \
+    --projection-expression "email" \
+    --query 'Items[].[email.S]' \
+    --output text)
+
+# Counter keys are <action>#<window seconds>#<key>; counts under a raw address are added to the
+# canonical counter for the same window so nobody gets a fresh allowance
+print_info "Rate limit counters in $RATE_LIMIT_TABLE"
+while IFS=$'\t' read -r KEY WINDOW_START COUNT TTL; do
+    [ -z "$KEY" ] && continue
+    SUBJECT="${KEY##*#}"
+    case "$SUBJECT" in *@*) ;; *) continue ;; esac
+    CANONICAL=$(canonical "$SUBJECT") || continue
+    [ "$CANONICAL" = "$SUBJECT" ] && continue
+
+    NEW_KEY="${KEY%#*}#$CANONICAL"
+    echo "  $KEY @ $WINDOW_START -> $NEW_KEY (+$COUNT)"
+    CHANGED=$((CHANGED + 1))
+    if [ "$APPLY" = true ]; then
+        aws dynamodb update-item \
+            --table-name "$RATE_LIMIT_TABLE" \
+            --region "$REGION" \
+            --key "{\"email\":{\"S\":\"$NEW_KEY\"},\"request_timestamp\":{\"N\":\"$WINDOW_START\"}}" \
+            --update-expression "ADD request_count :count SET #ttl = :ttl" \
+            --expression-attribute-names '{"#ttl":"ttl"}' \
+            --expression-attribute-values "{\":count\":{\"N\":\"$COUNT\"},\":ttl\":{\"N\":\"$TTL\"}}"
+        aws dynamodb delete-item \
+            --table-name "$RATE_LIMIT_TABLE" \
+            --region "$REGION" \
+            --key "{\"email\":{\"S\":\"$KEY\"},\"request_timestamp\":{\"N\":\"$WINDOW_START\"}}"
+    fi
+done < <(aws dynamodb scan \
+    --table-name "$RATE_LIMIT_TABLE" \
+    --region "$REGION" \
+    --projection-expression "email, request_timestamp, request_count, #ttl" \
+    --expression-attribute-names '{"#ttl":"ttl"}' \
+    --query 'Items[].[email.S, request_timestamp.N, request_count.N, ttl.N]' \
+    --output text)
+
+if [ "$APPLY" = true ]; then
+    print_success "Rewrote $CHANGED items in $ENVIRONMENT"
+else
+    print_info "$CHANGED items would change; rerun with --apply to rewrite them"
+fi
@@ -0,0 +1,87 @@
+#!/bin/bash
+
+# Event Capture Script
+# Pulls the redacted trigger events a Lambda logged with EVENT_CAPTURE=log into replay fixtures
+# Usage: ./capture-events.sh <function> [hours back]
+# The environment and region come from ENVIRONMENT (default test) and AWS_REGION (default eu-west-2)
+# Examples:
+#   ./capture-events.sh define-auth-challenge          # the last 24 hours
+#   ENVIRONMENT=prod ./capture-events.sh pre-signup 2
+# Fixtures are written to authentication/lambda/fixtures/<function>/; review them, then run
+# UPDATE_SNAPSHOTS=1 cargo test in authentication/lambda to record their snapshots
+
+set -e
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+print_info() {
+    echo -e "${BLUE}ℹ️  $1${NC}"
+}
+
+print_success() {
+    echo -e "${GREEN}✅ $1${NC}"
+}
+
+print_error() {
+    echo -e "${RED}❌ $1${NC}"
+}
+
+case "$1" in
+    create-auth-challenge|verify-auth-challenge|define-auth-challenge|pre-signup|pre-token-generation)
+        ;;
+    *)
+        print_error "Usage: $0 <function> [hours back]"
+        print_info "Functions: create-auth-challenge, verify-auth-challenge, define-auth-challenge, pre-signup, pre-token-generation"
+        exit 1
+        ;;
+esac
+
+FUNCTION="$1"
+HOURS=${2:-24}
+ENVIRONMENT=${ENVIRONMENT:-test}
+REGION=${AWS_REGION:-eu-west-2}
+LOG_GROUP="/aws/lambda/${APP_NAME:-appre}-$ENVIRONMENT-$FUNCTION"
+SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
+FIXTURE_DIR="$SCRIPT_DIR/../../authentication/lambda/fixtures/$FUNCTION"
+
+if ! command -v aws &> /dev/null; then
+    print_error "AWS CLI is not installed. Please install it first."
+    exit 1
+fi
+
+START_TIME=$(( ($(date +%s) - HOURS * 3600) * 1000 ))
+mkdir -p "$FIXTURE_DIR"
+
+print_info "Reading captured events from $LOG_GROUP for the last $HOURS hours..."
+COUNT=0
+while IFS=$'\t' read -r EVENT_ID MESSAGE; do
+    [ -z "$MESSAGE" ] && continue
+    # Everything after "EVENT_CAPTURE <function> " is the redacted event
+    EVENT="${MESSAGE#*EVENT_CAPTURE $FUNCTION }"
+    TRIGGER=$(echo "$EVENT" | grep -o '"triggerSource":"[^"]*"' | cut -d'"' -f4)
+    FILE="$FIXTURE_DIR/${TRIGGER:-event}-${EVENT_ID: -8}.json"
+
+    if command -v python3 &> /dev/null; then
+        echo "$EVENT" | python3 -m json.tool --indent 2 > "$FILE"
+    else
+        echo "$EVENT" > "$FILE"
+    fi
+    COUNT=$((COUNT + 1))
+done < <(aws logs filter-log-events \
+    --log-group-name "$LOG_GROUP" \
+    --region "$REGION" \
+    --start-time "$START_TIME" \
+    --filter-pattern "\"EVENT_CAPTURE $FUNCTION\"" \
+    --query 'events[].[eventId, message]' \
+    --output text)
+
+if [ "$COUNT" -eq 0 ]; then
+    print_error "No captured events found. Is EVENT_CAPTURE=log set on the function?"
+    exit 1
+fi
+
+print_success "Wrote $COUNT fixtures to $FIXTURE_DIR"
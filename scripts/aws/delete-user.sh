@@ -0,0 +1,89 @@
+#!/bin/bash
+
+# Delete User Script
+# Erases a user from the authentication system by invoking the delete-user Lambda
+# Usage: ./delete-user.sh <email|user_id> [environment] [region]
+# Examples:
+#   ./delete-user.sh user@example.com                          # test environment, eu-west-2
+#   ./delete-user.sh 12345678-1234-1234-1234-123456789abc prod  # by user ID in prod
+
+set -e
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+YELLOW='\033[1;33m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+print_info() {
+    echo -e "${BLUE}ℹ️  $1${NC}"
+}
+
+print_success() {
+    echo -e "${GREEN}✅ $1${NC}"
+}
+
+print_warning() {
+    echo -e "${YELLOW}⚠️  $1${NC}"
+}
+
+print_error() {
+    echo -e "${RED}❌ $1${NC}"
+}
+
+if [ $# -eq 0 ]; then
+    print_error "Usage: $0 <email|user_id> [environment] [region]"
+    print_info "Example: $0 user@example.com test"
+    exit 1
+fi
+
+TARGET="$1"
+ENVIRONMENT=${2:-${ENVIRONMENT:-test}}
+REGION=${3:-${AWS_REGION:-eu-west-2}}
+FUNCTION_NAME="${APP_NAME:-appre}-$ENVIRONMENT-delete-user"
+
+if ! command -v aws &> /dev/null; then
+    print_error "AWS CLI is not installed. Please install it first."
+    exit 1
+fi
+
+# Anything with an @ is treated as an email, everything else as a user ID
+if [[ "$TARGET" == *@* ]]; then
+    PAYLOAD="{\"email\":\"$TARGET\"}"
+else
+    PAYLOAD="{\"user_id\":\"$TARGET\"}"
+fi
+
+print_warning "This will permanently delete all data for: $TARGET ($ENVIRONMENT)"
+print_warning "  - User profile, OTP, rate limit and session records from DynamoDB"
+print_warning "  - User account from Cognito"
+read -p "Are you sure you want to continue? (y/N): " -n 1 -r
+echo
+if [[ ! $REPLY =~ ^[Yy]$ ]]; then
+    print_info "Operation cancelled"
+    exit 0
+fi
+
+RESPONSE_FILE=$(mktemp)
+trap 'rm -f "$RESPONSE_FILE"' EXIT
+
+print_info "Invoking $FUNCTION_NAME..."
+FUNCTION_ERROR=$(aws lambda invoke \
+    --function-name "$FUNCTION_NAME" \
+    --region "$REGION" \
+    --cli-binary-format raw-in-base64-out \
+    --payload "$PAYLOAD" \
+    --query 'FunctionError' \
+    --output text \
+    "$RESPONSE_FILE")
+
+if [ "$FUNCTION_ERROR" != "None" ]; then
+    print_error "Deletion failed: $(cat "$RESPONSE_FILE")"
+    print_info "The deletion is safe to re-run once the cause is fixed"
+    exit 1
+fi
+
+print_success "Deletion completed. Removed:"
+cat "$RESPONSE_FILE"
+echo
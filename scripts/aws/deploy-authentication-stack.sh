@@ -37,6 +37,13 @@ if [ -z "$REGION" ]; then
     exit 1
 fi
 
+# create- and verify-auth-challenge can't start without the OTP hashing key, so the stack
+# refuses to synthesize without one; catch it before building
+if [ -z "$OTP_HASH_KEY" ]; then
+    echo "❌ Error: OTP_HASH_KEY not set. Set it in .env as <version>:<secret>."
+    exit 1
+fi
+
 echo "🌍 Using region: $REGION"
 echo "🏷️  Using environment: $ENVIRONMENT"
 
@@ -0,0 +1,63 @@
+#!/bin/bash
+
+# Export User Data Script
+# Answers a subject access request by invoking the export-user-data Lambda
+# Usage: ./export-user-data.sh <email> [environment] [region] [output file]
+# Examples:
+#   ./export-user-data.sh user@example.com                      # test environment, eu-west-2
+#   ./export-user-data.sh user@example.com prod eu-west-2 out.json
+
+set -e
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+print_info() {
+    echo -e "${BLUE}ℹ️  $1${NC}"
+}
+
+print_success() {
+    echo -e "${GREEN}✅ $1${NC}"
+}
+
+print_error() {
+    echo -e "${RED}❌ $1${NC}"
+}
+
+if [ $# -eq 0 ]; then
+    print_error "Usage: $0 <email> [environment] [region] [output file]"
+    print_info "Example: $0 user@example.com test"
+    exit 1
+fi
+
+EMAIL="$1"
+ENVIRONMENT=${2:-${ENVIRONMENT:-test}}
+REGION=${3:-${AWS_REGION:-eu-west-2}}
+OUTPUT_FILE=${4:-"user-data-export-$(date +%Y%m%d%H%M%S).json"}
+FUNCTION_NAME="${APP_NAME:-appre}-$ENVIRONMENT-export-user-data"
+
+if ! command -v aws &> /dev/null; then
+    print_error "AWS CLI is not installed. Please install it first."
+    exit 1
+fi
+
+print_info "Invoking $FUNCTION_NAME for $EMAIL..."
+FUNCTION_ERROR=$(aws lambda invoke \
+    --function-name "$FUNCTION_NAME" \
+    --region "$REGION" \
+    --cli-binary-format raw-in-base64-out \
+    --payload "{\"email\":\"$EMAIL\"}" \
+    --query 'FunctionError' \
+    --output text \
+    "$OUTPUT_FILE")
+
+if [ "$FUNCTION_ERROR" != "None" ]; then
+    print_error "Export failed: $(cat "$OUTPUT_FILE")"
+    rm -f "$OUTPUT_FILE"
+    exit 1
+fi
+
+print_success "Export written to $OUTPUT_FILE"
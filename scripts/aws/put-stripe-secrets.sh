@@ -0,0 +1,76 @@
+#!/bin/bash
+
+# Stripe Secrets Script
+# Stores the Stripe secret key and webhook signing secret in the <app>-<env>-stripe Secrets Manager
+# secret, which the payments stack passes to its Lambdas without the values entering the template
+# Usage: ./put-stripe-secrets.sh
+# Reads STRIPE_SECRET_KEY and STRIPE_WEBHOOK_SECRET from the environment or the services .env file.
+# The environment and region come from ENVIRONMENT (default test) and AWS_REGION (default eu-west-2)
+# Examples:
+#   ./put-stripe-secrets.sh
+#   ENVIRONMENT=prod STRIPE_SECRET_KEY=sk_live_... STRIPE_WEBHOOK_SECRET=whsec_... ./put-stripe-secrets.sh
+
+set -e
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+print_info() {
+    echo -e "${BLUE}ℹ️  $1${NC}"
+}
+
+print_success() {
+    echo -e "${GREEN}✅ $1${NC}"
+}
+
+print_error() {
+    echo -e "${RED}❌ $1${NC}"
+}
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+if [ -f "$SCRIPT_DIR/../../.env" ]; then
+    set -a
+    source "$SCRIPT_DIR/../../.env"
+    set +a
+fi
+
+ENVIRONMENT=${ENVIRONMENT:-test}
+REGION=${AWS_REGION:-eu-west-2}
+SECRET_NAME="${APP_NAME:-appre}-$ENVIRONMENT-stripe"
+
+if ! command -v aws &> /dev/null; then
+    print_error "AWS CLI is not installed. Please install it first."
+    exit 1
+fi
+
+case "$STRIPE_SECRET_KEY" in
+    sk_*) ;;
+    *) print_error "STRIPE_SECRET_KEY must be set to a Stripe secret key (sk_test_... / sk_live_...)"; exit 1 ;;
+esac
+case "$STRIPE_WEBHOOK_SECRET" in
+    whsec_*) ;;
+    *) print_error "STRIPE_WEBHOOK_SECRET must be set to a webhook signing secret (whsec_...)"; exit 1 ;;
+esac
+
+SECRET_STRING="{\"secretKey\":\"$STRIPE_SECRET_KEY\",\"webhookSecret\":\"$STRIPE_WEBHOOK_SECRET\"}"
+
+if aws secretsmanager describe-secret --secret-id "$SECRET_NAME" --region "$REGION" &> /dev/null; then
+    print_info "Updating $SECRET_NAME"
+    aws secretsmanager put-secret-value \
+        --secret-id "$SECRET_NAME" \
+        --region "$REGION" \
+        --secret-string "$SECRET_STRING" > /dev/null
+else
+    print_info "Creating $SECRET_NAME"
+    aws secretsmanager create-secret \
+        --name "$SECRET_NAME" \
+        --region "$REGION" \
+        --description "Stripe credentials for the $ENVIRONMENT payments Lambdas" \
+        --tags "Key=Environment,Value=$ENVIRONMENT" \
+        --secret-string "$SECRET_STRING" > /dev/null
+fi
+
+print_success "Stored Stripe secrets in $SECRET_NAME; redeploy the payments stack to pick up a change"
@@ -0,0 +1,77 @@
+#!/bin/bash
+
+# Sign-up Policy Script
+# Manages the runtime allow/deny lists the pre-signup trigger adds to its bundled policy
+# Usage: ./signup-policy.sh list
+#        ./signup-policy.sh <add|remove> <allow|deny> <email or domain>
+# The environment and region come from ENVIRONMENT (default test) and AWS_REGION (default eu-west-2)
+# Examples:
+#   ./signup-policy.sh add deny disposable.example        # block a domain and its subdomains
+#   ./signup-policy.sh add allow beta.tester@example.com  # invite one address
+#   ENVIRONMENT=prod ./signup-policy.sh list
+
+set -e
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+print_info() {
+    echo -e "${BLUE}ℹ️  $1${NC}"
+}
+
+print_success() {
+    echo -e "${GREEN}✅ $1${NC}"
+}
+
+print_error() {
+    echo -e "${RED}❌ $1${NC}"
+}
+
+usage() {
+    print_error "Usage: $0 list | $0 <add|remove> <allow|deny> <email or domain>"
+    print_info "Example: $0 add deny disposable.example"
+    exit 1
+}
+
+ACTION="$1"
+LIST="$2"
+# Entries are matched case-insensitively, so store them lowercased
+ENTRY=$(echo "$3" | tr '[:upper:]' '[:lower:]')
+ENVIRONMENT=${ENVIRONMENT:-test}
+REGION=${AWS_REGION:-eu-west-2}
+TABLE_NAME="${APP_NAME:-appre}-$ENVIRONMENT-signup-policy"
+
+if ! command -v aws &> /dev/null; then
+    print_error "AWS CLI is not installed. Please install it first."
+    exit 1
+fi
+
+case "$ACTION" in
+    list)
+        print_info "Entries in $TABLE_NAME:"
+        aws dynamodb scan \
+            --table-name "$TABLE_NAME" \
+            --region "$REGION" \
+            --query 'Items[].[list.S, entry.S]' \
+            --output text
+        ;;
+    add|remove)
+        if [ "$LIST" != "allow" ] && [ "$LIST" != "deny" ] || [ -z "$ENTRY" ]; then
+            usage
+        fi
+        KEY="{\"list\":{\"S\":\"$LIST\"},\"entry\":{\"S\":\"$ENTRY\"}}"
+        if [ "$ACTION" = "add" ]; then
+            aws dynamodb put-item --table-name "$TABLE_NAME" --region "$REGION" --item "$KEY"
+            print_success "Added $ENTRY to the $LIST list in $ENVIRONMENT"
+        else
+            aws dynamodb delete-item --table-name "$TABLE_NAME" --region "$REGION" --key "$KEY"
+            print_success "Removed $ENTRY from the $LIST list in $ENVIRONMENT"
+        fi
+        ;;
+    *)
+        usage
+        ;;
+esac
@@ -44,7 +44,7 @@ rm -rf target/x86_64-unknown-linux-gnu/
 echo "🔨 Building Lambda functions..."
 
 # Build each function for AWS Lambda AL2023 runtime
-functions=("create-auth-challenge" "verify-auth-challenge" "define-auth-challenge" "pre-signup")
+functions=("create-auth-challenge" "verify-auth-challenge" "define-auth-challenge" "pre-signup" "pre-token-generation" "logout" "admin-review" "complete-profile" "delete-user" "export-user-data")
 
 for func in "${functions[@]}"; do
     echo "Building $func for AWS Lambda AL2023..."
@@ -83,6 +83,7 @@ echo "   $AUTH_DIR/target/lambda/create-auth-challenge/"
 echo "   $AUTH_DIR/target/lambda/verify-auth-challenge/"
 echo "   $AUTH_DIR/target/lambda/define-auth-challenge/"
 echo "   $AUTH_DIR/target/lambda/pre-signup/"
+echo "   $AUTH_DIR/target/lambda/pre-token-generation/"
 echo ""
 echo "📋 Next steps:"
 echo "1. Deploy the CDK infrastructure: $SCRIPT_DIR/../aws/deploy-authentication-stack.sh"
@@ -0,0 +1,68 @@
+#!/bin/bash
+
+# Build script for payments Lambda functions
+set -e
+
+echo "💳 Building payments Lambda functions..."
+
+# Get the script directory and navigate to payments workspace
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PAYMENTS_DIR="$SCRIPT_DIR/../../payments"
+
+echo "📁 Building from payments workspace: $PAYMENTS_DIR"
+
+# Change to the payments workspace directory
+cd "$PAYMENTS_DIR"
+
+# Load environment variables from .env file
+if [ -f "../.env" ]; then
+    echo "📄 Loading environment variables from ../.env"
+    export $(grep -v '^#' ../.env | xargs)
+else
+    echo "⚠️  Warning: ../.env file not found, using default values"
+    export APP_NAME=${APP_NAME:-appre}
+fi
+
+echo "🏷️  Building with APP_NAME: $APP_NAME"
+
+# Check if cargo-lambda is installed
+if ! command -v cargo-lambda &> /dev/null; then
+    echo "❌ cargo-lambda is not installed. Installing..."
+    cargo install cargo-lambda
+fi
+
+# Clean previous builds
+echo "🧹 Cleaning previous builds..."
+cargo clean
+
+# Also explicitly clean Lambda build artifacts
+echo "🧹 Cleaning Lambda build artifacts..."
+rm -rf target/lambda/
+rm -rf target/x86_64-unknown-linux-gnu/
+
+# Build each payments Lambda function
+functions=("stripe-onboarding" "stripe-webhook")
+
+for func in "${functions[@]}"; do
+    echo "🔨 Building $func Lambda..."
+    APP_NAME="$APP_NAME" cargo lambda build --release --package $func
+
+    # Check if build was successful
+    if [ $? -eq 0 ]; then
+        echo "✅ $func built successfully"
+    else
+        echo "❌ Failed to build $func"
+        exit 1
+    fi
+done
+
+echo ""
+echo "🎉 Payments Lambda functions built successfully!"
+echo ""
+echo "📁 Built artifacts are located in:"
+echo "   $PAYMENTS_DIR/target/lambda/stripe-onboarding/"
+echo "   $PAYMENTS_DIR/target/lambda/stripe-webhook/"
+echo ""
+echo "📋 Next steps:"
+echo "1. Deploy the CDK infrastructure for payments"
+echo "2. Register the StripeWebhookUrl output as a Connect webhook endpoint in Stripe"
\ No newline at end of file